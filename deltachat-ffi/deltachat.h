@@ -331,10 +331,20 @@ char*           dc_get_blobdir               (const dc_context_t* context);
  * - `e2ee_enabled` = 0=no end-to-end-encryption, 1=prefer end-to-end-encryption (default)
  * - `mdns_enabled` = 0=do not send or request read receipts,
  *                    1=send and request read receipts (default)
+ * - `request_receipts` = 0=do not request read receipts for outgoing messages,
+ *                    1=request read receipts,
+ *                    if unset, `mdns_enabled` is used (default)
+ * - `send_receipts` = 0=do not send read receipts,
+ *                    1=send read receipts if requested by the sender,
+ *                    if unset, `mdns_enabled` is used (default)
  * - `bcc_self`     = 0=do not send a copy of outgoing messages to self (default),
  *                    1=send a copy of outgoing messages to self.
  *                    Sending messages to self is needed for a proper multi-account setup,
  *                    however, on the other hand, may lead to unwanted notifications in non-delta clients.
+ * - `bcc_self_folder` = name of an existing folder the copies of sent chat messages are moved to,
+ *                    e.g. `Delta Chat Sent`.
+ *                    If unset (default), the copies are handled as other chat messages, see `mvbox_move`.
+ *                    The folder is not watched, the copies are downloaded before they are moved.
  * - `sentbox_watch`= 1=watch `Sent`-folder for changes,
  *                    0=do not watch the `Sent`-folder (default),
  *                    changes require restarting IO by calling dc_stop_io() and then dc_start_io().
@@ -355,6 +365,12 @@ char*           dc_get_blobdir               (const dc_context_t* context);
  *                    also show all mails of confirmed contacts,
  *                    DC_SHOW_EMAILS_ALL (2)=
  *                    also show mails of unconfirmed contacts.
+ * - `auto_accept_group_invites` = DC_AUTO_ACCEPT_GROUP_INVITES_OFF (0)=
+ *                    groups from unaccepted contacts are contact requests (default),
+ *                    DC_AUTO_ACCEPT_GROUP_INVITES_VERIFIED_ONLY (1)=
+ *                    accept groups created by verified contacts,
+ *                    DC_AUTO_ACCEPT_GROUP_INVITES_ALL (2)=
+ *                    accept all groups.
  * - `key_gen_type` = DC_KEY_GEN_DEFAULT (0)=
  *                    generate recommended key type (default),
  *                    DC_KEY_GEN_RSA2048 (1)=
@@ -403,6 +419,16 @@ char*           dc_get_blobdir               (const dc_context_t* context);
  *                    to not mess up with non-delivery-reports or read-receipts.
  *                    0=no limit (default).
  *                    Changes affect future messages only.
+ * - `max_attachment_size_outgoing` = Attachments larger than this number of bytes
+ *                    are rejected by dc_send_msg() and dc_prepare_msg().
+ *                    0=no limit (default).
+ * - `language`     = Language of the account as BCP 47 tag, e.g. `en` or `pt-BR`.
+ *                    The core does not load translations,
+ *                    the tag just records the language set by dc_set_stock_translation().
+ * - `qr_token_ttl` = Lifetime of the tokens used in QR codes returned by dc_get_securejoin_qr(), in seconds.
+ *                    Expired tokens are replaced when the QR code is generated the next time,
+ *                    scanning QR codes with expired tokens fails.
+ *                    0=tokens never expire (default).
  * - `ui.*`         = All keys prefixed by `ui.` can be used by the user-interfaces for system-specific purposes.
  *                    The prefix should be followed by the system and maybe subsystem,
  *                    e.g. `ui.desktop.foo`, `ui.desktop.linux.bar`, `ui.android.foo`, `ui.dc40.bar`, `ui.bot.simplebot.baz`.
@@ -1155,6 +1181,7 @@ dc_msg_t*       dc_get_draft                 (dc_context_t* context, uint32_t ch
 
 #define         DC_GCM_ADDDAYMARKER          0x01
 #define         DC_GCM_INFO_ONLY             0x02
+#define         DC_GCM_NO_INFO               0x04
 
 
 /**
@@ -1174,6 +1201,7 @@ dc_msg_t*       dc_get_draft                 (dc_context_t* context, uint32_t ch
  *     be added before each day (regarding the local timezone). Set this to 0 if you do not want this behaviour.
  *     To get the concrete time of the marker, use dc_array_get_timestamp().
  *     If set to DC_GCM_INFO_ONLY, only system messages will be returned, can be combined with DC_GCM_ADDDAYMARKER.
+ *     If set to DC_GCM_NO_INFO, system messages will be excluded, can be combined with DC_GCM_ADDDAYMARKER.
  * @param marker1before Deprecated, set this to 0.
  * @return Array of message IDs, must be dc_array_unref()'d when no longer used.
  */
@@ -2218,6 +2246,8 @@ char*           dc_initiate_key_transfer     (dc_context_t* context);
  *     dc_initiate_key_transfer() on the other device.
  *     There is no need to format the string correctly, the function will remove all spaces and other characters and
  *     insert the `-` characters at the correct places.
+ *     While the key is imported, #DC_EVENT_IMEX_PROGRESS events are emitted,
+ *     ending with 1000 on success and 0 on errors.
  * @return 1=key successfully decrypted and imported; both devices will use the same key now;
  *     0=key transfer failed e.g. due to a bad setup code.
  */
@@ -4203,6 +4233,8 @@ int dc_msg_has_html (dc_msg_t* msg);
   *                                  If the download fails or succeeds,
   *                                  the event @ref DC_EVENT_MSGS_CHANGED is emitted.
   * - @ref DC_DOWNLOAD_FAILURE     - Download error, the user may start over calling dc_download_full_msg() again.
+  * - @ref DC_DOWNLOAD_UNDECIPHERABLE - The message is downloaded completely but cannot be decrypted,
+  *                                  downloading it again does not help.
   *
   * @memberof dc_msg_t
   * @param msg The message object.
@@ -5581,6 +5613,16 @@ void dc_event_unref(dc_event_t* event);
 #define DC_EVENT_IMEX_PROGRESS            2051
 
 
+/**
+ * Inform about the amount of data written to a backup exported by dc_imex().
+ * Emitted alongside #DC_EVENT_IMEX_PROGRESS.
+ *
+ * @param data1 (int) Number of KiB written so far.
+ * @param data2 (int) Total number of KiB to write.
+ */
+#define DC_EVENT_IMEX_BYTES_PROGRESS      2053
+
+
 /**
  * A file has been exported. A file has been written by dc_imex().
  * This event may be sent multiple times by a single call to dc_imex().
@@ -5594,6 +5636,15 @@ void dc_event_unref(dc_event_t* event);
 #define DC_EVENT_IMEX_FILE_WRITTEN        2052
 
 
+/**
+ * Inform about the progress of rebuilding the search index.
+ *
+ * @param data1 (int) 1-999=progress in permille, 1000=success and done
+ * @param data2 0
+ */
+#define DC_EVENT_REINDEX_PROGRESS         2055
+
+
 /**
  * Progress information of a secure-join handshake from the view of the inviter
  * (Alice, the person who shows the QR code).
@@ -5620,12 +5671,27 @@ void dc_event_unref(dc_event_t* event);
  *
  * @param data1 (int) The ID of the inviting contact.
  * @param data2 (int) The progress as:
+ *     0=error, the handshake was aborted.
  *     400=vg-/vc-request-with-auth sent, typically shown as "alice@addr verified, introducing myself."
  *     (Bob has verified alice and waits until Alice does the same for him)
+ *     1000=vc-contact-confirm or vg-member-added received, the protocol is finished.
  */
 #define DC_EVENT_SECUREJOIN_JOINER_PROGRESS       2061
 
 
+/**
+ * A Secure-Join handshake message failed verification
+ * and an info message was added to the chat with the contact.
+ *
+ * UIs may use this event to show a specific error
+ * instead of relying on the user to notice the info message.
+ *
+ * @param data1 (int) ID of the contact the handshake is done with.
+ * @param data2 (int) The reason, one of the DC_SECUREJOIN_FAILURE_* constants.
+ */
+#define DC_EVENT_SECUREJOIN_FAILED                2062
+
+
 /**
  * The connectivity to the server changed.
  * This means that you should refresh the connectivity view
@@ -5645,6 +5711,18 @@ void dc_event_unref(dc_event_t* event);
 #define DC_EVENT_SELFAVATAR_CHANGED               2110
 
 
+/**
+ * A config value changed,
+ * either by dc_set_config() or by a synchronization message from another device.
+ * UIs showing settings may want to reload the value with dc_get_config().
+ *
+ * @param data1 0
+ * @param data2 (char*) The name of the changed key, eg. "displayname".
+ *     The value itself is never passed.
+ */
+#define DC_EVENT_CONFIG_CHANGED                   2111
+
+
 /**
  * webxdc status update received.
  * To get the received status update, use dc_get_webxdc_status_updates() with
@@ -5668,7 +5746,7 @@ void dc_event_unref(dc_event_t* event);
 
 
 #define DC_EVENT_DATA1_IS_STRING(e)  0    // not used anymore 
-#define DC_EVENT_DATA2_IS_STRING(e)  ((e)==DC_EVENT_CONFIGURE_PROGRESS || (e)==DC_EVENT_IMEX_FILE_WRITTEN || ((e)>=100 && (e)<=499))
+#define DC_EVENT_DATA2_IS_STRING(e)  ((e)==DC_EVENT_CONFIGURE_PROGRESS || (e)==DC_EVENT_IMEX_FILE_WRITTEN || (e)==DC_EVENT_CONFIG_CHANGED || ((e)>=100 && (e)<=499))
 
 
 /*
@@ -5679,6 +5757,25 @@ void dc_event_unref(dc_event_t* event);
 #define DC_SHOW_EMAILS_ALL               2
 
 
+/*
+ * Values for dc_get|set_config("auto_accept_group_invites")
+ */
+#define DC_AUTO_ACCEPT_GROUP_INVITES_OFF           0
+#define DC_AUTO_ACCEPT_GROUP_INVITES_VERIFIED_ONLY 1
+#define DC_AUTO_ACCEPT_GROUP_INVITES_ALL           2
+
+
+/*
+ * Values for data2 of #DC_EVENT_SECUREJOIN_FAILED
+ */
+#define DC_SECUREJOIN_FAILURE_FINGERPRINT_NOT_PROVIDED 1
+#define DC_SECUREJOIN_FAILURE_NOT_ENCRYPTED            2
+#define DC_SECUREJOIN_FAILURE_FINGERPRINT_MISMATCH     3
+#define DC_SECUREJOIN_FAILURE_AUTH_NOT_PROVIDED        4
+#define DC_SECUREJOIN_FAILURE_AUTH_INVALID             5
+#define DC_SECUREJOIN_FAILURE_TOKEN_EXPIRED            6
+
+
 /*
  * Values for dc_get|set_config("media_quality")
  */
@@ -5817,6 +5914,11 @@ void dc_event_unref(dc_event_t* event);
  */
 #define DC_DOWNLOAD_FAILURE      20
 
+/**
+ * Message cannot be decrypted, see dc_msg_get_download_state() for details.
+ */
+#define DC_DOWNLOAD_UNDECIPHERABLE 30
+
 /**
  * Download in progress, see dc_msg_get_download_state() for details.
  */
@@ -6359,6 +6461,47 @@ void dc_event_unref(dc_event_t* event);
 /// Used as status in the connectivity view.
 #define DC_STR_NOT_CONNECTED              121
 
+/// "New message"
+///
+/// Used as notification text if the notification preview is disabled.
+#define DC_STR_NEW_MESSAGE                122
+
+/// "Message from %1$s"
+///
+/// Used as notification text if only the sender is shown in notifications.
+///
+/// `%1$s` will be replaced by the name of the sender.
+#define DC_STR_MESSAGE_FROM               123
+
+/// "Location streaming extended until %1$s."
+///
+/// Added as info message if location streaming is enabled for a chat that is already streaming.
+///
+/// `%1$s` will be replaced by human-readable date and time.
+#define DC_STR_MSG_LOCATION_EXTENDED      124
+
+/// "Members %1$s added."
+///
+/// Used in status messages if several members are added at once.
+///
+/// `%1$s` will be replaced by the list of added members, see #DC_STR_LIST_AND and #DC_STR_LIST_OTHERS.
+#define DC_STR_MSG_ADD_MEMBERS            125
+
+/// "%1$s and %2$s"
+///
+/// Used to join the last two items of a list of names.
+///
+/// `%1$s` will be replaced by the comma-separated list of the other names,
+/// `%2$s` will be replaced by the last name.
+#define DC_STR_LIST_AND                   126
+
+/// "%1$s others"
+///
+/// Used at the end of lists of names that are too long to be shown completely.
+///
+/// `%1$s` will be replaced by the number of names not shown, always at least 2.
+#define DC_STR_LIST_OTHERS                127
+
 /**
  * @}
  */
@@ -480,11 +480,15 @@ fn render_info(
         EventType::LocationChanged(_) => 2035,
         EventType::ConfigureProgress { .. } => 2041,
         EventType::ImexProgress(_) => 2051,
+        EventType::ImexBytesProgress { .. } => 2053,
         EventType::ImexFileWritten(_) => 2052,
+        EventType::ReindexProgress(_) => 2055,
         EventType::SecurejoinInviterProgress { .. } => 2060,
         EventType::SecurejoinJoinerProgress { .. } => 2061,
+        EventType::SecurejoinFailed { .. } => 2062,
         EventType::ConnectivityChanged => 2100,
         EventType::SelfavatarChanged => 2110,
+        EventType::ConfigChanged { .. } => 2111,
         EventType::WebxdcStatusUpdate { .. } => 2120,
     }
 }
@@ -510,6 +514,7 @@ fn render_info(
         | EventType::Error(_)
         | EventType::ConnectivityChanged
         | EventType::SelfavatarChanged
+        | EventType::ConfigChanged { .. }
         | EventType::ErrorSelfNotInGroup(_) => 0,
         EventType::MsgsChanged { chat_id, .. }
         | EventType::IncomingMsg { chat_id, .. }
@@ -523,14 +528,14 @@ fn render_info(
             let id = id.unwrap_or_default();
             id.to_u32() as libc::c_int
         }
-        EventType::ConfigureProgress { progress, .. } | EventType::ImexProgress(progress) => {
-            *progress as libc::c_int
-        }
+        EventType::ConfigureProgress { progress, .. }
+        | EventType::ImexProgress(progress)
+        | EventType::ReindexProgress(progress) => *progress as libc::c_int,
         EventType::ImexFileWritten(_) => 0,
+        EventType::ImexBytesProgress { done, .. } => (*done / 1024) as libc::c_int,
         EventType::SecurejoinInviterProgress { contact_id, .. }
-        | EventType::SecurejoinJoinerProgress { contact_id, .. } => {
-            contact_id.to_u32() as libc::c_int
-        }
+        | EventType::SecurejoinJoinerProgress { contact_id, .. }
+        | EventType::SecurejoinFailed { contact_id, .. } => contact_id.to_u32() as libc::c_int,
         EventType::WebxdcStatusUpdate { msg_id, .. } => msg_id.to_u32() as libc::c_int,
     }
 }
@@ -561,9 +566,11 @@ fn render_info(
         | EventType::ConfigureProgress { .. }
         | EventType::ImexProgress(_)
         | EventType::ImexFileWritten(_)
+        | EventType::ReindexProgress(_)
         | EventType::MsgsNoticed(_)
         | EventType::ConnectivityChanged
-        | EventType::SelfavatarChanged => 0,
+        | EventType::SelfavatarChanged
+        | EventType::ConfigChanged { .. } => 0,
         EventType::ChatModified(_) => 0,
         EventType::MsgsChanged { msg_id, .. }
         | EventType::IncomingMsg { msg_id, .. }
@@ -572,7 +579,9 @@ fn render_info(
         | EventType::MsgRead { msg_id, .. } => msg_id.to_u32() as libc::c_int,
         EventType::SecurejoinInviterProgress { progress, .. }
         | EventType::SecurejoinJoinerProgress { progress, .. } => *progress as libc::c_int,
+        EventType::SecurejoinFailed { reason, .. } => *reason as u32 as libc::c_int,
         EventType::ChatEphemeralTimerModified { timer, .. } => timer.to_u32() as libc::c_int,
+        EventType::ImexBytesProgress { total, .. } => (*total / 1024) as libc::c_int,
         EventType::WebxdcStatusUpdate {
             status_update_serial,
             ..
@@ -614,8 +623,11 @@ fn render_info(
         | EventType::ContactsChanged(_)
         | EventType::LocationChanged(_)
         | EventType::ImexProgress(_)
+        | EventType::ImexBytesProgress { .. }
+        | EventType::ReindexProgress(_)
         | EventType::SecurejoinInviterProgress { .. }
         | EventType::SecurejoinJoinerProgress { .. }
+        | EventType::SecurejoinFailed { .. }
         | EventType::ConnectivityChanged
         | EventType::SelfavatarChanged
         | EventType::WebxdcStatusUpdate { .. }
@@ -631,6 +643,7 @@ fn render_info(
             let data2 = file.to_c_string().unwrap_or_default();
             data2.into_raw()
         }
+        EventType::ConfigChanged { key } => key.to_c_string().unwrap_or_default().into_raw(),
     }
 }
 
@@ -1464,7 +1477,7 @@ fn from_prim<S, T>(s: S) -> Option<T>
         return 0;
     }
     let ctx = &*context;
-    block_on(chat::create_broadcast_list(ctx))
+    block_on(chat::create_broadcast_list(ctx, ""))
         .log_err(ctx, "Failed to create broadcast list")
         .map(|id| id.to_u32())
         .unwrap_or(0)
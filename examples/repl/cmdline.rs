@@ -189,6 +189,7 @@ async fn log_msg(context: &Context, prefix: impl AsRef<str>, msg: &Message) {
         DownloadState::Available => " [⬇ Download available]",
         DownloadState::InProgress => " [⬇ Download in progress...]️",
         DownloadState::Failure => " [⬇ Download failed]",
+        DownloadState::Undecipherable => " [⚠ Cannot decrypt]",
     };
 
     let temp2 = dc_timestamp_to_str(msg.get_timestamp());
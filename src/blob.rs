@@ -12,6 +12,7 @@
 use anyhow::{format_err, Context as _, Error};
 use image::{DynamicImage, ImageFormat};
 use num_traits::FromPrimitive;
+use sha2::{Digest, Sha256};
 use thiserror::Error;
 
 use crate::config::Config;
@@ -83,6 +84,59 @@ pub async fn create(
         Ok(blob)
     }
 
+    /// Creates a blob object, reusing an existing blob with the same name and content.
+    ///
+    /// Unlike [BlobObject::create], which always writes a new file,
+    /// the name is derived from the suggested name and a hash of `data`.
+    /// If a file with this name and the same content already exists in the blob directory,
+    /// it is returned instead of writing the data again,
+    /// so that several messages may refer to the same file.
+    /// Blobs are only removed by housekeeping once no message refers to them anymore,
+    /// however, changing the file in place affects all messages referring to it.
+    ///
+    /// # Errors
+    ///
+    /// See [BlobObject::create].
+    pub async fn create_deduplicated(
+        context: &'a Context,
+        suggested_name: &str,
+        data: &[u8],
+    ) -> std::result::Result<BlobObject<'a>, BlobError> {
+        let blobdir = context.get_blobdir();
+        let (stem, ext) = BlobObject::sanitise_name(suggested_name);
+        let hash = Sha256::digest(data);
+        let stem = format!("{}-{}", stem, hex::encode(&hash[..8]));
+
+        let name = format!("{}{}", stem, ext);
+        if let Ok(existing) = fs::read(blobdir.join(&name)).await {
+            if existing == data {
+                return Ok(BlobObject {
+                    blobdir,
+                    name: format!("$BLOBDIR/{}", name),
+                });
+            }
+        }
+
+        let (name, mut file) = BlobObject::create_new_file(context, blobdir, &stem, &ext).await?;
+        file.write_all(data)
+            .await
+            .map_err(|err| BlobError::WriteFailure {
+                blobdir: blobdir.to_path_buf(),
+                blobname: name.clone(),
+                cause: err.into(),
+            })?;
+
+        // workaround, see create() for details
+        let _ = file.flush().await;
+
+        let blob = BlobObject {
+            blobdir,
+            name: format!("$BLOBDIR/{}", name),
+        };
+        context.emit_event(EventType::NewBlobFile(blob.as_name().to_string()));
+        Ok(blob)
+    }
+
     // Creates a new file, returning a tuple of the name and the handle.
     async fn create_new_file(
         context: &Context,
@@ -1,5 +1,6 @@
 //! # Chat module.
 
+use std::cmp::min;
 use std::collections::HashMap;
 use std::convert::{TryFrom, TryInto};
 use std::str::FromStr;
@@ -16,21 +17,24 @@
 use crate::config::Config;
 use crate::constants::{
     Blocked, Chattype, DC_CHAT_ID_ALLDONE_HINT, DC_CHAT_ID_ARCHIVED_LINK, DC_CHAT_ID_LAST_SPECIAL,
-    DC_CHAT_ID_TRASH, DC_GCM_ADDDAYMARKER, DC_GCM_INFO_ONLY, DC_RESEND_USER_AVATAR_DAYS,
+    DC_CHAT_ID_TRASH, DC_GCM_ADDDAYMARKER, DC_GCM_INFO_ONLY, DC_GCM_NO_INFO,
+    DC_RESEND_USER_AVATAR_DAYS,
 };
-use crate::contact::{Contact, ContactId, Origin, VerifiedStatus};
+use crate::contact::{addr_cmp, Contact, ContactId, Origin, VerifiedStatus};
 use crate::context::Context;
-use crate::dc_receive_imf::ReceivedMsg;
+use crate::dc_receive_imf::{ReceivedMsg, FUTURE_TIMESTAMP_TOLERANCE};
 use crate::dc_tools::{
     dc_create_id, dc_create_outgoing_rfc724_mid, dc_create_smeared_timestamp,
-    dc_create_smeared_timestamps, dc_get_abs_path, dc_gm2local_offset, improve_single_line_input,
-    time, IsNoneOrEmpty,
+    dc_create_smeared_timestamps, dc_get_abs_path, dc_get_filebytes, dc_gm2local_offset,
+    improve_single_line_input, time, IsNoneOrEmpty,
 };
+use crate::e2ee::EncryptHelper;
 use crate::ephemeral::Timer as EphemeralTimer;
 use crate::events::EventType;
 use crate::html::new_html_mimepart;
+use crate::mention;
 use crate::message::{self, Message, MessageState, MsgId, Viewtype};
-use crate::mimefactory::MimeFactory;
+use crate::mimefactory::{MimeFactory, RenderedEmail};
 use crate::mimeparser::SystemMessage;
 use crate::param::{Param, Params};
 use crate::peerstate::{Peerstate, PeerstateVerifiedStatus};
@@ -438,7 +442,7 @@ pub(crate) async fn add_protection_msg(
                 dc_create_smeared_timestamp(context).await,
                 None,
                 None,
-                None,
+                Some(from_id),
             )
             .await?;
         }
@@ -446,6 +450,30 @@ pub(crate) async fn add_protection_msg(
         Ok(())
     }
 
+    /// Returns the protection changes of the chat as `(status, actor, timestamp)` tuples,
+    /// oldest first.
+    ///
+    /// The history is built from the protection messages in the chat,
+    /// see [`get_chat_audit_log`].
+    pub async fn get_protection_history(
+        self,
+        context: &Context,
+    ) -> Result<Vec<(ProtectionStatus, ContactId, i64)>> {
+        let history = get_chat_audit_log(context, self)
+            .await?
+            .into_iter()
+            .filter_map(|entry| {
+                let status = match entry.action {
+                    AuditAction::ProtectionEnabled => ProtectionStatus::Protected,
+                    AuditAction::ProtectionDisabled => ProtectionStatus::Unprotected,
+                    AuditAction::MemberAdded | AuditAction::MemberRemoved => return None,
+                };
+                Some((status, entry.actor, entry.timestamp))
+            })
+            .collect();
+        Ok(history)
+    }
+
     /// Sets protection and sends or adds a message.
     pub async fn set_protection(self, context: &Context, protect: ProtectionStatus) -> Result<()> {
         ensure!(!self.is_special(), "set protection: invalid chat-id.");
@@ -491,6 +519,18 @@ pub async fn set_visibility(self, context: &Context, visibility: ChatVisibility)
         Ok(())
     }
 
+    /// Archives the chat and marks all its messages as noticed.
+    ///
+    /// This is [`ChatId::set_visibility`] with [`ChatVisibility::Archived`],
+    /// which already marks fresh messages as noticed and refreshes the chatlist;
+    /// additionally, [`EventType::MsgsNoticed`] is emitted so that UIs can remove notifications.
+    pub async fn archive_and_mark_noticed(self, context: &Context) -> Result<()> {
+        self.set_visibility(context, ChatVisibility::Archived)
+            .await?;
+        context.emit_event(EventType::MsgsNoticed(self));
+        Ok(())
+    }
+
     // Unarchives a chat that is archived and not muted.
     // Needed when a message is added to a chat so that the chat gets a normal visibility again.
     // Sending an appropriate event is up to the caller.
@@ -609,6 +649,23 @@ pub async fn get_draft(self, context: &Context) -> Result<Option<Message>> {
         }
     }
 
+    /// Returns the timestamp of the last draft edit, if the chat has a draft.
+    ///
+    /// The draft is sorted into the chatlist like a message with this timestamp.
+    pub async fn get_draft_timestamp(self, context: &Context) -> Result<Option<i64>> {
+        if self.is_special() {
+            return Ok(None);
+        }
+        let timestamp: Option<i64> = context
+            .sql
+            .query_get_value(
+                "SELECT timestamp FROM msgs WHERE chat_id=? AND state=?;",
+                paramsv![self, MessageState::OutDraft],
+            )
+            .await?;
+        Ok(timestamp)
+    }
+
     /// Delete draft message in specified chat, if there is one.
     ///
     /// Returns `true`, if message was deleted, `false` otherwise.
@@ -716,6 +773,25 @@ async fn do_set_draft(self, context: &Context, msg: &mut Message) -> Result<bool
         Ok(true)
     }
 
+    /// Returns the time until which the chat is muted.
+    ///
+    /// Returns `None` if the chat is not muted or the mute has expired
+    /// and [`MUTED_FOREVER_TIMESTAMP`] if the chat is muted forever.
+    pub async fn is_muted_until(self, context: &Context) -> Result<Option<SystemTime>> {
+        let mute_duration: Option<MuteDuration> = context
+            .sql
+            .query_get_value("SELECT muted_until FROM chats WHERE id=?;", paramsv![self])
+            .await?;
+        let muted_until = match mute_duration.unwrap_or(MuteDuration::NotMuted) {
+            MuteDuration::NotMuted => None,
+            MuteDuration::Forever => {
+                Some(SystemTime::UNIX_EPOCH + Duration::from_secs(MUTED_FOREVER_TIMESTAMP))
+            }
+            MuteDuration::Until(when) => Some(when).filter(|when| *when > SystemTime::now()),
+        };
+        Ok(muted_until)
+    }
+
     /// Returns number of messages in a chat.
     pub async fn get_msg_cnt(self, context: &Context) -> Result<usize> {
         let count = context
@@ -753,6 +829,98 @@ pub async fn get_fresh_msg_cnt(self, context: &Context) -> Result<usize> {
         Ok(count as usize)
     }
 
+    /// Returns the oldest fresh message of the chat, or `None` if there are no fresh messages.
+    ///
+    /// This can be used to scroll to the first unread message when opening the chat.
+    pub async fn get_first_unread_msg_id(self, context: &Context) -> Result<Option<MsgId>> {
+        context
+            .sql
+            .query_get_value(
+                "SELECT id
+                FROM msgs
+                WHERE state=?
+                AND hidden=0
+                AND chat_id=?
+                ORDER BY timestamp, id
+                LIMIT 1;",
+                paramsv![MessageState::InFresh, self],
+            )
+            .await
+    }
+
+    /// Returns the newest visible message of the chat, or `None` if the chat is empty.
+    ///
+    /// Hidden messages and drafts are not considered.
+    pub async fn get_last_msg_id(self, context: &Context) -> Result<Option<MsgId>> {
+        context
+            .sql
+            .query_get_value(
+                "SELECT id
+                FROM msgs
+                WHERE hidden=0
+                AND chat_id=?
+                ORDER BY timestamp DESC, id DESC
+                LIMIT 1;",
+                paramsv![self],
+            )
+            .await
+    }
+
+    /// Deletes all messages of the chat sent or received before `cutoff`.
+    ///
+    /// Drafts are kept. As for [`message::delete_msgs`], the messages are also deleted
+    /// from the server and unused blobs are removed on the next housekeeping.
+    ///
+    /// Returns the number of deleted messages.
+    pub async fn delete_messages_older_than(self, context: &Context, cutoff: i64) -> Result<usize> {
+        ensure!(
+            !self.is_special(),
+            "bad chat_id, can not be a special chat: {}",
+            self
+        );
+
+        let deleted = context
+            .sql
+            .transaction(move |transaction| {
+                transaction.execute(
+                    "UPDATE imap SET target=''
+                     WHERE rfc724_mid IN (SELECT rfc724_mid FROM msgs
+                                          WHERE chat_id=? AND timestamp<? AND state!=?)",
+                    paramsv![self, cutoff, MessageState::OutDraft],
+                )?;
+                transaction.execute(
+                    "DELETE FROM locations
+                     WHERE independent=1
+                     AND id IN (SELECT location_id FROM msgs
+                                WHERE chat_id=? AND timestamp<? AND state!=?)",
+                    paramsv![self, cutoff, MessageState::OutDraft],
+                )?;
+                // If you change which information is removed here, also change MsgId::trash().
+                let deleted = transaction.execute(
+                    "UPDATE msgs
+                     SET chat_id=?, txt='', subject='', txt_raw='',
+                         mime_headers='', from_id=0, to_id=0, param=''
+                     WHERE chat_id=? AND timestamp<? AND state!=?",
+                    paramsv![DC_CHAT_ID_TRASH, self, cutoff, MessageState::OutDraft],
+                )?;
+                transaction.execute(
+                    "DELETE FROM mentions WHERE msg_id IN (SELECT id FROM msgs WHERE chat_id=?)",
+                    paramsv![DC_CHAT_ID_TRASH],
+                )?;
+                Ok(deleted)
+            })
+            .await?;
+
+        if deleted > 0 {
+            context.emit_msgs_changed_without_ids();
+
+            // Run housekeeping to delete unused blobs.
+            context.set_config(Config::LastHousekeeping, None).await?;
+            context.interrupt_inbox(InterruptInfo::new(false)).await;
+        }
+        Ok(deleted)
+    }
+
     pub(crate) async fn get_param(self, context: &Context) -> Result<Params> {
         let res: Option<String> = context
             .sql
@@ -991,6 +1159,43 @@ fn column_result(value: rusqlite::types::ValueRef) -> rusqlite::types::FromSqlRe
     }
 }
 
+/// Reason why messages cannot be sent to a chat, see [`Chat::why_cant_send`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CantSendReason {
+    /// Special chats such as the trash chat.
+    SpecialChat,
+
+    /// The device chat only contains messages added by the core.
+    DeviceChat,
+
+    /// The chat is a contact request which has to be accepted first.
+    ContactRequest,
+
+    /// The mailing list does not accept replies.
+    ReadOnlyMailingList,
+
+    /// The user is not a member of the group anymore.
+    NotAMember,
+}
+
+/// End-to-end encryption state of a chat, see [`Chat::get_encryption_status`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChatEncryption {
+    /// Messages are sent unencrypted,
+    /// e.g. because keys of some members are missing or they do not prefer encryption.
+    None,
+
+    /// Messages are encrypted because the members prefer encryption,
+    /// but this may change when a member resets the preference.
+    Opportunistic,
+
+    /// Verified keys of all members are known, but the chat is not protected.
+    Guaranteed,
+
+    /// The chat is protected, messages are only sent encrypted to verified keys.
+    Verified,
+}
+
 /// An object representing a single chat in memory.
 /// Chat objects are created using eg. `Chat::load_from_db`
 /// and are not updated on database changes;
@@ -1085,12 +1290,81 @@ pub fn is_mailing_list(&self) -> bool {
 
     /// Returns true if user can send messages to this chat.
     pub async fn can_send(&self, context: &Context) -> Result<bool> {
-        let cannot_send = self.id.is_special()
-            || self.is_device_talk()
-            || self.is_contact_request()
-            || (self.is_mailing_list() && self.param.get(Param::ListPost).is_none_or_empty())
-            || !self.is_self_in_chat(context).await?;
-        Ok(!cannot_send)
+        Ok(self.why_cant_send(context).await?.is_none())
+    }
+
+    /// Returns the reason why messages cannot be sent to this chat,
+    /// or `None` if sending is possible.
+    pub async fn why_cant_send(&self, context: &Context) -> Result<Option<CantSendReason>> {
+        let reason = if self.id.is_special() {
+            Some(CantSendReason::SpecialChat)
+        } else if self.is_device_talk() {
+            Some(CantSendReason::DeviceChat)
+        } else if self.is_contact_request() {
+            Some(CantSendReason::ContactRequest)
+        } else if self.is_mailing_list() && self.param.get(Param::ListPost).is_none_or_empty() {
+            Some(CantSendReason::ReadOnlyMailingList)
+        } else if !self.is_self_in_chat(context).await? {
+            Some(CantSendReason::NotAMember)
+        } else {
+            None
+        };
+        Ok(reason)
+    }
+
+    /// Returns the end-to-end encryption state of the chat,
+    /// which UIs can use to show a single lock icon.
+    ///
+    /// The state is derived from the protection status of the chat
+    /// and from the peerstates of the members, as done when sending messages.
+    pub async fn get_encryption_status(&self, context: &Context) -> Result<ChatEncryption> {
+        if self.is_protected() {
+            return Ok(ChatEncryption::Verified);
+        }
+        if self.id.is_special() || self.is_device_talk() || self.is_mailing_list() {
+            return Ok(ChatEncryption::None);
+        }
+
+        let mut contacts = Vec::new();
+        for contact_id in get_chat_contacts(context, self.id)
+            .await?
+            .into_iter()
+            .filter(|contact_id| !contact_id.is_special())
+        {
+            contacts.push(Contact::load_from_db(context, contact_id).await?);
+        }
+        let mut peerstates = Vec::new();
+        for contact in &contacts {
+            // A peerstate without a key cannot be used for encryption.
+            let peerstate = Peerstate::from_addr(context, contact.get_addr())
+                .await?
+                .filter(|peerstate| {
+                    peerstate
+                        .peek_key(PeerstateVerifiedStatus::Unverified)
+                        .is_some()
+                });
+            peerstates.push((peerstate, contact.get_addr()));
+        }
+
+        if !EncryptHelper::new(context)
+            .await?
+            .should_encrypt(context, false, &peerstates)?
+        {
+            return Ok(ChatEncryption::None);
+        }
+        let all_verified = !peerstates.is_empty()
+            && peerstates.iter().all(|(peerstate, _)| {
+                peerstate.as_ref().map_or(false, |peerstate| {
+                    peerstate
+                        .peek_key(PeerstateVerifiedStatus::BidirectVerified)
+                        .is_some()
+                })
+            });
+        if all_verified {
+            Ok(ChatEncryption::Guaranteed)
+        } else {
+            Ok(ChatEncryption::Opportunistic)
+        }
     }
 
     /// Checks if the user is part of a chat
@@ -1104,6 +1378,51 @@ pub(crate) async fn is_self_in_chat(&self, context: &Context) -> Result<bool> {
         }
     }
 
+    /// Returns the number of members of the chat,
+    /// the same as the length of [`get_chat_contacts`] but without loading the contacts.
+    pub async fn member_count(&self, context: &Context) -> Result<usize> {
+        context
+            .sql
+            .count(
+                "SELECT COUNT(*) FROM chats_contacts WHERE chat_id=?;",
+                paramsv![self.id],
+            )
+            .await
+    }
+
+    /// Returns the read horizon of each chat member, i.e. for every member
+    /// who sent a read receipt in this chat, the newest message the member has read.
+    ///
+    /// Contacts that are not members of the chat anymore are not returned.
+    pub async fn get_read_horizons(&self, context: &Context) -> Result<Vec<(ContactId, MsgId)>> {
+        let rows = context
+            .sql
+            .query_map(
+                "SELECT d.contact_id, m.id
+                 FROM msgs_mdns d
+                 INNER JOIN msgs m ON m.id=d.msg_id
+                 WHERE m.chat_id=?
+                   AND d.contact_id IN (SELECT contact_id FROM chats_contacts WHERE chat_id=?)
+                 ORDER BY d.contact_id, m.timestamp DESC, m.id DESC;",
+                paramsv![self.id, self.id],
+                |row| Ok((row.get::<_, ContactId>(0)?, row.get::<_, MsgId>(1)?)),
+                |rows| {
+                    rows.collect::<std::result::Result<Vec<_>, _>>()
+                        .map_err(Into::into)
+                },
+            )
+            .await?;
+
+        let mut horizons: Vec<(ContactId, MsgId)> = Vec::new();
+        for (contact_id, msg_id) in rows {
+            // Rows are sorted newest first for each contact, keep only the first one.
+            if horizons.last().map(|(id, _)| *id) != Some(contact_id) {
+                horizons.push((contact_id, msg_id));
+            }
+        }
+        Ok(horizons)
+    }
+
     pub async fn update_param(&mut self, context: &Context) -> Result<()> {
         context
             .sql
@@ -1151,6 +1470,11 @@ pub async fn get_profile_image(&self, context: &Context) -> Result<Option<PathBu
         Ok(None)
     }
 
+    /// Returns a color for the chat.
+    ///
+    /// For 1:1 chats this is the color of the contact, see [`Contact::get_color`].
+    /// For other chats the color is calculated from the chat name
+    /// using the XEP-0392 hash, so it is the same on all clients.
     pub async fn get_color(&self, context: &Context) -> Result<u32> {
         let mut color = 0;
 
@@ -1377,6 +1701,8 @@ async fn prepare_msg_raw(
 
         let ephemeral_timer = if msg.param.get_cmd() == SystemMessage::EphemeralTimerChanged {
             EphemeralTimer::Disabled
+        } else if let Some(timer) = msg.get_ephemeral_timer_override() {
+            timer
         } else {
             self.id.get_ephemeral_timer(context).await?
         };
@@ -1480,6 +1806,9 @@ async fn prepare_msg_raw(
                 .await?;
             msg.id = MsgId::new(u32::try_from(raw_id)?);
         }
+        if let Some(text) = &msg.text {
+            mention::save_mentions(context, msg.id, self.id, text).await?;
+        }
         context.interrupt_ephemeral_task().await;
         Ok(msg.id)
     }
@@ -1822,6 +2151,19 @@ async fn prepare_msg_blob(context: &Context, msg: &mut Message) -> Result<()> {
                 warn!(context, "Cannot recode image, using original data: {:?}", e);
             }
         }
+        if !msg.is_increation() {
+            let max_size = context
+                .get_config_u64(Config::MaxAttachmentSizeOutgoing)
+                .await?;
+            let size = dc_get_filebytes(context, &blob.to_abs_path()).await;
+            ensure!(
+                max_size == 0 || size <= max_size,
+                "Attachment \"{}\" has {} bytes, exceeding the limit of {} bytes set by max_attachment_size_outgoing.",
+                blob.as_file_name(),
+                size,
+                max_size
+            );
+        }
         msg.param.set(Param::File, blob.as_name());
 
         if msg.viewtype == Viewtype::File || msg.viewtype == Viewtype::Image {
@@ -1896,13 +2238,12 @@ async fn prepare_msg_common(
 
     prepare_msg_blob(context, msg).await?;
     chat_id.unarchive_if_not_muted(context).await?;
+    let timestamp = match msg.get_sort_timestamp_override() {
+        Some(timestamp) => min(timestamp, time().saturating_add(FUTURE_TIMESTAMP_TOLERANCE)),
+        None => dc_create_smeared_timestamp(context).await,
+    };
     msg.id = chat
-        .prepare_msg_raw(
-            context,
-            msg,
-            update_msg_id,
-            dc_create_smeared_timestamp(context).await,
-        )
+        .prepare_msg_raw(context, msg, update_msg_id, timestamp)
         .await?;
     msg.chat_id = chat_id;
 
@@ -2018,6 +2359,9 @@ async fn prepare_send_msg(
 ///
 /// Returns rowid if job was created or `None` if SMTP job is not needed, e.g. when sending to a
 /// group with only self and no BCC-to-self configured.
+/// For broadcast lists, a separate job is created for each recipient,
+/// this way, every copy can be encrypted to the key of a single recipient
+/// and recipients do not learn about each other; the rowid of the last job is returned then.
 ///
 /// The caller has to interrupt SMTP loop or otherwise process a new row.
 async fn create_send_msg_job(context: &Context, msg_id: MsgId) -> Result<Option<i64>> {
@@ -2038,24 +2382,34 @@ async fn create_send_msg_job(context: &Context, msg_id: MsgId) -> Result<Option<
     };
 
     let mimefactory = MimeFactory::from_msg(context, &msg, attach_selfavatar).await?;
-
-    let mut recipients = mimefactory.recipients();
-
     let from = context.get_primary_self_addr().await?;
-    let lowercase_from = from.to_lowercase();
-
-    // Send BCC to self if it is enabled and we are not going to
-    // delete it immediately.
-    if context.get_config_bool(Config::BccSelf).await?
-        && context.get_config_delete_server_after().await? != Some(0)
-        && !recipients
-            .iter()
-            .any(|x| x.to_lowercase() == lowercase_from)
-    {
-        recipients.push(from);
+    let bcc_self = should_bcc_self(context).await?;
+
+    let mut copies = Vec::new();
+    if mimefactory.is_broadcast() {
+        for addr in mimefactory.recipients() {
+            let mut copy = mimefactory.clone();
+            copy.retain_recipient(&addr);
+            copies.push((copy, vec![addr]));
+        }
+        if bcc_self {
+            let mut copy = mimefactory.clone();
+            copy.retain_recipient(&from);
+            copies.push((copy, vec![from]));
+        }
+    } else {
+        let mut recipients = mimefactory.recipients();
+        // Send BCC to self if it is enabled and we are not going to
+        // delete it immediately.
+        if bcc_self && !recipients.iter().any(|addr| addr_cmp(addr, &from)) {
+            recipients.push(from);
+        }
+        if !recipients.is_empty() {
+            copies.push((mimefactory, recipients));
+        }
     }
 
-    if recipients.is_empty() {
+    if copies.is_empty() {
         // may happen eg. for groups with only SELF and bcc_self disabled
         info!(
             context,
@@ -2065,29 +2419,42 @@ async fn create_send_msg_job(context: &Context, msg_id: MsgId) -> Result<Option<
         return Ok(None);
     }
 
-    let rendered_msg = match mimefactory.render(context).await {
-        Ok(res) => Ok(res),
-        Err(err) => {
-            message::set_msg_failed(context, msg_id, Some(err.to_string())).await;
-            Err(err)
+    // A single copy failing, eg. because a recipient of a broadcast list has no key,
+    // does not fail the whole message as long as other copies could be queued.
+    let mut row_id = None;
+    let mut first_rendered_msg = None;
+    let mut all_encrypted = true;
+    let mut last_err = None;
+    for (mimefactory, recipients) in copies {
+        match create_smtp_job(context, msg_id, mimefactory, &recipients, needs_encryption).await {
+            Ok((id, rendered_msg)) => {
+                row_id = Some(id);
+                all_encrypted &= rendered_msg.is_encrypted;
+                first_rendered_msg.get_or_insert(rendered_msg);
+            }
+            Err(err) => {
+                warn!(
+                    context,
+                    "cannot send message {} to {}: {}",
+                    msg_id,
+                    recipients.join(" "),
+                    err
+                );
+                last_err = Some(err);
+            }
         }
-    }?;
-
-    if needs_encryption && !rendered_msg.is_encrypted {
-        /* unrecoverable */
-        message::set_msg_failed(
-            context,
-            msg_id,
-            Some("End-to-end-encryption unavailable unexpectedly."),
-        )
-        .await;
-        bail!(
-            "e2e encryption unavailable {} - {:?}",
-            msg_id,
-            needs_encryption
-        );
     }
+    let rendered_msg = match (first_rendered_msg, last_err) {
+        (Some(rendered_msg), _) => rendered_msg,
+        (None, Some(err)) => {
+            message::set_msg_failed(context, msg_id, Some(err.to_string())).await;
+            return Err(err);
+        }
+        (None, None) => bail!("no smtp job created for message {}", msg_id),
+    };
 
+    // The following side effects concern the message and the chat as a whole,
+    // so they are applied once, even if several copies were queued.
     if rendered_msg.is_gossiped {
         msg.chat_id.set_gossiped_timestamp(context, time()).await?;
     }
@@ -2106,29 +2473,47 @@ async fn create_send_msg_job(context: &Context, msg_id: MsgId) -> Result<Option<
         }
     }
 
-    if let Some(sync_ids) = rendered_msg.sync_ids_to_delete {
-        if let Err(err) = context.delete_sync_ids(sync_ids).await {
-            error!(context, "Failed to delete sync ids: {:?}", err);
-        }
-    }
-
     if attach_selfavatar {
         if let Err(err) = msg.chat_id.set_selfavatar_timestamp(context, time()).await {
             error!(context, "Failed to set selfavatar timestamp: {:?}", err);
         }
     }
 
-    if rendered_msg.is_encrypted && !needs_encryption {
+    if all_encrypted && !needs_encryption {
         msg.param.set_int(Param::GuaranteeE2ee, 1);
         msg.update_param(context).await;
     }
 
+    msg.subject = rendered_msg.subject;
+    msg.update_subject(context).await;
+
+    Ok(row_id)
+}
+
+/// Renders a single copy of a message and inserts it into the `smtp` table.
+///
+/// Returns the rowid of the job and the rendered message.
+/// Nothing is queued if encryption is required but the copy could not be encrypted.
+async fn create_smtp_job(
+    context: &Context,
+    msg_id: MsgId,
+    mimefactory: MimeFactory<'_>,
+    recipients: &[String],
+    needs_encryption: bool,
+) -> Result<(i64, RenderedEmail)> {
     ensure!(!recipients.is_empty(), "no recipients for smtp job set");
 
-    let recipients = recipients.join(" ");
+    let mut rendered_msg = mimefactory.render(context).await?;
+    if needs_encryption && !rendered_msg.is_encrypted {
+        /* unrecoverable */
+        bail!("End-to-end-encryption unavailable unexpectedly.");
+    }
 
-    msg.subject = rendered_msg.subject.clone();
-    msg.update_subject(context).await;
+    if let Some(sync_ids) = rendered_msg.sync_ids_to_delete.take() {
+        if let Err(err) = context.delete_sync_ids(sync_ids).await {
+            error!(context, "Failed to delete sync ids: {:?}", err);
+        }
+    }
 
     let row_id = context
         .sql
@@ -2137,15 +2522,47 @@ async fn create_send_msg_job(context: &Context, msg_id: MsgId) -> Result<Option<
              VALUES           (?1,         ?2,         ?3,   ?4)",
             paramsv![
                 &rendered_msg.rfc724_mid,
-                recipients,
+                recipients.join(" "),
                 &rendered_msg.message,
                 msg_id
             ],
         )
         .await?;
+    Ok((row_id, rendered_msg))
+}
+
+/// Creates a job for sending an already sent message once more to a single address.
+///
+/// Unlike [`create_send_msg_job`], neither the message nor the chat is modified,
+/// eg. the message is not marked as failed on errors and no copy is sent to self,
+/// as the message was already sent to the other members before.
+async fn create_resend_msg_job(
+    context: &Context,
+    msg_id: MsgId,
+    addr: &str,
+) -> Result<Option<i64>> {
+    let msg = Message::load_from_db(context, msg_id).await?;
+    let mut mimefactory = MimeFactory::from_msg(context, &msg, false).await?;
+    mimefactory.retain_recipient(addr);
+    let recipients = mimefactory.recipients();
+    if recipients.is_empty() {
+        return Ok(None);
+    }
+    let needs_encryption = msg.param.get_bool(Param::GuaranteeE2ee).unwrap_or_default();
+    let (row_id, _) =
+        create_smtp_job(context, msg_id, mimefactory, &recipients, needs_encryption).await?;
     Ok(Some(row_id))
 }
 
+/// Returns true if a copy of sent messages should be sent to self.
+///
+/// This is the case if BCC to self is enabled
+/// and the server copy is not going to be deleted immediately.
+async fn should_bcc_self(context: &Context) -> Result<bool> {
+    Ok(context.get_config_bool(Config::BccSelf).await?
+        && context.get_config_delete_server_after().await? != Some(0))
+}
+
 pub async fn send_text_msg(
     context: &Context,
     chat_id: ChatId,
@@ -2197,26 +2614,18 @@ pub async fn get_chat_msgs(
 ) -> Result<Vec<ChatItem>> {
     let process_row = if (flags & DC_GCM_INFO_ONLY) != 0 {
         |row: &rusqlite::Row| {
-            // is_info logic taken from Message.is_info()
-            let params = row.get::<_, String>("param")?;
-            let (from_id, to_id) = (
-                row.get::<_, ContactId>("from_id")?,
-                row.get::<_, ContactId>("to_id")?,
-            );
-            let is_info_msg: bool = from_id == ContactId::INFO
-                || to_id == ContactId::INFO
-                || match Params::from_str(&params) {
-                    Ok(p) => {
-                        let cmd = p.get_cmd();
-                        cmd != SystemMessage::Unknown && cmd != SystemMessage::AutocryptSetupMessage
-                    }
-                    _ => false,
-                };
-
             Ok((
                 row.get::<_, i64>("timestamp")?,
                 row.get::<_, MsgId>("id")?,
-                !is_info_msg,
+                !is_info_row(row)?,
+            ))
+        }
+    } else if (flags & DC_GCM_NO_INFO) != 0 {
+        |row: &rusqlite::Row| {
+            Ok((
+                row.get::<_, i64>("timestamp")?,
+                row.get::<_, MsgId>("id")?,
+                is_info_row(row)?,
             ))
         }
     } else {
@@ -2279,6 +2688,21 @@ pub async fn get_chat_msgs(
                 process_rows,
             )
             .await?
+    } else if (flags & DC_GCM_NO_INFO) != 0 {
+        context
+            .sql
+            .query_map(
+                "SELECT m.id AS id, m.timestamp AS timestamp, m.param AS param, m.from_id AS from_id, m.to_id AS to_id
+               FROM msgs m
+              WHERE m.chat_id=?
+                AND m.hidden=0
+                AND m.from_id!=?
+                AND m.to_id!=?;",
+                paramsv![chat_id, ContactId::INFO, ContactId::INFO],
+                process_row,
+                process_rows,
+            )
+            .await?
     } else {
         context
             .sql
@@ -2296,6 +2720,25 @@ pub async fn get_chat_msgs(
     Ok(items)
 }
 
+/// Returns true if the row of a `get_chat_msgs()` query is an info message,
+/// the logic is taken from [`Message::is_info`].
+fn is_info_row(row: &rusqlite::Row) -> rusqlite::Result<bool> {
+    let params = row.get::<_, String>("param")?;
+    let (from_id, to_id) = (
+        row.get::<_, ContactId>("from_id")?,
+        row.get::<_, ContactId>("to_id")?,
+    );
+    Ok(from_id == ContactId::INFO
+        || to_id == ContactId::INFO
+        || match Params::from_str(&params) {
+            Ok(p) => {
+                let cmd = p.get_cmd();
+                cmd != SystemMessage::Unknown && cmd != SystemMessage::AutocryptSetupMessage
+            }
+            _ => false,
+        })
+}
+
 pub(crate) async fn marknoticed_chat_if_older_than(
     context: &Context,
     chat_id: ChatId,
@@ -2506,24 +2949,152 @@ pub async fn get_next_media(
     Ok(ret)
 }
 
-/// Returns a vector of contact IDs for given chat ID.
-pub async fn get_chat_contacts(context: &Context, chat_id: ChatId) -> Result<Vec<ContactId>> {
-    // Normal chats do not include SELF.  Group chats do (as it may happen that one is deleted from a
-    // groupchat but the chats stays visible, moreover, this makes displaying lists easier)
+/// Kind of a membership or protection change recorded in a chat.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditAction {
+    MemberAdded,
+    MemberRemoved,
+    ProtectionEnabled,
+    ProtectionDisabled,
+}
 
-    let list = context
+/// A membership or protection change of a chat,
+/// parsed from the system message stored for it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditEntry {
+    /// The system message recording the change.
+    pub msg_id: MsgId,
+    pub action: AuditAction,
+    /// The contact who made the change.
+    pub actor: ContactId,
+    /// Address of the added or removed member, `None` for protection changes.
+    pub target: Option<String>,
+    pub timestamp: i64,
+}
+
+/// Returns the timeline of member additions, removals and protection changes of a chat,
+/// oldest first.
+pub async fn get_chat_audit_log(context: &Context, chat_id: ChatId) -> Result<Vec<AuditEntry>> {
+    let rows = context
         .sql
         .query_map(
-            "SELECT cc.contact_id
-               FROM chats_contacts cc
-               LEFT JOIN contacts c
-                      ON c.id=cc.contact_id
-              WHERE cc.chat_id=?
-              ORDER BY c.id=1, LOWER(c.name||c.addr), c.id;",
+            "SELECT id, from_id, timestamp, param
+               FROM msgs
+              WHERE chat_id=?
+                AND param LIKE '%S=%'
+              ORDER BY timestamp, id;",
             paramsv![chat_id],
-            |row| row.get::<_, ContactId>(0),
-            |ids| ids.collect::<Result<Vec<_>, _>>().map_err(Into::into),
-        )
+            |row| {
+                let msg_id: MsgId = row.get(0)?;
+                let from_id: ContactId = row.get(1)?;
+                let timestamp: i64 = row.get(2)?;
+                let param: String = row.get(3)?;
+                Ok((msg_id, from_id, timestamp, param))
+            },
+            |rows| rows.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+        )
+        .await?;
+
+    let mut entries = Vec::new();
+    for (msg_id, actor, timestamp, param) in rows {
+        let param: Params = param.parse().unwrap_or_default();
+        let action = match param.get_cmd() {
+            SystemMessage::MemberAddedToGroup => AuditAction::MemberAdded,
+            SystemMessage::MemberRemovedFromGroup => AuditAction::MemberRemoved,
+            SystemMessage::ChatProtectionEnabled => AuditAction::ProtectionEnabled,
+            SystemMessage::ChatProtectionDisabled => AuditAction::ProtectionDisabled,
+            _ => continue,
+        };
+        let target = match action {
+            AuditAction::MemberAdded | AuditAction::MemberRemoved => {
+                param.get(Param::Arg).map(|addr| addr.to_string())
+            }
+            AuditAction::ProtectionEnabled | AuditAction::ProtectionDisabled => None,
+        };
+        entries.push(AuditEntry {
+            msg_id,
+            action,
+            actor,
+            target,
+            timestamp,
+        });
+    }
+    Ok(entries)
+}
+
+/// Maximum number of chats notified by [`announce_e2ee_change`].
+const E2EE_ANNOUNCEMENT_MAX_CHATS: u32 = 50;
+
+/// Chats without messages within this number of seconds are not notified
+/// by [`announce_e2ee_change`].
+const E2EE_ANNOUNCEMENT_MAX_AGE: i64 = 30 * 24 * 60 * 60;
+
+/// Sends a hidden message to recently active 1:1 chats
+/// so that the peers learn about the changed Autocrypt preference.
+///
+/// To avoid sending a burst of messages,
+/// only the [`E2EE_ANNOUNCEMENT_MAX_CHATS`] most recently active chats are notified.
+/// Chats the message cannot be sent to are skipped.
+/// Returns the number of chats notified.
+pub(crate) async fn announce_e2ee_change(context: &Context) -> Result<usize> {
+    let chat_ids = context
+        .sql
+        .query_map(
+            "SELECT c.id FROM chats c
+             WHERE c.id>9 AND c.type=? AND c.blocked=?
+             AND (SELECT MAX(m.timestamp) FROM msgs m WHERE m.chat_id=c.id)>?
+             ORDER BY (SELECT MAX(m.timestamp) FROM msgs m WHERE m.chat_id=c.id) DESC
+             LIMIT ?",
+            paramsv![
+                Chattype::Single,
+                Blocked::Not,
+                time() - E2EE_ANNOUNCEMENT_MAX_AGE,
+                E2EE_ANNOUNCEMENT_MAX_CHATS
+            ],
+            |row| row.get::<_, ChatId>(0),
+            |ids| ids.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+        )
+        .await?;
+
+    let mut announced = 0;
+    for chat_id in chat_ids {
+        let chat = Chat::load_from_db(context, chat_id).await?;
+        if chat.is_self_talk() || !chat.can_send(context).await? {
+            continue;
+        }
+        let mut msg = Message::new(Viewtype::Text);
+        msg.hidden = true;
+        msg.param.set_cmd(SystemMessage::AutocryptAnnouncement);
+        match send_msg(context, chat_id, &mut msg).await {
+            Ok(_) => announced += 1,
+            Err(err) => warn!(
+                context,
+                "Cannot announce e2ee change to chat {}: {:#}", chat_id, err
+            ),
+        }
+    }
+    info!(context, "Announced e2ee change to {} chats.", announced);
+    Ok(announced)
+}
+
+/// Returns a vector of contact IDs for given chat ID.
+pub async fn get_chat_contacts(context: &Context, chat_id: ChatId) -> Result<Vec<ContactId>> {
+    // Normal chats do not include SELF.  Group chats do (as it may happen that one is deleted from a
+    // groupchat but the chats stays visible, moreover, this makes displaying lists easier)
+
+    let list = context
+        .sql
+        .query_map(
+            "SELECT cc.contact_id
+               FROM chats_contacts cc
+               LEFT JOIN contacts c
+                      ON c.id=cc.contact_id
+              WHERE cc.chat_id=?
+              ORDER BY c.id=1, LOWER(c.name||c.addr), c.id;",
+            paramsv![chat_id],
+            |row| row.get::<_, ContactId>(0),
+            |ids| ids.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+        )
         .await?;
 
     Ok(list)
@@ -2595,8 +3166,15 @@ async fn find_unused_broadcast_list_name(context: &Context) -> Result<String> {
 }
 
 /// Creates a new broadcast list.
-pub async fn create_broadcast_list(context: &Context) -> Result<ChatId> {
-    let chat_name = find_unused_broadcast_list_name(context).await?;
+///
+/// If `chat_name` is empty, an unused name based on the translated "Broadcast List" is chosen.
+pub async fn create_broadcast_list(context: &Context, chat_name: &str) -> Result<ChatId> {
+    let chat_name = improve_single_line_input(chat_name);
+    let chat_name = if chat_name.is_empty() {
+        find_unused_broadcast_list_name(context).await?
+    } else {
+        chat_name
+    };
     let grpid = dc_create_id();
     let row_id = context
         .sql
@@ -2748,6 +3326,96 @@ pub(crate) async fn add_contact_to_chat_ex(
     Ok(true)
 }
 
+/// Adds several contacts to a group or broadcast list at once.
+///
+/// All contacts are validated first and then added in a single transaction,
+/// so either all or none of them become members.
+/// For promoted groups a single member-added system message is sent;
+/// receivers recreate the member list from the message recipients.
+/// Contacts that are already members are skipped.
+pub async fn add_contacts_to_chat(
+    context: &Context,
+    chat_id: ChatId,
+    contact_ids: &[ContactId],
+) -> Result<()> {
+    ensure!(!chat_id.is_special(), "can not add member to special chats");
+    let chat = Chat::load_from_db(context, chat_id).await?;
+    ensure!(
+        chat.typ == Chattype::Group || chat.typ == Chattype::Broadcast,
+        "{} is not a group/broadcast where one can add members",
+        chat_id
+    );
+    ensure!(!chat.is_mailing_list(), "Mailing lists can't be changed");
+    if !chat.is_self_in_chat(context).await? {
+        context.emit_event(EventType::ErrorSelfNotInGroup(
+            "Cannot add contacts to group; self not in group.".into(),
+        ));
+        bail!("can not add contacts because our account is not part of it");
+    }
+
+    let mut new_contacts = Vec::new();
+    for &contact_id in contact_ids {
+        ensure!(
+            Contact::real_exists_by_id(context, contact_id).await?,
+            "invalid contact_id {} for adding to group",
+            contact_id
+        );
+        let contact = Contact::get_by_id(context, contact_id).await?;
+        if context.is_self_addr(contact.get_addr()).await?
+            || is_contact_in_chat(context, chat_id, contact_id).await?
+            || new_contacts.iter().any(|c: &Contact| c.id == contact_id)
+        {
+            continue;
+        }
+        ensure!(
+            !chat.is_protected()
+                || contact.is_verified(context).await? == VerifiedStatus::BidirectVerified,
+            "Only bidirectional verified contacts can be added to protected chats, {} is not",
+            contact_id
+        );
+        new_contacts.push(contact);
+    }
+    if new_contacts.is_empty() {
+        return Ok(());
+    }
+
+    chat_id.reset_gossiped_timestamp(context).await?;
+    let ids: Vec<ContactId> = new_contacts.iter().map(|c| c.id).collect();
+    context
+        .sql
+        .transaction(move |transaction| {
+            let mut stmt = transaction
+                .prepare("INSERT INTO chats_contacts (chat_id, contact_id) VALUES(?, ?)")?;
+            for contact_id in ids {
+                stmt.execute(paramsv![chat_id, contact_id])?;
+            }
+            Ok(())
+        })
+        .await?;
+
+    if chat.typ == Chattype::Group && chat.is_promoted() {
+        let addrs = new_contacts
+            .iter()
+            .map(|c| c.get_addr().to_string())
+            .collect::<Vec<_>>();
+        let mut msg = Message::new(Viewtype::Text);
+        msg.text = Some(stock_str::msg_add_members(context, &addrs, ContactId::SELF).await);
+        msg.param.set_cmd(SystemMessage::MemberAddedToGroup);
+        // `Chat-Group-Member-Added` carries a single address,
+        // the complete list is sent in `Chat-Group-Members-Added`
+        // that is ignored by older versions.
+        if let Some(first_addr) = addrs.first() {
+            msg.param.set(Param::Arg, first_addr);
+        }
+        if addrs.len() > 1 {
+            msg.param.set(Param::Arg3, addrs.join(", "));
+        }
+        send_msg(context, chat_id, &mut msg).await?;
+    }
+    context.emit_event(EventType::ChatModified(chat_id));
+    Ok(())
+}
+
 pub(crate) async fn shall_attach_selfavatar(context: &Context, chat_id: ChatId) -> Result<bool> {
     // versions before 12/2019 already allowed to set selfavatar, however, it was never sent to others.
     // to avoid sending out previously set selfavatars unexpectedly we added this additional check.
@@ -2782,6 +3450,10 @@ pub(crate) async fn shall_attach_selfavatar(context: &Context, chat_id: ChatId)
     Ok(needs_attach)
 }
 
+/// Expiry in seconds since the epoch returned by [`ChatId::is_muted_until`]
+/// for chats muted forever, 9999-12-31 23:59:59 UTC.
+pub const MUTED_FOREVER_TIMESTAMP: u64 = 253_402_300_799;
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum MuteDuration {
     NotMuted,
@@ -2937,13 +3609,28 @@ pub(crate) async fn is_group_explicitly_left(context: &Context, grpid: &str) ->
     Ok(exists)
 }
 
-/// Sets group or mailing list chat name.
+/// Maximum number of characters of a chat name set by [`set_chat_name`].
+///
+/// Longer names received in `Chat-Group-Name` are ignored.
+pub(crate) const MAX_CHAT_NAME_CHARS: usize = 200;
+
+/// Sets the name of a group, mailing list or broadcast list.
+///
+/// Leading and trailing whitespace is removed and whitespace including newlines
+/// is collapsed to single spaces. Empty names and names longer than
+/// [`MAX_CHAT_NAME_CHARS`] characters are rejected.
+/// For promoted groups, the new name is sent to the members.
 pub async fn set_chat_name(context: &Context, chat_id: ChatId, new_name: &str) -> Result<()> {
-    let new_name = improve_single_line_input(new_name);
+    let new_name = new_name.split_whitespace().collect::<Vec<_>>().join(" ");
     /* the function only sets the names of group chats; normal chats get their names from the contacts */
     let mut success = false;
 
-    ensure!(!new_name.is_empty(), "Invalid name");
+    ensure!(!new_name.is_empty(), "Chat name must not be empty");
+    ensure!(
+        new_name.chars().count() <= MAX_CHAT_NAME_CHARS,
+        "Chat name is too long, at most {} characters are allowed",
+        MAX_CHAT_NAME_CHARS
+    );
     ensure!(!chat_id.is_special(), "Invalid chat ID");
 
     let chat = Chat::load_from_db(context, chat_id).await?;
@@ -3185,6 +3872,117 @@ pub async fn resend_msgs(context: &Context, msg_ids: &[MsgId]) -> Result<()> {
     Ok(())
 }
 
+/// Maximum number of messages resent by [`resend_recent_to_contact`].
+pub const RESEND_TO_CONTACT_MAX: usize = 20;
+
+/// Resends the last `count` own messages of a group to a contact that joined late,
+/// so that the contact gets some context.
+///
+/// Only messages sent before the contact was added to the group are resent,
+/// messages the contact has already read are skipped.
+/// The messages are sent to the contact only, encrypted if possible,
+/// and at most [`RESEND_TO_CONTACT_MAX`] messages are resent.
+pub async fn resend_recent_to_contact(
+    context: &Context,
+    chat_id: ChatId,
+    contact_id: ContactId,
+    count: usize,
+) -> Result<()> {
+    let chat = Chat::load_from_db(context, chat_id).await?;
+    ensure!(chat.typ == Chattype::Group, "{} is not a group", chat_id);
+    ensure!(
+        !contact_id.is_special(),
+        "Cannot resend to special contact {}",
+        contact_id
+    );
+    ensure!(
+        is_contact_in_chat(context, chat_id, contact_id).await?,
+        "{} is not a member of {}",
+        contact_id,
+        chat_id
+    );
+    let contact = Contact::load_from_db(context, contact_id).await?;
+    let addr = contact.get_addr();
+
+    // Find out when the contact was added to the group.
+    let added_timestamp = context
+        .sql
+        .query_map(
+            "SELECT timestamp, param FROM msgs
+             WHERE chat_id=? AND param LIKE ?
+             ORDER BY timestamp DESC, id DESC",
+            paramsv![chat_id, format!("%{}%", addr)],
+            |row| {
+                let timestamp: i64 = row.get(0)?;
+                let param: String = row.get(1)?;
+                Ok((timestamp, param))
+            },
+            |rows| rows.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+        )
+        .await?
+        .into_iter()
+        .find(|(_, param)| {
+            let param: Params = param.parse().unwrap_or_default();
+            param.get_cmd() == SystemMessage::MemberAddedToGroup
+                && (addr_cmp(param.get(Param::Arg).unwrap_or_default(), addr)
+                    || param
+                        .get(Param::Arg3)
+                        .unwrap_or_default()
+                        .split(',')
+                        .any(|a| addr_cmp(a.trim(), addr)))
+        })
+        .map_or_else(time, |(timestamp, _)| timestamp);
+
+    let max_cnt = min(count, RESEND_TO_CONTACT_MAX);
+    let msg_ids = context
+        .sql
+        .query_map(
+            "SELECT m.id AS id, m.from_id AS from_id, m.to_id AS to_id, m.param AS param
+             FROM msgs m
+             WHERE m.chat_id=? AND m.from_id=? AND m.to_id!=? AND m.hidden=0
+               AND m.state IN (?, ?) AND m.timestamp<?
+               AND NOT EXISTS (SELECT 1 FROM msgs_mdns d WHERE d.msg_id=m.id AND d.contact_id=?)
+             ORDER BY m.timestamp DESC, m.id DESC",
+            paramsv![
+                chat_id,
+                ContactId::SELF,
+                ContactId::INFO,
+                MessageState::OutDelivered,
+                MessageState::OutMdnRcvd,
+                added_timestamp,
+                contact_id
+            ],
+            |row| Ok((row.get::<_, MsgId>("id")?, is_info_row(row)?)),
+            |rows| {
+                let mut ids = Vec::new();
+                for row in rows {
+                    let (msg_id, is_info) = row?;
+                    if ids.len() >= max_cnt {
+                        break;
+                    }
+                    if !is_info {
+                        ids.push(msg_id);
+                    }
+                }
+                Ok(ids)
+            },
+        )
+        .await?;
+
+    // Resend in the original order.
+    for msg_id in msg_ids.into_iter().rev() {
+        match create_resend_msg_job(context, msg_id, addr).await {
+            Ok(Some(_)) => context.interrupt_smtp(InterruptInfo::new(false)).await,
+            Ok(None) => {}
+            Err(err) => warn!(
+                context,
+                "Cannot resend message {} to {}: {:#}", msg_id, contact_id, err
+            ),
+        }
+    }
+    Ok(())
+}
+
 pub(crate) async fn get_chat_cnt(context: &Context) -> Result<usize> {
     if context.sql.is_open().await {
         // no database, no chats - this is no error (needed eg. for information)
@@ -3224,6 +4022,22 @@ pub(crate) async fn get_chat_id_by_grpid(
         .await
 }
 
+impl Context {
+    /// Returns the ID of the group with the given group ID, if it exists.
+    ///
+    /// The group ID is the `grpid` of a [`Chat`] as sent in the `Chat-Group-ID` header.
+    /// Blocked groups and contact requests are returned, too,
+    /// use [`Chat::load_from_db`] to check the state of the chat.
+    pub async fn get_chat_id_by_grpid(&self, grpid: &str) -> Result<Option<ChatId>> {
+        if grpid.is_empty() {
+            return Ok(None);
+        }
+        Ok(get_chat_id_by_grpid(self, grpid)
+            .await?
+            .map(|(chat_id, _protected, _blocked)| chat_id))
+    }
+}
+
 /// Adds a message to device chat.
 ///
 /// Optional `label` can be provided to ensure that message is added only once.
@@ -3320,7 +4134,7 @@ pub async fn add_device_msg_with_importance(
 
     if !msg_id.is_unset() {
         if important {
-            context.emit_incoming_msg(chat_id, msg_id);
+            context.emit_incoming_msg(chat_id, msg_id, Chattype::Single, false);
         } else {
             context.emit_msgs_changed(chat_id, msg_id);
         }
@@ -3466,10 +4280,12 @@ pub(crate) async fn update_msg_text_and_timestamp(
 mod tests {
     use super::*;
 
+    use crate::aheader::EncryptPreference;
     use crate::chatlist::{dc_get_archived_cnt, Chatlist};
     use crate::constants::{DC_GCL_ARCHIVED_ONLY, DC_GCL_NO_SPECIALS};
     use crate::contact::Contact;
     use crate::dc_receive_imf::dc_receive_imf;
+    use crate::peerstate::Peerstate;
     use crate::test_utils::TestContext;
     use async_std::fs::File;
     use async_std::prelude::*;
@@ -3688,6 +4504,125 @@ async fn test_add_contact_to_chat_ex_add_self() {
         assert_eq!(added, false);
     }
 
+    #[async_std::test]
+    async fn test_add_contacts_to_chat() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        let chat_id = create_group_chat(&t, ProtectionStatus::Unprotected, "foo").await?;
+        t.send_text(chat_id, "promote").await;
+
+        let bob = Contact::create(&t, "", "bob@example.net").await?;
+        let claire = Contact::create(&t, "", "claire@example.org").await?;
+        let dave = Contact::create(&t, "", "dave@example.org").await?;
+        let msg_cnt = chat_id.get_msg_cnt(&t).await?;
+        add_contacts_to_chat(&t, chat_id, &[bob, claire, dave]).await?;
+
+        assert_eq!(get_chat_contacts(&t, chat_id).await?.len(), 4);
+        assert_eq!(chat_id.get_msg_cnt(&t).await?, msg_cnt + 1);
+        let msg = t.get_last_msg_in(chat_id).await;
+        assert!(msg.is_info());
+        assert_eq!(msg.get_info_type(), SystemMessage::MemberAddedToGroup);
+        assert_eq!(
+            msg.get_text().unwrap(),
+            "Members bob@example.net, claire@example.org and dave@example.org added by me."
+        );
+
+        let sent = t.pop_sent_msg().await;
+        let payload = sent.payload();
+        assert!(payload.contains("Chat-Group-Member-Added: bob@example.net\r\n"));
+        assert!(payload.contains("Chat-Group-Members-Added:"));
+        assert!(payload.contains("claire@example.org"));
+        assert!(payload.contains("dave@example.org"));
+
+        // Adding existing members again is a no-op.
+        add_contacts_to_chat(&t, chat_id, &[bob, claire]).await?;
+        assert_eq!(chat_id.get_msg_cnt(&t).await?, msg_cnt + 1);
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn test_add_contacts_to_chat_received() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+        let bob = TestContext::new_bob().await;
+        let alice_chat_id = create_group_chat(&alice, ProtectionStatus::Unprotected, "foo").await?;
+        let bob_id = Contact::create(&alice, "", "bob@example.net").await?;
+        add_contact_to_chat(&alice, alice_chat_id, bob_id).await?;
+        let sent = alice.send_text(alice_chat_id, "hi").await;
+        let bob_chat_id = bob.recv_msg(&sent).await.chat_id;
+
+        let claire = Contact::create(&alice, "", "claire@example.org").await?;
+        let dave = Contact::create(&alice, "", "dave@example.org").await?;
+        add_contacts_to_chat(&alice, alice_chat_id, &[claire, dave]).await?;
+        let msg = bob.recv_msg(&alice.pop_sent_msg().await).await;
+        assert_eq!(msg.chat_id, bob_chat_id);
+        assert_eq!(msg.get_info_type(), SystemMessage::MemberAddedToGroup);
+        let text = msg.get_text().unwrap();
+        assert!(text.starts_with("Members "));
+        assert!(text.contains("claire@example.org"));
+        assert!(text.contains("dave@example.org"));
+        assert_eq!(get_chat_contacts(&bob, bob_chat_id).await?.len(), 4);
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn test_get_chat_audit_log() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+        let bob = TestContext::new_bob().await;
+        let alice_chat_id = create_group_chat(&alice, ProtectionStatus::Unprotected, "foo").await?;
+        let claire = Contact::create(&alice, "", "claire@example.org").await?;
+        let bob_id = Contact::create(&alice, "", "bob@example.net").await?;
+        add_contact_to_chat(&alice, alice_chat_id, bob_id).await?;
+        alice.send_text(alice_chat_id, "hi").await;
+        let bob_chat_id = bob.recv_msg(&alice.pop_sent_msg().await).await.chat_id;
+        assert!(get_chat_audit_log(&alice, alice_chat_id).await?.is_empty());
+
+        add_contact_to_chat(&alice, alice_chat_id, claire).await?;
+        bob.recv_msg(&alice.pop_sent_msg().await).await;
+        remove_contact_from_chat(&alice, alice_chat_id, claire).await?;
+        bob.recv_msg(&alice.pop_sent_msg().await).await;
+
+        let log = get_chat_audit_log(&alice, alice_chat_id).await?;
+        assert_eq!(log.len(), 2);
+        assert_eq!(log[0].action, AuditAction::MemberAdded);
+        assert_eq!(log[0].actor, ContactId::SELF);
+        assert_eq!(log[0].target, Some("claire@example.org".to_string()));
+        assert_eq!(log[1].action, AuditAction::MemberRemoved);
+        assert_eq!(log[1].target, Some("claire@example.org".to_string()));
+        assert!(log[0].timestamp <= log[1].timestamp);
+
+        // The receiver gets the same structured entries.
+        let log = get_chat_audit_log(&bob, bob_chat_id).await?;
+        assert_eq!(log.len(), 2);
+        assert_eq!(log[0].action, AuditAction::MemberAdded);
+        assert_eq!(log[0].target, Some("claire@example.org".to_string()));
+        assert_ne!(log[0].actor, ContactId::SELF);
+        assert_eq!(log[1].action, AuditAction::MemberRemoved);
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn test_get_protection_history() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+        let chat_id = create_group_chat(&alice, ProtectionStatus::Unprotected, "foo").await?;
+        assert!(chat_id.get_protection_history(&alice).await?.is_empty());
+
+        chat_id
+            .set_protection(&alice, ProtectionStatus::Protected)
+            .await?;
+        chat_id
+            .set_protection(&alice, ProtectionStatus::Unprotected)
+            .await?;
+
+        let history = chat_id.get_protection_history(&alice).await?;
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].0, ProtectionStatus::Protected);
+        assert_eq!(history[0].1, ContactId::SELF);
+        assert_eq!(history[1].0, ProtectionStatus::Unprotected);
+        assert_eq!(history[1].1, ContactId::SELF);
+        assert!(history[0].2 > 0);
+        assert!(history[0].2 <= history[1].2);
+        Ok(())
+    }
+
     #[async_std::test]
     async fn test_modify_chat_multi_device() -> Result<()> {
         let a1 = TestContext::new_alice().await;
@@ -4145,6 +5080,41 @@ async fn chatlist_len(ctx: &Context, listflags: usize) -> usize {
             .len()
     }
 
+    #[async_std::test]
+    async fn test_archive_and_mark_noticed() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+        let bob = TestContext::new_bob().await;
+        let bob_chat_id = bob.create_chat(&alice).await.id;
+        alice
+            .recv_msg(&bob.send_text(bob_chat_id, "one").await)
+            .await;
+        let chat_id = alice
+            .recv_msg(&bob.send_text(bob_chat_id, "two").await)
+            .await
+            .chat_id;
+        chat_id.accept(&alice).await?;
+        assert_eq!(chat_id.get_fresh_msg_cnt(&alice).await?, 2);
+
+        chat_id.archive_and_mark_noticed(&alice).await?;
+        assert_eq!(
+            Chat::load_from_db(&alice, chat_id).await?.get_visibility(),
+            ChatVisibility::Archived
+        );
+        assert_eq!(chat_id.get_fresh_msg_cnt(&alice).await?, 0);
+        alice
+            .evtracker
+            .get_matching(
+                |e| matches!(e, EventType::MsgsChanged { chat_id, .. } if chat_id.is_unset()),
+            )
+            .await;
+        alice
+            .evtracker
+            .get_matching(|e| matches!(e, EventType::MsgsNoticed(id) if *id == chat_id))
+            .await;
+
+        Ok(())
+    }
+
     #[async_std::test]
     async fn test_archive() {
         // create two chats
@@ -4331,23 +5301,114 @@ async fn msg_from_bob(t: &TestContext, num: u32) -> Result<()> {
         Ok(())
     }
 
-    async fn get_chats_from_chat_list(ctx: &Context, listflags: usize) -> Vec<ChatId> {
-        let chatlist = Chatlist::try_load(ctx, listflags, None, None)
+    #[async_std::test]
+    async fn test_is_muted_until() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        let chat_id = t
+            .create_chat_with_contact("bob", "bob@example.net")
             .await
-            .unwrap();
-        let mut result = Vec::new();
-        for chatlist_index in 0..chatlist.len() {
-            result.push(chatlist.get_chat_id(chatlist_index).unwrap())
-        }
-        result
+            .id;
+        assert_eq!(chat_id.is_muted_until(&t).await?, None);
+
+        let until = SystemTime::UNIX_EPOCH + Duration::from_secs(time() as u64 + 3600);
+        set_muted(&t, chat_id, MuteDuration::Until(until)).await?;
+        t.evtracker
+            .get_matching(|evt| matches!(evt, EventType::ChatModified(id) if *id == chat_id))
+            .await;
+        assert_eq!(chat_id.is_muted_until(&t).await?, Some(until));
+
+        set_muted(&t, chat_id, MuteDuration::Forever).await?;
+        assert_eq!(
+            chat_id.is_muted_until(&t).await?,
+            Some(SystemTime::UNIX_EPOCH + Duration::from_secs(MUTED_FOREVER_TIMESTAMP))
+        );
+
+        let expired = SystemTime::UNIX_EPOCH + Duration::from_secs(time() as u64 - 3600);
+        set_muted(&t, chat_id, MuteDuration::Until(expired)).await?;
+        assert_eq!(chat_id.is_muted_until(&t).await?, None);
+
+        set_muted(&t, chat_id, MuteDuration::NotMuted).await?;
+        t.evtracker
+            .get_matching(|evt| matches!(evt, EventType::ChatModified(id) if *id == chat_id))
+            .await;
+        assert_eq!(chat_id.is_muted_until(&t).await?, None);
+
+        // Muting and unmuting does not add any messages to the chat.
+        assert_eq!(chat_id.get_msg_cnt(&t).await?, 0);
+
+        Ok(())
     }
 
     #[async_std::test]
-    async fn test_pinned() {
-        let t = TestContext::new().await;
-
-        // create 3 chats, wait 1 second in between to get a reliable order (we order by time)
-        let mut msg = Message::new(Viewtype::Text);
+    async fn test_context_get_chat_id_by_grpid() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+        let chat_id = create_group_chat(&alice, ProtectionStatus::Unprotected, "Group").await?;
+        let chat = Chat::load_from_db(&alice, chat_id).await?;
+        assert!(!chat.grpid.is_empty());
+        assert_eq!(
+            alice.get_chat_id_by_grpid(&chat.grpid).await?,
+            Some(chat_id)
+        );
+
+        assert_eq!(alice.get_chat_id_by_grpid("unknown-grpid").await?, None);
+
+        // 1:1 chats have an empty group ID.
+        alice
+            .create_chat_with_contact("bob", "bob@example.net")
+            .await;
+        assert_eq!(alice.get_chat_id_by_grpid("").await?, None);
+
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn test_member_count() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+        let bob_id = Contact::create(&alice, "Bob", "bob@example.net").await?;
+        let claire_id = Contact::create(&alice, "Claire", "claire@example.org").await?;
+
+        let chat_id = create_group_chat(&alice, ProtectionStatus::Unprotected, "Group").await?;
+        add_contact_to_chat(&alice, chat_id, bob_id).await?;
+        add_contact_to_chat(&alice, chat_id, claire_id).await?;
+        let chat = Chat::load_from_db(&alice, chat_id).await?;
+        assert_eq!(chat.member_count(&alice).await?, 3);
+        assert_eq!(
+            chat.member_count(&alice).await?,
+            get_chat_contacts(&alice, chat_id).await?.len()
+        );
+
+        remove_contact_from_chat(&alice, chat_id, claire_id).await?;
+        assert_eq!(chat.member_count(&alice).await?, 2);
+        assert_eq!(
+            chat.member_count(&alice).await?,
+            get_chat_contacts(&alice, chat_id).await?.len()
+        );
+
+        let single_chat = alice
+            .create_chat_with_contact("Bob", "bob@example.net")
+            .await;
+        assert_eq!(single_chat.member_count(&alice).await?, 1);
+
+        Ok(())
+    }
+
+    async fn get_chats_from_chat_list(ctx: &Context, listflags: usize) -> Vec<ChatId> {
+        let chatlist = Chatlist::try_load(ctx, listflags, None, None)
+            .await
+            .unwrap();
+        let mut result = Vec::new();
+        for chatlist_index in 0..chatlist.len() {
+            result.push(chatlist.get_chat_id(chatlist_index).unwrap())
+        }
+        result
+    }
+
+    #[async_std::test]
+    async fn test_pinned() {
+        let t = TestContext::new().await;
+
+        // create 3 chats, wait 1 second in between to get a reliable order (we order by time)
+        let mut msg = Message::new(Viewtype::Text);
         msg.text = Some("foo".to_string());
         let msg_id = add_device_msg(&t, None, Some(&mut msg)).await.unwrap();
         let chat_id1 = message::Message::load_from_db(&t, msg_id)
@@ -4417,6 +5478,64 @@ async fn test_set_chat_name() {
         );
     }
 
+    #[async_std::test]
+    async fn test_set_chat_name_invalid() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        let chat_id = create_group_chat(&t, ProtectionStatus::Unprotected, "foo").await?;
+
+        assert!(set_chat_name(&t, chat_id, "").await.is_err());
+        assert!(set_chat_name(&t, chat_id, " \n\t ").await.is_err());
+        assert!(
+            set_chat_name(&t, chat_id, &"x".repeat(MAX_CHAT_NAME_CHARS + 1))
+                .await
+                .is_err()
+        );
+        assert_eq!(Chat::load_from_db(&t, chat_id).await?.get_name(), "foo");
+
+        set_chat_name(&t, chat_id, &"x".repeat(MAX_CHAT_NAME_CHARS)).await?;
+        assert_eq!(
+            Chat::load_from_db(&t, chat_id).await?.get_name().len(),
+            MAX_CHAT_NAME_CHARS
+        );
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn test_set_chat_name_sent() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+        let bob = TestContext::new_bob().await;
+        let alice_chat_id = create_group_chat(&alice, ProtectionStatus::Unprotected, "foo").await?;
+        let alice_bob_id = Contact::create(&alice, "", "bob@example.net").await?;
+        add_contact_to_chat(&alice, alice_chat_id, alice_bob_id).await?;
+        let sent = alice.send_text(alice_chat_id, "hi").await;
+        let bob_chat_id = bob.recv_msg(&sent).await.chat_id;
+
+        set_chat_name(&alice, alice_chat_id, "  bar\n\n baz ").await?;
+        let msg = alice.get_last_msg_in(alice_chat_id).await;
+        assert_eq!(msg.get_info_type(), SystemMessage::GroupNameChanged);
+        assert_eq!(
+            msg.get_text().unwrap(),
+            "Group name changed from \"foo\" to \"bar baz\" by me."
+        );
+
+        let msg = bob.recv_msg(&alice.pop_sent_msg().await).await;
+        assert_eq!(msg.get_info_type(), SystemMessage::GroupNameChanged);
+        assert_eq!(
+            Chat::load_from_db(&bob, bob_chat_id).await?.get_name(),
+            "bar baz"
+        );
+
+        // the longest name allowed for sending is accepted by the receiver
+        let long_name = "ä".repeat(MAX_CHAT_NAME_CHARS);
+        set_chat_name(&alice, alice_chat_id, &long_name).await?;
+        bob.recv_msg(&alice.pop_sent_msg().await).await;
+        assert_eq!(
+            Chat::load_from_db(&bob, bob_chat_id).await?.get_name(),
+            long_name
+        );
+        Ok(())
+    }
+
     #[async_std::test]
     async fn test_create_same_chat_twice() {
         let context = TestContext::new().await;
@@ -4556,6 +5675,56 @@ async fn test_add_info_msg_with_cmd() -> Result<()> {
         Ok(())
     }
 
+    #[async_std::test]
+    async fn test_get_chat_msgs_no_info() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        let chat = t.create_chat_with_contact("bob", "bob@example.net").await;
+        let msg_id1 = send_text_msg(&t, chat.id, "hello".to_string()).await?;
+        let timestamp = Message::load_from_db(&t, msg_id1).await?.timestamp_sort;
+        let info_id1 = add_info_msg(&t, chat.id, "foo info", timestamp).await?;
+        let info_id2 = add_info_msg_with_cmd(
+            &t,
+            chat.id,
+            "timer changed",
+            SystemMessage::EphemeralTimerChanged,
+            timestamp,
+            None,
+            None,
+            None,
+        )
+        .await?;
+        let msg_id2 = send_text_msg(&t, chat.id, "bye".to_string()).await?;
+
+        let msg_items = |ids: &[MsgId]| -> Vec<ChatItem> {
+            ids.iter()
+                .map(|&msg_id| ChatItem::Message { msg_id })
+                .collect()
+        };
+        assert_eq!(
+            get_chat_msgs(&t, chat.id, 0).await?,
+            msg_items(&[msg_id1, info_id1, info_id2, msg_id2])
+        );
+        assert_eq!(
+            get_chat_msgs(&t, chat.id, DC_GCM_NO_INFO).await?,
+            msg_items(&[msg_id1, msg_id2])
+        );
+        assert_eq!(
+            get_chat_msgs(&t, chat.id, DC_GCM_INFO_ONLY).await?,
+            msg_items(&[info_id1, info_id2])
+        );
+
+        let items = get_chat_msgs(&t, chat.id, DC_GCM_NO_INFO | DC_GCM_ADDDAYMARKER).await?;
+        assert!(matches!(items.first(), Some(ChatItem::DayMarker { .. })));
+        assert_eq!(
+            items
+                .into_iter()
+                .filter(|item| matches!(item, ChatItem::Message { .. }))
+                .collect::<Vec<_>>(),
+            msg_items(&[msg_id1, msg_id2])
+        );
+        Ok(())
+    }
+
     #[async_std::test]
     async fn test_set_protection() {
         let t = TestContext::new_alice().await;
@@ -4740,6 +5909,133 @@ async fn test_group_with_removed_message_id() -> Result<()> {
         Ok(())
     }
 
+    #[async_std::test]
+    async fn test_get_first_unread_msg_id() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+        let bob = TestContext::new_bob().await;
+        let bob_chat_id = bob.create_chat(&alice).await.id;
+        let alice_chat_id = alice.create_chat(&bob).await.id;
+        assert_eq!(alice_chat_id.get_first_unread_msg_id(&alice).await?, None);
+
+        let mut msg_ids = Vec::new();
+        for text in ["one", "two", "three"] {
+            let sent = bob.send_text(bob_chat_id, text).await;
+            msg_ids.push(alice.recv_msg(&sent).await.id);
+        }
+        assert_eq!(
+            alice_chat_id.get_first_unread_msg_id(&alice).await?,
+            Some(msg_ids[0])
+        );
+
+        message::markseen_msgs(&alice, vec![msg_ids[0]]).await?;
+        assert_eq!(
+            alice_chat_id.get_first_unread_msg_id(&alice).await?,
+            Some(msg_ids[1])
+        );
+
+        message::markseen_msgs(&alice, vec![msg_ids[1], msg_ids[2]]).await?;
+        assert_eq!(alice_chat_id.get_first_unread_msg_id(&alice).await?, None);
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn test_get_read_horizons() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        let bob_id = Contact::create(&t, "", "bob@example.net").await?;
+        let claire_id = Contact::create(&t, "", "claire@example.org").await?;
+        let chat_id = create_group_chat(&t, ProtectionStatus::Unprotected, "foo").await?;
+        add_contact_to_chat(&t, chat_id, bob_id).await?;
+        add_contact_to_chat(&t, chat_id, claire_id).await?;
+        let chat = Chat::load_from_db(&t, chat_id).await?;
+        assert!(chat.get_read_horizons(&t).await?.is_empty());
+
+        let msg1 = t.send_text(chat_id, "one").await.sender_msg_id;
+        let msg2 = t.send_text(chat_id, "two").await.sender_msg_id;
+        let mid1 = Message::load_from_db(&t, msg1).await?.rfc724_mid;
+        let mid2 = Message::load_from_db(&t, msg2).await?.rfc724_mid;
+
+        // Bob has read only the first message, Claire has read both.
+        message::handle_mdn(&t, bob_id, &mid1, time()).await?;
+        message::handle_mdn(&t, claire_id, &mid1, time()).await?;
+        message::handle_mdn(&t, claire_id, &mid2, time()).await?;
+        assert_eq!(
+            chat.get_read_horizons(&t).await?,
+            vec![(bob_id, msg1), (claire_id, msg2)]
+        );
+
+        // Removed members have no read horizon.
+        remove_contact_from_chat(&t, chat_id, claire_id).await?;
+        assert_eq!(chat.get_read_horizons(&t).await?, vec![(bob_id, msg1)]);
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn test_get_last_msg_id() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        let chat_id = t
+            .create_chat_with_contact("bob", "bob@example.net")
+            .await
+            .id;
+        assert_eq!(chat_id.get_last_msg_id(&t).await?, None);
+
+        send_text_msg(&t, chat_id, "one".to_string()).await?;
+        let msg_id = send_text_msg(&t, chat_id, "two".to_string()).await?;
+        assert_eq!(chat_id.get_last_msg_id(&t).await?, Some(msg_id));
+
+        // Drafts are not visible messages.
+        let mut draft = Message::new(Viewtype::Text);
+        draft.set_text(Some("draft".to_string()));
+        chat_id.set_draft(&t, Some(&mut draft)).await?;
+        assert_eq!(chat_id.get_last_msg_id(&t).await?, Some(msg_id));
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn test_delete_messages_older_than() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        let chat_id = t
+            .create_chat_with_contact("bob", "bob@example.net")
+            .await
+            .id;
+
+        let mut msg_ids = Vec::new();
+        for (text, timestamp) in [("old", 1000), ("older", 500), ("new", 3000)] {
+            let msg_id = send_text_msg(&t, chat_id, text.to_string()).await?;
+            t.sql
+                .execute(
+                    "UPDATE msgs SET timestamp=? WHERE id=?",
+                    paramsv![timestamp, msg_id],
+                )
+                .await?;
+            msg_ids.push(msg_id);
+        }
+        let mut draft = Message::new(Viewtype::Text);
+        draft.set_text(Some("draft".to_string()));
+        chat_id.set_draft(&t, Some(&mut draft)).await?;
+        t.sql
+            .execute(
+                "UPDATE msgs SET timestamp=1 WHERE chat_id=? AND state=?",
+                paramsv![chat_id, MessageState::OutDraft],
+            )
+            .await?;
+
+        assert_eq!(chat_id.delete_messages_older_than(&t, 2000).await?, 2);
+        for msg_id in &msg_ids[..2] {
+            let msg = Message::load_from_db(&t, *msg_id).await?;
+            assert_eq!(msg.chat_id, DC_CHAT_ID_TRASH);
+        }
+        let msg = Message::load_from_db(&t, msg_ids[2]).await?;
+        assert_eq!(msg.chat_id, chat_id);
+        assert!(chat_id.get_draft(&t).await?.is_some());
+
+        assert_eq!(chat_id.delete_messages_older_than(&t, 2000).await?, 0);
+        assert!(DC_CHAT_ID_TRASH
+            .delete_messages_older_than(&t, 2000)
+            .await
+            .is_err());
+        Ok(())
+    }
+
     #[async_std::test]
     async fn test_marknoticed_chat() -> Result<()> {
         let t = TestContext::new_alice().await;
@@ -4926,6 +6222,8 @@ async fn test_chat_get_color() -> Result<()> {
         let chat_id = create_group_chat(&t, ProtectionStatus::Unprotected, "a chat").await?;
         let color1 = Chat::load_from_db(&t, chat_id).await?.get_color(&t).await?;
         assert_eq!(color1, 0x008772);
+        let chat = Chat::load_from_db(&t, chat_id).await?;
+        assert_eq!(chat.get_color(&t).await?, color1);
 
         // upper-/lowercase makes a difference for the colors, these are different groups
         // (in contrast to email addresses, where upper-/lowercase is ignored in practise)
@@ -4936,6 +6234,40 @@ async fn test_chat_get_color() -> Result<()> {
         Ok(())
     }
 
+    #[async_std::test]
+    async fn test_set_sort_timestamp() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        let chat_id = t
+            .create_chat_with_contact("bob", "bob@example.net")
+            .await
+            .id;
+        let new_id = t.send_text(chat_id, "new").await.sender_msg_id;
+
+        let mut msg = Message::new(Viewtype::Text);
+        msg.set_text(Some("bridged".to_string()));
+        msg.set_sort_timestamp(1_500_000_000);
+        let bridged_id = t.send_msg(chat_id, &mut msg).await.sender_msg_id;
+        let bridged = Message::load_from_db(&t, bridged_id).await?;
+        assert_eq!(bridged.get_sort_timestamp(), 1_500_000_000);
+        assert_eq!(
+            get_chat_msgs(&t, chat_id, 0).await?,
+            vec![
+                ChatItem::Message { msg_id: bridged_id },
+                ChatItem::Message { msg_id: new_id }
+            ]
+        );
+
+        // Timestamps in the future are limited.
+        let mut msg = Message::new(Viewtype::Text);
+        msg.set_text(Some("future".to_string()));
+        msg.set_sort_timestamp(time() + 100 * 24 * 60 * 60);
+        let future_id = t.send_msg(chat_id, &mut msg).await.sender_msg_id;
+        let future = Message::load_from_db(&t, future_id).await?;
+        assert!(future.get_sort_timestamp() <= time() + FUTURE_TIMESTAMP_TOLERANCE);
+
+        Ok(())
+    }
+
     async fn test_sticker(filename: &str, bytes: &[u8], w: i32, h: i32) -> Result<()> {
         let alice = TestContext::new_alice().await;
         let bob = TestContext::new_bob().await;
@@ -5027,6 +6359,36 @@ async fn test_sticker_forward() -> Result<()> {
         Ok(())
     }
 
+    #[async_std::test]
+    async fn test_max_attachment_size_outgoing() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        let chat_id = t.get_self_chat().await.id;
+        let file = t.get_blobdir().join("file.txt");
+        File::create(&file).await?.write_all(&[b'x'; 1000]).await?;
+
+        t.set_config(Config::MaxAttachmentSizeOutgoing, Some("999"))
+            .await?;
+        let mut msg = Message::new(Viewtype::File);
+        msg.set_file(file.to_str().unwrap(), None);
+        let err = prepare_msg(&t, chat_id, &mut msg).await.unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("exceeding the limit of 999 bytes set by max_attachment_size_outgoing"));
+
+        t.set_config(Config::MaxAttachmentSizeOutgoing, Some("1000"))
+            .await?;
+        let mut msg = Message::new(Viewtype::File);
+        msg.set_file(file.to_str().unwrap(), None);
+        prepare_msg(&t, chat_id, &mut msg).await?;
+
+        t.set_config(Config::MaxAttachmentSizeOutgoing, Some("0"))
+            .await?;
+        let mut msg = Message::new(Viewtype::File);
+        msg.set_file(file.to_str().unwrap(), None);
+        prepare_msg(&t, chat_id, &mut msg).await?;
+        Ok(())
+    }
+
     #[async_std::test]
     async fn test_forward() -> Result<()> {
         let alice = TestContext::new_alice().await;
@@ -5159,7 +6521,7 @@ async fn test_only_minimal_data_are_forwarded() -> Result<()> {
         let single_id = ChatId::create_for_contact(&bob, claire_id).await?;
         let group_id = create_group_chat(&bob, ProtectionStatus::Unprotected, "group2").await?;
         add_contact_to_chat(&bob, group_id, claire_id).await?;
-        let broadcast_id = create_broadcast_list(&bob).await?;
+        let broadcast_id = create_broadcast_list(&bob, "").await?;
         add_contact_to_chat(&bob, broadcast_id, claire_id).await?;
         for chat_id in &[single_id, group_id, broadcast_id] {
             forward_msgs(&bob, &[orig_msg.id], *chat_id).await?;
@@ -5228,6 +6590,74 @@ async fn test_resend_own_message() -> Result<()> {
         Ok(())
     }
 
+    #[async_std::test]
+    async fn test_resend_recent_to_contact() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+        let alice_grp = create_group_chat(&alice, ProtectionStatus::Unprotected, "grp").await?;
+        let bob_id = Contact::create(&alice, "", "bob@example.net").await?;
+        add_contact_to_chat(&alice, alice_grp, bob_id).await?;
+        alice.send_text(alice_grp, "first").await;
+        alice.send_text(alice_grp, "second").await;
+
+        // Alice adds Claire, messages sent afterwards were sent to Claire already.
+        let claire_id = Contact::create(&alice, "", "claire@example.org").await?;
+        add_contact_to_chat(&alice, alice_grp, claire_id).await?;
+        let member_added = alice.pop_sent_msg().await;
+        alice.send_text(alice_grp, "third").await;
+
+        async fn get_queued(t: &TestContext, min_id: i64) -> Result<Vec<(i64, String, String)>> {
+            t.sql
+                .query_map(
+                    "SELECT id, recipients, mime FROM smtp WHERE id>? ORDER BY id",
+                    paramsv![min_id],
+                    |row| {
+                        let id: i64 = row.get(0)?;
+                        let recipients: String = row.get(1)?;
+                        let mime: String = row.get(2)?;
+                        Ok((id, recipients, mime))
+                    },
+                    |rows| rows.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+                )
+                .await
+        }
+        let last_id = get_queued(&alice, 0).await?.last().unwrap().0;
+
+        resend_recent_to_contact(&alice, alice_grp, claire_id, 1).await?;
+        let queued = get_queued(&alice, last_id).await?;
+        assert_eq!(queued.len(), 1);
+        assert!(queued[0].2.contains("second"));
+        let last_id = queued[0].0;
+
+        resend_recent_to_contact(&alice, alice_grp, claire_id, 10).await?;
+        let queued = get_queued(&alice, last_id).await?;
+        assert_eq!(queued.len(), 2);
+        assert!(queued[0].2.contains("first"));
+        assert!(queued[1].2.contains("second"));
+
+        // Claire receives the resent messages in the group.
+        let claire = TestContext::new().await;
+        claire.configure_addr("claire@example.org").await;
+        let claire_grp = claire.recv_msg(&member_added).await.chat_id;
+        for (_, recipients, mime) in queued {
+            assert_eq!(recipients, "claire@example.org");
+            dc_receive_imf(&claire, mime.as_bytes(), false).await?;
+        }
+        assert_eq!(get_chat_msgs(&claire, claire_grp, 0).await?.len(), 3);
+
+        // Resending to self or to non-members fails.
+        assert!(
+            resend_recent_to_contact(&alice, alice_grp, ContactId::SELF, 1)
+                .await
+                .is_err()
+        );
+        let dave_id = Contact::create(&alice, "", "dave@example.org").await?;
+        assert!(resend_recent_to_contact(&alice, alice_grp, dave_id, 1)
+            .await
+            .is_err());
+
+        Ok(())
+    }
+
     #[async_std::test]
     async fn test_resend_foreign_message_fails() -> Result<()> {
         let alice = TestContext::new_alice().await;
@@ -5331,6 +6761,87 @@ async fn test_can_send_group() -> Result<()> {
                 .await?,
             false
         );
+        assert_eq!(
+            Chat::load_from_db(&alice, chat_id)
+                .await?
+                .why_cant_send(&alice)
+                .await?,
+            Some(CantSendReason::NotAMember)
+        );
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn test_announce_e2ee_change() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+        let bob = TestContext::new_bob().await;
+        let alice_chat_id = alice.create_chat(&bob).await.id;
+        bob.recv_msg(&alice.send_text(alice_chat_id, "hi").await)
+            .await;
+
+        // Without `AnnounceE2eeChange`, nothing is sent.
+        alice.set_config_bool(Config::E2eeEnabled, false).await?;
+        assert_eq!(
+            alice
+                .sql
+                .count("SELECT COUNT(*) FROM smtp", paramsv![])
+                .await?,
+            0
+        );
+        alice.set_config_bool(Config::E2eeEnabled, true).await?;
+
+        alice
+            .set_config_bool(Config::AnnounceE2eeChange, true)
+            .await?;
+        alice.set_config_bool(Config::E2eeEnabled, true).await?;
+        assert_eq!(
+            alice
+                .sql
+                .count("SELECT COUNT(*) FROM smtp", paramsv![])
+                .await?,
+            0
+        );
+
+        alice.set_config_bool(Config::E2eeEnabled, false).await?;
+        let sent = alice.pop_sent_msg().await;
+        assert!(sent
+            .payload()
+            .contains("Chat-Content: autocrypt-announcement"));
+        let msg = Message::load_from_db(&alice, sent.sender_msg_id).await?;
+        assert!(msg.hidden);
+
+        // Bob learns the new preference, but the message is not shown.
+        let bob_chat_id = bob.create_chat(&alice).await.id;
+        let bob_msg_cnt = get_chat_msgs(&bob, bob_chat_id, 0).await?.len();
+        let received = bob.recv_msg_opt(&sent).await.unwrap();
+        assert!(received.chat_id.is_trash());
+        assert_eq!(
+            get_chat_msgs(&bob, bob_chat_id, 0).await?.len(),
+            bob_msg_cnt
+        );
+        let peerstate = Peerstate::from_addr(&bob, "alice@example.org")
+            .await?
+            .unwrap();
+        assert_eq!(peerstate.prefer_encrypt, EncryptPreference::NoPreference);
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn test_why_cant_send() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+        let chat = alice
+            .create_chat_with_contact("Bob", "bob@example.net")
+            .await;
+        assert_eq!(chat.why_cant_send(&alice).await?, None);
+
+        alice.update_device_chats().await?;
+        let device_chat_id = ChatId::get_for_contact(&alice, ContactId::DEVICE).await?;
+        let device_chat = Chat::load_from_db(&alice, device_chat_id).await?;
+        assert!(!device_chat.can_send(&alice).await?);
+        assert_eq!(
+            device_chat.why_cant_send(&alice).await?,
+            Some(CantSendReason::DeviceChat)
+        );
         Ok(())
     }
 
@@ -5350,7 +6861,7 @@ async fn test_broadcast() -> Result<()> {
         assert!(msg.get_showpadlock());
 
         // test broadcast list
-        let broadcast_id = create_broadcast_list(&alice).await?;
+        let broadcast_id = create_broadcast_list(&alice, "").await?;
         add_contact_to_chat(
             &alice,
             broadcast_id,
@@ -5368,7 +6879,7 @@ async fn test_broadcast() -> Result<()> {
 
         let msg = bob.recv_msg(&alice.pop_sent_msg().await).await;
         assert_eq!(msg.get_text(), Some("ola!".to_string()));
-        assert!(!msg.get_showpadlock()); // avoid leaking recipients in encryption data
+        assert!(msg.get_showpadlock()); // every recipient gets a separately encrypted copy
         let chat = Chat::load_from_db(&bob, msg.chat_id).await?;
         assert_eq!(chat.typ, Chattype::Single);
         assert_eq!(chat.id, chat_bob.id);
@@ -5377,6 +6888,105 @@ async fn test_broadcast() -> Result<()> {
         Ok(())
     }
 
+    #[async_std::test]
+    async fn test_broadcast_separate_copies() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+        let bob = TestContext::new_bob().await;
+        let fiona = TestContext::new_fiona().await;
+
+        let broadcast_id = create_broadcast_list(&alice, "Friends").await?;
+        assert_eq!(
+            Chat::load_from_db(&alice, broadcast_id).await?.get_name(),
+            "Friends"
+        );
+        for t in [&bob, &fiona] {
+            // let Alice learn the key of the recipient
+            let chat = t.create_chat(&alice).await;
+            send_text_msg(t, chat.id, "hi".to_string()).await?;
+            alice.recv_msg(&t.pop_sent_msg().await).await;
+
+            let contact_id = alice.add_or_lookup_contact(t).await.id;
+            add_contact_to_chat(&alice, broadcast_id, contact_id).await?;
+        }
+
+        let msg_id = send_text_msg(&alice, broadcast_id, "hi all".to_string()).await?;
+        let mut rows = alice
+            .sql
+            .query_map(
+                "SELECT recipients, mime FROM smtp WHERE msg_id=?",
+                paramsv![msg_id],
+                |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
+                |rows| rows.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+            )
+            .await?;
+        assert_eq!(rows.len(), 2);
+        rows.sort();
+
+        let (recipients, mime) = rows.pop().unwrap();
+        assert_eq!(recipients, "fiona@example.net");
+        assert!(mime.contains("hidden-recipients"));
+        assert!(!mime.contains("bob@example.net"));
+        dc_receive_imf(&fiona, mime.as_bytes(), false).await?;
+        let msg = fiona.get_last_msg().await;
+        assert_eq!(msg.get_text(), Some("hi all".to_string()));
+        assert!(msg.get_showpadlock());
+
+        let (recipients, mime) = rows.pop().unwrap();
+        assert_eq!(recipients, "bob@example.net");
+        assert!(mime.contains("hidden-recipients"));
+        assert!(!mime.contains("fiona@example.net"));
+        dc_receive_imf(&bob, mime.as_bytes(), false).await?;
+        let msg = bob.get_last_msg().await;
+        assert_eq!(msg.get_text(), Some("hi all".to_string()));
+        assert!(msg.get_showpadlock());
+
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn test_broadcast_recipient_without_key() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+        let bob = TestContext::new_bob().await;
+
+        // Alice knows Bob's key, but not Fiona's one
+        let chat = bob.create_chat(&alice).await;
+        send_text_msg(&bob, chat.id, "hi".to_string()).await?;
+        alice.recv_msg(&bob.pop_sent_msg().await).await;
+
+        let broadcast_id = create_broadcast_list(&alice, "Friends").await?;
+        let bob_id = alice.add_or_lookup_contact(&bob).await.id;
+        add_contact_to_chat(&alice, broadcast_id, bob_id).await?;
+        let fiona_id = Contact::create(&alice, "", "fiona@example.net").await?;
+        add_contact_to_chat(&alice, broadcast_id, fiona_id).await?;
+
+        let msg_id = send_text_msg(&alice, broadcast_id, "hi all".to_string()).await?;
+        let rows = alice
+            .sql
+            .query_map(
+                "SELECT recipients, mime FROM smtp WHERE msg_id=? ORDER BY id",
+                paramsv![msg_id],
+                |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
+                |rows| rows.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+            )
+            .await?;
+        assert_eq!(rows.len(), 2);
+        assert!(rows
+            .iter()
+            .any(|(recipients, mime)| recipients == "bob@example.net"
+                && mime.contains("-----BEGIN PGP MESSAGE-----")));
+        assert!(
+            rows.iter()
+                .any(|(recipients, mime)| recipients == "fiona@example.net"
+                    && mime.contains("hi all"))
+        );
+
+        let msg = Message::load_from_db(&alice, msg_id).await?;
+        assert_eq!(msg.state, MessageState::OutPending);
+        assert!(!msg.param.get_bool(Param::GuaranteeE2ee).unwrap_or_default());
+
+        Ok(())
+    }
+
     #[async_std::test]
     async fn test_create_for_contact_with_blocked() -> Result<()> {
         let t = TestContext::new().await;
@@ -5465,4 +7075,58 @@ async fn test_chat_get_encryption_info() -> Result<()> {
 
         Ok(())
     }
+
+    #[async_std::test]
+    async fn test_chat_get_encryption_status() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+        let bob = TestContext::new_bob().await;
+
+        let contact_bob = Contact::create(&alice, "Bob", "bob@example.net").await?;
+        let contact_fiona = Contact::create(&alice, "", "fiona@example.net").await?;
+
+        let chat_id = create_group_chat(&alice, ProtectionStatus::Unprotected, "Group").await?;
+        add_contact_to_chat(&alice, chat_id, contact_bob).await?;
+        let chat = Chat::load_from_db(&alice, chat_id).await?;
+        assert_eq!(
+            chat.get_encryption_status(&alice).await?,
+            ChatEncryption::None
+        );
+
+        let direct_chat = bob.create_chat(&alice).await;
+        send_text_msg(&bob, direct_chat.id, "Hello!".to_string()).await?;
+        let alice_direct_chat = alice.recv_msg(&bob.pop_sent_msg().await).await.chat_id;
+        let alice_direct_chat = Chat::load_from_db(&alice, alice_direct_chat).await?;
+        assert_eq!(
+            alice_direct_chat.get_encryption_status(&alice).await?,
+            ChatEncryption::Opportunistic
+        );
+        assert_eq!(
+            chat.get_encryption_status(&alice).await?,
+            ChatEncryption::Opportunistic
+        );
+
+        // Fiona has no key, so messages to the group are not encrypted.
+        add_contact_to_chat(&alice, chat_id, contact_fiona).await?;
+        assert_eq!(
+            chat.get_encryption_status(&alice).await?,
+            ChatEncryption::None
+        );
+
+        let protected_chat_id =
+            create_group_chat(&alice, ProtectionStatus::Protected, "Verified group").await?;
+        let protected_chat = Chat::load_from_db(&alice, protected_chat_id).await?;
+        assert_eq!(
+            protected_chat.get_encryption_status(&alice).await?,
+            ChatEncryption::Verified
+        );
+
+        let device_chat_id = ChatId::get_for_contact(&alice, ContactId::DEVICE).await?;
+        let device_chat = Chat::load_from_db(&alice, device_chat_id).await?;
+        assert_eq!(
+            device_chat.get_encryption_status(&alice).await?,
+            ChatEncryption::None
+        );
+
+        Ok(())
+    }
 }
@@ -9,6 +9,8 @@
 };
 use crate::contact::{Contact, ContactId};
 use crate::context::Context;
+use crate::dc_receive_imf::FUTURE_TIMESTAMP_TOLERANCE;
+use crate::dc_tools::time;
 use crate::message::{Message, MessageState, MsgId};
 use crate::stock_str;
 use crate::summary::Summary;
@@ -118,6 +120,9 @@ pub async fn try_load(
         // - `GROUP BY` is needed several messages may have the same
         //   timestamp
         // - the list starts with the newest chats
+        // - drafts (`state=OutDraft`) are included, so a chat is sorted by
+        //   the newer of its last message and its last draft edit;
+        //   on equal timestamps, the newer message id comes first
         //
         // The query shows messages from blocked contacts in
         // groups. Otherwise it would be hard to follow conversations.
@@ -354,6 +359,89 @@ pub fn iter(&self) -> impl Iterator<Item = &(ChatId, Option<MsgId>)> {
     }
 }
 
+/// Predicates for [`Context::get_all_chat_ids`].
+///
+/// All set predicates have to match.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ChatFilter {
+    /// Only return group chats.
+    pub groups_only: bool,
+
+    /// Only return chats with fresh messages.
+    pub unread_only: bool,
+
+    /// Only return chats where location streaming is enabled.
+    pub sending_locations_only: bool,
+}
+
+impl Context {
+    /// Returns the IDs of all non-blocked chats matching `filter`, newest chats first.
+    ///
+    /// Unlike [`Chatlist::try_load`], this does not load last messages
+    /// and does not add special chats.
+    pub async fn get_all_chat_ids(&self, filter: ChatFilter) -> Result<Vec<ChatId>> {
+        self.sql
+            .query_map(
+                "SELECT c.id FROM chats c
+                 WHERE c.id>9 AND c.blocked!=?1
+                 AND (?2=0 OR c.type=?3)
+                 AND (?4=0 OR EXISTS
+                      (SELECT 1 FROM msgs m WHERE m.chat_id=c.id AND m.state=?5 AND m.hidden=0))
+                 AND (?6=0 OR c.locations_send_until>?7)
+                 ORDER BY c.id DESC",
+                paramsv![
+                    Blocked::Yes,
+                    filter.groups_only,
+                    Chattype::Group,
+                    filter.unread_only,
+                    MessageState::InFresh,
+                    filter.sending_locations_only,
+                    time()
+                ],
+                |row| row.get::<_, ChatId>(0),
+                |ids| ids.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+            )
+            .await
+    }
+
+    /// Repairs the data the chatlist is sorted by,
+    /// eg. after a crash or after the database was edited manually.
+    ///
+    /// The chatlist is sorted by the timestamps of the last messages,
+    /// so messages dated more than an hour in the future
+    /// would stick their chats to the top.
+    /// These messages are dated back to their sending time if known and not in the future,
+    /// otherwise to now.
+    /// Chats with messages get a creation timestamp not later than their first message
+    /// and the indexes of the chats and messages tables are rebuilt.
+    pub async fn rebuild_chatlist_index(&self) -> Result<()> {
+        let now = time();
+        self.sql
+            .transaction(move |transaction| {
+                transaction.execute(
+                    "UPDATE msgs
+                     SET timestamp=CASE WHEN timestamp_sent>0 THEN MIN(timestamp_sent, ?1) ELSE ?1 END
+                     WHERE timestamp>?2",
+                    paramsv![now, now.saturating_add(FUTURE_TIMESTAMP_TOLERANCE)],
+                )?;
+                transaction.execute(
+                    "UPDATE chats
+                     SET created_timestamp=(SELECT MIN(timestamp) FROM msgs WHERE chat_id=chats.id)
+                     WHERE id>9
+                       AND EXISTS (SELECT 1 FROM msgs WHERE chat_id=chats.id)
+                       AND (created_timestamp=0
+                            OR created_timestamp>(SELECT MIN(timestamp) FROM msgs WHERE chat_id=chats.id))",
+                    paramsv![],
+                )?;
+                transaction.execute_batch("REINDEX chats; REINDEX msgs;")?;
+                Ok(())
+            })
+            .await?;
+        self.emit_msgs_changed_without_ids();
+        Ok(())
+    }
+}
+
 /// Returns the number of archived chats
 pub async fn dc_get_archived_cnt(context: &Context) -> Result<usize> {
     let count = context
@@ -370,12 +458,104 @@ pub async fn dc_get_archived_cnt(context: &Context) -> Result<usize> {
 mod tests {
     use super::*;
 
-    use crate::chat::{create_group_chat, get_chat_contacts, ProtectionStatus};
+    use crate::chat::{self, create_group_chat, get_chat_contacts, ProtectionStatus};
     use crate::dc_receive_imf::dc_receive_imf;
     use crate::message::Viewtype;
     use crate::stock_str::StockMessage;
     use crate::test_utils::TestContext;
 
+    #[async_std::test]
+    async fn test_get_all_chat_ids() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+        let bob = TestContext::new_bob().await;
+
+        let bob_chat_id = bob.create_chat(&alice).await.id;
+        let alice_single_chat_id = alice
+            .recv_msg(&bob.send_text(bob_chat_id, "hi").await)
+            .await
+            .chat_id;
+        let bob_group_id = create_group_chat(&bob, ProtectionStatus::Unprotected, "group").await?;
+        let alice_id = bob.add_or_lookup_contact(&alice).await.id;
+        chat::add_contact_to_chat(&bob, bob_group_id, alice_id).await?;
+        let alice_unread_group_id = alice
+            .recv_msg(&bob.send_text(bob_group_id, "hello group").await)
+            .await
+            .chat_id;
+        let alice_read_group_id =
+            create_group_chat(&alice, ProtectionStatus::Unprotected, "read").await?;
+
+        let all = alice.get_all_chat_ids(ChatFilter::default()).await?;
+        assert!(all.contains(&alice_single_chat_id));
+        assert!(all.contains(&alice_unread_group_id));
+        assert!(all.contains(&alice_read_group_id));
+
+        let groups = alice
+            .get_all_chat_ids(ChatFilter {
+                groups_only: true,
+                ..Default::default()
+            })
+            .await?;
+        assert_eq!(groups, vec![alice_read_group_id, alice_unread_group_id]);
+
+        let unread_groups = alice
+            .get_all_chat_ids(ChatFilter {
+                groups_only: true,
+                unread_only: true,
+                ..Default::default()
+            })
+            .await?;
+        assert_eq!(unread_groups, vec![alice_unread_group_id]);
+
+        let sending_locations = alice
+            .get_all_chat_ids(ChatFilter {
+                sending_locations_only: true,
+                ..Default::default()
+            })
+            .await?;
+        assert!(sending_locations.is_empty());
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn test_rebuild_chatlist_index() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        let mut chat_ids = Vec::new();
+        for (i, addr) in ["bob@example.net", "claire@example.org"].iter().enumerate() {
+            let msg = format!(
+                "From: {}\n\
+                 To: alice@example.org\n\
+                 Message-ID: <rebuild{}@example.net>\n\
+                 Chat-Version: 1.0\n\
+                 Date: Sun, 22 Mar 2020 22:3{}:57 +0000\n\
+                 \n\
+                 hello\n",
+                addr, i, i
+            );
+            dc_receive_imf(&t, msg.as_bytes(), false).await?;
+            chat_ids.push(t.get_last_msg().await.chat_id);
+        }
+        let claire_chat_id = chat_ids.pop().unwrap();
+        let bob_chat_id = chat_ids.pop().unwrap();
+        let chats = Chatlist::try_load(&t, 0, None, None).await?;
+        assert_eq!(chats.get_chat_id(0)?, claire_chat_id);
+
+        // Date Bob's message into the future, his chat sticks to the top.
+        t.sql
+            .execute(
+                "UPDATE msgs SET timestamp=? WHERE chat_id=?",
+                paramsv![time() + 365 * 24 * 60 * 60, bob_chat_id],
+            )
+            .await?;
+        let chats = Chatlist::try_load(&t, 0, None, None).await?;
+        assert_eq!(chats.get_chat_id(0)?, bob_chat_id);
+
+        t.rebuild_chatlist_index().await?;
+        let chats = Chatlist::try_load(&t, 0, None, None).await?;
+        assert_eq!(chats.get_chat_id(0)?, claire_chat_id);
+        assert_eq!(chats.get_chat_id(1)?, bob_chat_id);
+        Ok(())
+    }
+
     #[async_std::test]
     async fn test_try_load() {
         let t = TestContext::new().await;
@@ -432,6 +612,51 @@ async fn test_try_load() {
         assert_eq!(chats.len(), 1);
     }
 
+    #[async_std::test]
+    async fn test_sort_draft_up() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        let old_chat_id = t
+            .create_chat_with_contact("bob", "bob@example.net")
+            .await
+            .id;
+        let new_chat_id = t
+            .create_chat_with_contact("claire", "claire@example.org")
+            .await
+            .id;
+        t.send_text(old_chat_id, "old message").await;
+        let new_msg = t.send_text(new_chat_id, "new message").await;
+        // Smeared timestamps of sent messages may be ahead of the current time,
+        // move them to the past as if they were sent a while ago.
+        t.sql
+            .execute("UPDATE msgs SET timestamp=timestamp-60", paramsv![])
+            .await?;
+
+        let chats = Chatlist::try_load(&t, 0, None, None).await?;
+        assert_eq!(chats.get_chat_id(0)?, new_chat_id);
+        assert_eq!(chats.get_chat_id(1)?, old_chat_id);
+        assert_eq!(old_chat_id.get_draft_timestamp(&t).await?, None);
+
+        // Editing a draft in the old chat sorts it to the top.
+        let mut msg = Message::new(Viewtype::Text);
+        msg.set_text(Some("draft".to_string()));
+        old_chat_id.set_draft(&t, Some(&mut msg)).await?;
+        let draft_timestamp = old_chat_id.get_draft_timestamp(&t).await?.unwrap();
+        let new_msg = Message::load_from_db(&t, new_msg.sender_msg_id).await?;
+        assert!(draft_timestamp > new_msg.get_timestamp());
+
+        let chats = Chatlist::try_load(&t, 0, None, None).await?;
+        assert_eq!(chats.get_chat_id(0)?, old_chat_id);
+        assert_eq!(chats.get_chat_id(1)?, new_chat_id);
+
+        // Deleting the draft restores the previous order.
+        old_chat_id.set_draft(&t, None).await?;
+        assert_eq!(old_chat_id.get_draft_timestamp(&t).await?, None);
+        let chats = Chatlist::try_load(&t, 0, None, None).await?;
+        assert_eq!(chats.get_chat_id(0)?, new_chat_id);
+
+        Ok(())
+    }
+
     #[async_std::test]
     async fn test_sort_self_talk_up_on_forward() {
         let t = TestContext::new().await;
@@ -1,17 +1,24 @@
 //! # Key-value configuration management.
 
-use anyhow::{ensure, Context as _, Result};
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::{ensure, format_err, Context as _, Result};
+use async_std::path::PathBuf;
 use strum::{EnumProperty, IntoEnumIterator};
 use strum_macros::{AsRefStr, Display, EnumIter, EnumProperty, EnumString};
 
 use crate::blob::BlobObject;
+use crate::chat;
 use crate::constants::DC_VERSION_STR;
 use crate::contact::addr_cmp;
 use crate::context::Context;
-use crate::dc_tools::{dc_get_abs_path, improve_single_line_input, EmailAddress};
+use crate::dc_tools::{dc_get_abs_path, dc_read_file, improve_single_line_input, EmailAddress};
 use crate::events::EventType;
+use crate::login_param::Socks5Config;
 use crate::mimefactory::RECOMMENDED_FILE_SIZE;
 use crate::provider::{get_provider_by_id, Provider};
+use crate::sync::SyncData;
 
 /// The available configuration keys.
 #[derive(
@@ -58,12 +65,37 @@ pub enum Config {
     #[strum(props(default = "0"))]
     BccSelf,
 
+    /// Folder the copies of sent chat messages are moved to, eg. `Delta Chat Sent`.
+    /// If unset, the copies are handled like other chat messages, see [`Config::MvboxMove`].
+    ///
+    /// The folder is not watched, so copies are downloaded from the folder
+    /// they arrive in before they are moved.
+    BccSelfFolder,
+
     #[strum(props(default = "1"))]
     E2eeEnabled,
 
+    /// If set to "1", changing [`Config::E2eeEnabled`] sends a hidden message
+    /// to recently active 1:1 chats, so that the peers learn the new Autocrypt preference.
+    #[strum(props(default = "0"))]
+    AnnounceE2eeChange,
+
+    /// Send and request read receipts.
+    ///
+    /// Used as the default for [`Config::RequestReceipts`] and [`Config::SendReceipts`].
     #[strum(props(default = "1"))]
     MdnsEnabled,
 
+    /// Request read receipts for outgoing messages.
+    ///
+    /// If unset, [`Config::MdnsEnabled`] is used.
+    RequestReceipts,
+
+    /// Send read receipts for seen messages if the sender requested them.
+    ///
+    /// If unset, [`Config::MdnsEnabled`] is used.
+    SendReceipts,
+
     #[strum(props(default = "0"))]
     SentboxWatch,
 
@@ -77,6 +109,14 @@ pub enum Config {
     #[strum(props(default = "0"))]
     OnlyFetchMvbox,
 
+    /// Do not watch or fetch the "Mvbox" (aka DeltaChat folder) at all,
+    /// even if it exists.
+    ///
+    /// Messages are received via the Inbox only.
+    /// This overrides [`Config::MvboxMove`] and [`Config::OnlyFetchMvbox`].
+    #[strum(props(default = "0"))]
+    DisableMvbox,
+
     #[strum(props(default = "0"))] // also change ShowEmails.default() on changes
     ShowEmails,
 
@@ -97,6 +137,20 @@ pub enum Config {
     #[strum(props(default = "0"))]
     KeyGenType,
 
+    /// Number of days after which a new self keypair is generated.
+    ///
+    /// Old keys are kept to decrypt existing messages.
+    /// Equals to 0 by default, which means keys are never rotated.
+    #[strum(props(default = "0"))]
+    KeyRotationDays,
+
+    /// Number of days after which keys are gossiped again in group chats.
+    ///
+    /// Independently of this setting, keys are gossiped when members are added.
+    /// If set to 0, keys are gossiped with every message.
+    #[strum(props(default = "2"))]
+    GossipPeriodDays,
+
     /// Timer in seconds after which the message is deleted from the
     /// server.
     ///
@@ -180,12 +234,149 @@ pub enum Config {
     #[strum(props(default = "0"))]
     DownloadLimit,
 
+    /// Defines the max. size (in bytes) of attachments of outgoing messages,
+    /// larger attachments are rejected before sending.
+    /// 0 = no limit.
+    #[strum(props(default = "0"))]
+    MaxAttachmentSizeOutgoing,
+
+    /// If set to "1", fresh messages mentioning the user are returned by
+    /// `Context::get_fresh_msgs()` even if the chat is muted.
+    #[strum(props(default = "0"))]
+    NotifyOnMention,
+
+    /// How much of a message is returned by `Context::get_notification_text()`,
+    /// see [`crate::constants::NotificationPreview`].
+    #[strum(props(default = "0"))] // also change NotificationPreview.default() on changes
+    NotificationPreview,
+
+    /// Whether groups from senders without an accepted chat are created
+    /// as accepted chats instead of contact requests,
+    /// see [`crate::constants::AutoAcceptGroupInvites`].
+    #[strum(props(default = "0"))] // also change AutoAcceptGroupInvites.default() on changes
+    AutoAcceptGroupInvites,
+
     /// Send sync messages, requires `BccSelf` to be set as well.
     /// In a future versions, this switch may be removed.
     #[strum(props(default = "0"))]
     SendSyncMsgs,
+
+    /// Comma-separated list of config keys that are never synchronized to other devices,
+    /// eg. `displayname,show_emails`.
+    SyncExcludedKeys,
+
+    /// Language of the account as BCP 47 tag, eg. `en` or `pt-BR`.
+    ///
+    /// Core does not load translations by itself, this is done by the UI
+    /// using `set_stock_translation()`.
+    /// The tag only records the language the stock strings are intended to be in.
+    Language,
+
+    /// Lifetime of Secure-Join QR code tokens in seconds.
+    /// Expired tokens are replaced when a QR code is generated the next time
+    /// and handshakes started with expired tokens are denied.
+    /// 0 = tokens never expire.
+    #[strum(props(default = "0"))]
+    QrTokenTtl,
+}
+
+impl Config {
+    /// Whether changes of this key are synchronized to other devices
+    /// if [`Config::SendSyncMsgs`] is enabled.
+    pub(crate) fn is_synced(&self) -> bool {
+        matches!(
+            self,
+            Self::Displayname
+                | Self::Selfstatus
+                | Self::MdnsEnabled
+                | Self::RequestReceipts
+                | Self::SendReceipts
+                | Self::ShowEmails
+                | Self::MediaQuality
+        )
+    }
+}
+
+/// Returns true if `tag` looks like a BCP 47 language tag,
+/// ie. a primary language subtag of 2-8 letters
+/// followed by subtags of 1-8 letters or digits, separated by `-`.
+fn is_language_tag(tag: &str) -> bool {
+    let mut subtags = tag.split('-');
+    let primary = subtags.next().unwrap_or_default();
+    (2..=8).contains(&primary.len())
+        && primary.chars().all(|c| c.is_ascii_alphabetic())
+        && subtags.all(|subtag| {
+            (1..=8).contains(&subtag.len()) && subtag.chars().all(|c| c.is_ascii_alphanumeric())
+        })
+}
+
+/// Checks `value` for `key` and returns the value to store.
+fn check_config_value(key: Config, value: Option<&str>) -> Result<Option<String>> {
+    match key {
+        Config::Displayname => Ok(value.map(improve_single_line_input)),
+        Config::Language => {
+            let value = value.map(str::trim).filter(|tag| !tag.is_empty());
+            if let Some(tag) = value {
+                ensure!(is_language_tag(tag), "Invalid language tag {:?}", tag);
+            }
+            Ok(value.map(|tag| tag.to_string()))
+        }
+        Config::SyncExcludedKeys => {
+            for name in value.unwrap_or_default().split(',') {
+                let name = name.trim();
+                ensure!(
+                    name.is_empty() || Config::from_str(name).is_ok(),
+                    "Unknown config key {:?} in sync_excluded_keys",
+                    name
+                );
+            }
+            Ok(value.map(|value| value.to_string()))
+        }
+        _ => Ok(value.map(|value| value.to_string())),
+    }
 }
 
+/// Config changes staged by [`Context::with_config_transaction`].
+#[derive(Debug, Default)]
+pub struct ConfigTx {
+    /// Staged keys with the passed and the checked value.
+    changes: Vec<(Config, Option<String>, Option<String>)>,
+    /// The first error, if any, makes the whole transaction fail.
+    error: Option<anyhow::Error>,
+}
+
+impl ConfigTx {
+    /// Stages setting `key` to `value`, see [`Context::set_config`].
+    ///
+    /// The value is checked immediately; if it is invalid,
+    /// no change of the transaction is applied.
+    /// [`Config::Selfavatar`] cannot be set in a transaction.
+    pub fn set(&mut self, key: Config, value: Option<&str>) -> &mut Self {
+        if self.error.is_some() {
+            return self;
+        }
+        if key == Config::Selfavatar {
+            self.error = Some(format_err!("{} cannot be set in a transaction", key));
+            return self;
+        }
+        match check_config_value(key, value) {
+            Ok(checked) => self
+                .changes
+                .push((key, value.map(|value| value.to_string()), checked)),
+            Err(err) => self.error = Some(err),
+        }
+        self
+    }
+
+    /// Stages setting a boolean `key`, see [`ConfigTx::set`].
+    pub fn set_bool(&mut self, key: Config, value: bool) -> &mut Self {
+        self.set(key, if value { Some("1") } else { Some("0") })
+    }
+}
+
+/// Timeout for checking SOCKS5 proxies in [`Context::set_config_validated`].
+const SOCKS5_CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
 impl Context {
     pub async fn config_exists(&self, key: Config) -> Result<bool> {
         Ok(self.sql.get_raw_config(key).await?.is_some())
@@ -215,6 +406,20 @@ pub async fn get_config(&self, key: Config) -> Result<Option<String>> {
         }
     }
 
+    /// Returns the path and the content of the self avatar in the blobdir,
+    /// or `None` if no avatar is set.
+    ///
+    /// The avatar is already recoded to the avatar size when it is set.
+    pub async fn get_self_avatar(&self) -> Result<Option<(PathBuf, Vec<u8>)>> {
+        let rel_path = match self.sql.get_raw_config(Config::Selfavatar).await? {
+            Some(rel_path) => rel_path,
+            None => return Ok(None),
+        };
+        let path = dc_get_abs_path(self, &rel_path);
+        let data = dc_read_file(self, &path).await?;
+        Ok(Some((path, data)))
+    }
+
     pub async fn get_config_int(&self, key: Config) -> Result<i32> {
         self.get_config(key)
             .await
@@ -237,7 +442,26 @@ pub async fn get_config_bool(&self, key: Config) -> Result<bool> {
         Ok(self.get_config_int(key).await? != 0)
     }
 
+    /// Returns true if read receipts should be requested for outgoing messages.
+    pub(crate) async fn should_request_mdns(&self) -> Result<bool> {
+        match self.get_config(Config::RequestReceipts).await? {
+            Some(value) => Ok(value.parse::<i32>().unwrap_or_default() != 0),
+            None => self.get_config_bool(Config::MdnsEnabled).await,
+        }
+    }
+
+    /// Returns true if read receipts should be sent for seen messages.
+    pub(crate) async fn should_send_mdns(&self) -> Result<bool> {
+        match self.get_config(Config::SendReceipts).await? {
+            Some(value) => Ok(value.parse::<i32>().unwrap_or_default() != 0),
+            None => self.get_config_bool(Config::MdnsEnabled).await,
+        }
+    }
+
     pub(crate) async fn should_watch_mvbox(&self) -> Result<bool> {
+        if self.get_config_bool(Config::DisableMvbox).await? {
+            return Ok(false);
+        }
         Ok(self.get_config_bool(Config::MvboxMove).await?
             || self.get_config_bool(Config::OnlyFetchMvbox).await?)
     }
@@ -276,9 +500,65 @@ pub async fn get_config_delete_device_after(&self) -> Result<Option<i64>> {
         }
     }
 
+    /// Returns the keys listed in [`Config::SyncExcludedKeys`].
+    pub(crate) async fn get_sync_excluded_keys(&self) -> Result<Vec<Config>> {
+        Ok(self
+            .get_config(Config::SyncExcludedKeys)
+            .await?
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|key| Config::from_str(key.trim()).ok())
+            .collect())
+    }
+
     /// Set the given config key.
     /// If `None` is passed as a value the value is cleared and set to the default if there is one.
+    ///
+    /// If the key is synchronized and not listed in [`Config::SyncExcludedKeys`],
+    /// the change is also queued to be sent to other devices.
     pub async fn set_config(&self, key: Config, value: Option<&str>) -> Result<()> {
+        self.set_config_ex(key, value, true).await
+    }
+
+    /// Same as [`Context::set_config`], but if `validate` is true and a SOCKS5 setting is changed
+    /// while SOCKS5 is enabled, checks that the proxy is reachable
+    /// and returns an error if it is not, so typos are noticed immediately.
+    ///
+    /// The value is stored even if the check fails.
+    /// As host, port and credentials are set one by one,
+    /// UIs should only validate when setting the last of them.
+    pub async fn set_config_validated(
+        &self,
+        key: Config,
+        value: Option<&str>,
+        validate: bool,
+    ) -> Result<()> {
+        self.set_config(key, value).await?;
+        if validate
+            && matches!(
+                key,
+                Config::Socks5Enabled
+                    | Config::Socks5Host
+                    | Config::Socks5Port
+                    | Config::Socks5User
+                    | Config::Socks5Password
+            )
+        {
+            if let Some(socks5_config) = Socks5Config::from_database(self).await? {
+                socks5_config.check(SOCKS5_CHECK_TIMEOUT).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Same as [`Context::set_config`], but the change is only queued for synchronization
+    /// if `sync` is true. This is set to false when executing received sync items.
+    pub(crate) async fn set_config_ex(
+        &self,
+        key: Config,
+        value: Option<&str>,
+        sync: bool,
+    ) -> Result<()> {
         match key {
             Config::Selfavatar => {
                 self.sql
@@ -299,23 +579,90 @@ pub async fn set_config(&self, key: Config, value: Option<&str>) -> Result<()> {
                 }
                 self.emit_event(EventType::SelfavatarChanged);
             }
+            Config::E2eeEnabled => {
+                let was_enabled = self.get_config_bool(key).await?;
+                self.sql.set_raw_config(key, value).await?;
+                self.e2ee_changed(was_enabled).await?;
+            }
             Config::DeleteDeviceAfter => {
                 let ret = self.sql.set_raw_config(key, value).await;
                 // Interrupt ephemeral loop to delete old messages immediately.
                 self.interrupt_ephemeral_task().await;
                 ret?
             }
-            Config::Displayname => {
-                let value = value.map(improve_single_line_input);
-                self.sql.set_raw_config(key, value.as_deref()).await?;
-            }
             _ => {
-                self.sql.set_raw_config(key, value).await?;
+                let checked = check_config_value(key, value)?;
+                self.sql.set_raw_config(key, checked.as_deref()).await?;
+            }
+        }
+        self.config_changed(key, value, sync).await
+    }
+
+    /// Announces a changed [`Config::E2eeEnabled`] if [`Config::AnnounceE2eeChange`] is set.
+    ///
+    /// The new value is already stored at this point,
+    /// so failing to send the announcement is only logged and does not fail the config change.
+    async fn e2ee_changed(&self, was_enabled: bool) -> Result<()> {
+        if self.get_config_bool(Config::E2eeEnabled).await? != was_enabled
+            && self.get_config_bool(Config::AnnounceE2eeChange).await?
+        {
+            if let Err(err) = chat::announce_e2ee_change(self).await {
+                warn!(self, "Cannot announce e2ee change: {:#}", err);
             }
         }
         Ok(())
     }
 
+    /// Queues a sync item for the changed `key` if needed and emits [`EventType::ConfigChanged`].
+    async fn config_changed(&self, key: Config, value: Option<&str>, sync: bool) -> Result<()> {
+        if sync && key.is_synced() && !self.get_sync_excluded_keys().await?.contains(&key) {
+            self.add_sync_item(SyncData::AlterConfig {
+                key: key.to_string(),
+                val: value.map(|val| val.to_string()),
+            })
+            .await?;
+        }
+        self.emit_event(EventType::ConfigChanged {
+            key: key.to_string(),
+        });
+        Ok(())
+    }
+
+    /// Sets several config keys at once.
+    ///
+    /// `f` stages the changes using [`ConfigTx::set`].
+    /// If all staged values are valid, they are stored in a single database transaction,
+    /// otherwise nothing is changed and the first error is returned.
+    /// This is useful for settings that only work together,
+    /// eg. server, port and security when switching providers.
+    pub async fn with_config_transaction(&self, f: impl FnOnce(&mut ConfigTx)) -> Result<()> {
+        let mut tx = ConfigTx::default();
+        f(&mut tx);
+        if let Some(err) = tx.error {
+            return Err(err);
+        }
+
+        let e2ee_was_enabled = self.get_config_bool(Config::E2eeEnabled).await?;
+        self.sql
+            .set_raw_configs(
+                tx.changes
+                    .iter()
+                    .map(|(key, _, checked)| (key.to_string(), checked.clone()))
+                    .collect(),
+            )
+            .await?;
+
+        for (key, value, _) in tx.changes.iter() {
+            match key {
+                Config::E2eeEnabled => self.e2ee_changed(e2ee_was_enabled).await?,
+                Config::DeleteDeviceAfter => self.interrupt_ephemeral_task().await,
+                _ => {}
+            }
+            self.config_changed(*key, value.as_deref(), true).await?;
+        }
+        Ok(())
+    }
+
     pub async fn set_config_bool(&self, key: Config, value: bool) -> Result<()> {
         self.set_config(key, if value { Some("1") } else { Some("0") })
             .await?;
@@ -433,7 +780,6 @@ fn get_config_keys_string() -> String {
 mod tests {
     use super::*;
 
-    use std::str::FromStr;
     use std::string::ToString;
 
     use crate::constants;
@@ -442,6 +788,149 @@ mod tests {
     use crate::test_utils::TestContextManager;
     use num_traits::FromPrimitive;
 
+    #[async_std::test]
+    async fn test_with_config_transaction() -> Result<()> {
+        let t = TestContext::new().await;
+        t.with_config_transaction(|tx| {
+            tx.set(Config::MailServer, Some("imap.example.org"))
+                .set(Config::MailPort, Some("993"))
+                .set(Config::Displayname, Some("  Alice\n"));
+        })
+        .await?;
+        assert_eq!(
+            t.get_config(Config::MailServer).await?,
+            Some("imap.example.org".to_string())
+        );
+        assert_eq!(t.get_config_int(Config::MailPort).await?, 993);
+        assert_eq!(
+            t.get_config(Config::Displayname).await?,
+            Some("Alice".to_string())
+        );
+
+        // If a later change is invalid, none of the changes is applied.
+        assert!(t
+            .with_config_transaction(|tx| {
+                tx.set(Config::MailServer, Some("imap.example.net"))
+                    .set(Config::MailPort, None)
+                    .set(Config::Language, Some("not a language tag"))
+                    .set(Config::SendServer, Some("smtp.example.net"));
+            })
+            .await
+            .is_err());
+        assert_eq!(
+            t.get_config(Config::MailServer).await?,
+            Some("imap.example.org".to_string())
+        );
+        assert_eq!(t.get_config_int(Config::MailPort).await?, 993);
+        assert_eq!(t.get_config(Config::SendServer).await?, None);
+
+        assert!(t
+            .with_config_transaction(|tx| {
+                tx.set(Config::Selfavatar, None);
+            })
+            .await
+            .is_err());
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn test_set_config_validated_socks5() -> Result<()> {
+        use async_std::net::TcpListener;
+        use async_std::prelude::*;
+
+        let t = TestContext::new_alice().await;
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let port = listener.local_addr()?.port().to_string();
+        async_std::task::spawn(async move {
+            // Minimal SOCKS5 proxy accepting clients without authentication.
+            while let Some(Ok(mut stream)) = listener.incoming().next().await {
+                let mut greeting = [0u8; 3];
+                if stream.read_exact(&mut greeting).await.is_ok() {
+                    stream.write_all(&[0x05, 0x00]).await.ok();
+                }
+            }
+        });
+
+        t.set_config(Config::Socks5Host, Some("127.0.0.1")).await?;
+        t.set_config(Config::Socks5Port, Some(&port)).await?;
+        t.set_config_validated(Config::Socks5Enabled, Some("1"), true)
+            .await?;
+
+        // Nothing is listening on a closed port.
+        let closed_port = TcpListener::bind("127.0.0.1:0")
+            .await?
+            .local_addr()?
+            .port()
+            .to_string();
+        assert!(t
+            .set_config_validated(Config::Socks5Port, Some(&closed_port), true)
+            .await
+            .is_err());
+        assert_eq!(t.get_config(Config::Socks5Port).await?, Some(closed_port));
+
+        // Without validation, the value is just stored.
+        t.set_config_validated(Config::Socks5Port, Some("1"), false)
+            .await?;
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn test_language() -> Result<()> {
+        let t = TestContext::new().await;
+        assert_eq!(t.get_config(Config::Language).await?, None);
+
+        t.set_config(Config::Language, Some("pt-BR")).await?;
+        assert_eq!(
+            t.get_config(Config::Language).await?,
+            Some("pt-BR".to_string())
+        );
+        assert_eq!(t.get_info().await?.get("language").unwrap(), "pt-BR");
+
+        t.set_config(Config::Language, Some(" de ")).await?;
+        assert_eq!(
+            t.get_config(Config::Language).await?,
+            Some("de".to_string())
+        );
+
+        for tag in &["x", "en_US", "en-", "de-toolongsubtag", "1a"] {
+            assert!(t.set_config(Config::Language, Some(tag)).await.is_err());
+        }
+        assert_eq!(
+            t.get_config(Config::Language).await?,
+            Some("de".to_string())
+        );
+
+        t.set_config(Config::Language, Some("")).await?;
+        assert_eq!(t.get_config(Config::Language).await?, None);
+
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn test_get_self_avatar() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        assert!(t.get_self_avatar().await?.is_none());
+
+        let avatar_src = t.dir.path().join("avatar.png");
+        let avatar_bytes = include_bytes!("../test-data/image/avatar64x64.png");
+        async_std::fs::write(&avatar_src, avatar_bytes).await?;
+        t.set_config(Config::Selfavatar, Some(avatar_src.to_str().unwrap()))
+            .await?;
+
+        let (path, data) = t.get_self_avatar().await?.unwrap();
+        assert!(path.starts_with(t.get_blobdir()));
+        assert_eq!(
+            Some(path.to_string_lossy().into_owned()),
+            t.get_config(Config::Selfavatar).await?
+        );
+        assert_eq!(data, std::fs::read(&path)?);
+        assert!(!data.is_empty());
+
+        t.set_config(Config::Selfavatar, None).await?;
+        assert!(t.get_self_avatar().await?.is_none());
+        Ok(())
+    }
+
     #[test]
     fn test_to_string() {
         assert_eq!(Config::MailServer.to_string(), "mail_server");
@@ -505,6 +994,32 @@ async fn test_set_config_bool() -> Result<()> {
         Ok(())
     }
 
+    #[async_std::test]
+    async fn test_config_changed_event() -> Result<()> {
+        let t = TestContext::new_alice().await;
+
+        t.set_config(Config::Displayname, Some("Alice Local"))
+            .await?;
+        t.evtracker
+            .get_matching(|e| matches!(e, EventType::ConfigChanged { key } if key == "displayname"))
+            .await;
+
+        // Changes synchronized from another device are reported as well.
+        let sync_items = t.parse_sync_items(
+            r#"{"items":[{"timestamp":1631781316,"data":{"AlterConfig":{"key":"selfstatus","val":"synced"}}}]}"#
+                .to_string(),
+        )?;
+        t.execute_sync_items(&sync_items).await?;
+        t.evtracker
+            .get_matching(|e| matches!(e, EventType::ConfigChanged { key } if key == "selfstatus"))
+            .await;
+        assert_eq!(
+            t.get_config(Config::Selfstatus).await?,
+            Some("synced".to_string())
+        );
+        Ok(())
+    }
+
     #[async_std::test]
     async fn test_self_addrs() -> Result<()> {
         let alice = TestContext::new_alice().await;
@@ -50,6 +50,51 @@ fn default() -> Self {
     }
 }
 
+/// Which group invites are accepted without showing a contact request,
+/// see [`Config::AutoAcceptGroupInvites`].
+///
+/// [`Config::AutoAcceptGroupInvites`]: crate::config::Config::AutoAcceptGroupInvites
+#[derive(
+    Debug, Display, Clone, Copy, PartialEq, Eq, FromPrimitive, ToPrimitive, FromSql, ToSql,
+)]
+#[repr(u8)]
+pub enum AutoAcceptGroupInvites {
+    /// Every new group is a contact request.
+    Off = 0,
+    /// Groups created by a verified contact are accepted.
+    VerifiedOnly = 1,
+    /// All groups are accepted.
+    All = 2,
+}
+
+impl Default for AutoAcceptGroupInvites {
+    fn default() -> Self {
+        AutoAcceptGroupInvites::Off // also change Config.AutoAcceptGroupInvites props(default) on changes
+    }
+}
+
+/// How much of a message is revealed by [`Context::get_notification_text`].
+///
+/// [`Context::get_notification_text`]: crate::context::Context::get_notification_text
+#[derive(
+    Debug, Display, Clone, Copy, PartialEq, Eq, FromPrimitive, ToPrimitive, FromSql, ToSql,
+)]
+#[repr(u8)]
+pub enum NotificationPreview {
+    /// Sender and message text.
+    Full = 0,
+    /// Only the sender.
+    SenderOnly = 1,
+    /// Neither sender nor message text.
+    None = 2,
+}
+
+impl Default for NotificationPreview {
+    fn default() -> Self {
+        NotificationPreview::Full // also change Config.NotificationPreview props(default) on changes
+    }
+}
+
 #[derive(
     Debug, Display, Clone, Copy, PartialEq, Eq, FromPrimitive, ToPrimitive, FromSql, ToSql,
 )]
@@ -110,6 +155,7 @@ fn default() -> Self {
 
 pub const DC_GCM_ADDDAYMARKER: u32 = 0x01;
 pub const DC_GCM_INFO_ONLY: u32 = 0x02;
+pub const DC_GCM_NO_INFO: u32 = 0x04;
 
 pub const DC_GCL_VERIFIED_ONLY: u32 = 0x01;
 pub const DC_GCL_ADD_SELF: u32 = 0x02;
@@ -273,6 +319,27 @@ fn test_showemails_values() {
         assert_eq!(ShowEmails::All, ShowEmails::from_i32(2).unwrap());
     }
 
+    #[test]
+    fn test_autoacceptgroupinvites_values() {
+        // values may be written to disk and must not change
+        assert_eq!(
+            AutoAcceptGroupInvites::Off,
+            AutoAcceptGroupInvites::default()
+        );
+        assert_eq!(
+            AutoAcceptGroupInvites::Off,
+            AutoAcceptGroupInvites::from_i32(0).unwrap()
+        );
+        assert_eq!(
+            AutoAcceptGroupInvites::VerifiedOnly,
+            AutoAcceptGroupInvites::from_i32(1).unwrap()
+        );
+        assert_eq!(
+            AutoAcceptGroupInvites::All,
+            AutoAcceptGroupInvites::from_i32(2).unwrap()
+        );
+    }
+
     #[test]
     fn test_blocked_values() {
         // values may be written to disk and must not change
@@ -1,5 +1,6 @@
 //! Contacts module
 
+use std::collections::BTreeMap;
 use std::convert::{TryFrom, TryInto};
 use std::fmt;
 
@@ -18,7 +19,7 @@
 use crate::context::Context;
 use crate::dc_tools::{dc_get_abs_path, improve_single_line_input, EmailAddress};
 use crate::events::EventType;
-use crate::key::{DcKey, SignedPublicKey};
+use crate::key::{DcKey, Fingerprint, SignedPublicKey};
 use crate::login_param::LoginParam;
 use crate::message::MessageState;
 use crate::mimeparser::AvatarAction;
@@ -1081,6 +1082,9 @@ pub async fn get_profile_image(&self, context: &Context) -> Result<Option<PathBu
     /// The color is calculated from the contact's email address
     /// and can be used for an fallback avatar with white initials
     /// as well as for headlines in bubbles of group chats.
+    ///
+    /// The address is lowercased and hashed as described in XEP-0392,
+    /// so all clients show the same color for the same address.
     pub fn get_color(&self) -> u32 {
         str_to_color(&self.addr.to_lowercase())
     }
@@ -1130,6 +1134,54 @@ pub async fn is_verified_ex(
         Ok(VerifiedStatus::Unverified)
     }
 
+    /// Returns true if a public key of the contact is known,
+    /// so that messages to the contact can be encrypted.
+    ///
+    /// For [`ContactId::SELF`], this is always true.
+    pub async fn has_key(&self, context: &Context) -> Result<bool> {
+        if self.id == ContactId::SELF {
+            return Ok(true);
+        }
+        let has_key = Peerstate::from_addr(context, &self.addr)
+            .await?
+            .map_or(false, |peerstate| {
+                peerstate
+                    .peek_key(PeerstateVerifiedStatus::Unverified)
+                    .is_some()
+            });
+        Ok(has_key)
+    }
+
+    /// Returns true if the key of the contact is verified.
+    ///
+    /// UIs may show a key badge instead of the email address for such contacts.
+    pub async fn is_verified_key_contact(&self, context: &Context) -> Result<bool> {
+        Ok(self.is_verified(context).await? == VerifiedStatus::BidirectVerified)
+    }
+
+    /// Returns the chats shared with the contact,
+    /// that is the 1:1 chat and all groups the contact is a member of.
+    ///
+    /// Groups that were left are not returned.
+    pub async fn get_chats(&self, context: &Context) -> Result<Vec<ChatId>> {
+        context
+            .sql
+            .query_map(
+                "SELECT c.id
+                   FROM chats c
+                  WHERE c.id>9 -- 9 = DC_CHAT_ID_LAST_SPECIAL
+                    AND c.id IN (SELECT chat_id FROM chats_contacts WHERE contact_id=?)
+                    AND (c.type=?
+                         OR (c.type=?
+                             AND c.id IN (SELECT chat_id FROM chats_contacts WHERE contact_id=?)))
+                  ORDER BY c.id",
+                paramsv![self.id, Chattype::Single, Chattype::Group, ContactId::SELF],
+                |row| row.get::<_, ChatId>(0),
+                |ids| ids.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+            )
+            .await
+    }
+
     pub async fn get_real_cnt(context: &Context) -> Result<usize> {
         if !context.sql.is_open().await {
             return Ok(0);
@@ -1336,8 +1388,9 @@ pub(crate) async fn set_status(
 ) -> Result<()> {
     if contact_id == ContactId::SELF {
         if encrypted && has_chat_version {
+            // The status comes from another device, so it is not synchronized back.
             context
-                .set_config(Config::Selfstatus, Some(&status))
+                .set_config_ex(Config::Selfstatus, Some(&status), false)
                 .await?;
         }
     } else {
@@ -1416,6 +1469,253 @@ fn cat_fingerprint(
     }
 }
 
+impl Context {
+    /// Returns the ID of the contact with the given address, if there is one.
+    ///
+    /// Unlike [`Contact::lookup_id_by_addr`], contacts are returned regardless of their origin
+    /// and blocking state. The address is compared as by [`addr_cmp`],
+    /// i.e. ignoring case, surrounding whitespace and a `mailto:` prefix.
+    /// No contact is created.
+    pub async fn lookup_contact_id_by_addr(&self, addr: &str) -> Result<Option<ContactId>> {
+        let addr_normalized = addr_normalize(addr);
+        if addr_normalized.is_empty() {
+            return Ok(None);
+        }
+        if self.is_self_addr(addr_normalized).await? {
+            return Ok(Some(ContactId::SELF));
+        }
+        self.sql
+            .query_get_value(
+                "SELECT id FROM contacts WHERE addr=? COLLATE NOCASE AND id>?",
+                paramsv![addr_normalized, ContactId::LAST_SPECIAL],
+            )
+            .await
+    }
+
+    /// Blocks the key with the given fingerprint.
+    ///
+    /// Incoming messages signed by a blocked key are not shown,
+    /// regardless of the address they are sent from.
+    pub async fn block_by_fingerprint(&self, fingerprint: &Fingerprint) -> Result<()> {
+        self.sql
+            .execute(
+                "INSERT OR IGNORE INTO blocked_fingerprints (fingerprint, timestamp) VALUES (?, ?)",
+                paramsv![fingerprint.hex(), time()],
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Unblocks the key with the given fingerprint.
+    ///
+    /// Messages signed by the key are received normally again.
+    pub async fn unblock_fingerprint(&self, fingerprint: &Fingerprint) -> Result<()> {
+        self.sql
+            .execute(
+                "DELETE FROM blocked_fingerprints WHERE fingerprint=?",
+                paramsv![fingerprint.hex()],
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Returns the fingerprints of all blocked keys, most recently blocked first.
+    pub async fn get_blocked_fingerprints(&self) -> Result<Vec<Fingerprint>> {
+        self.sql
+            .query_map(
+                "SELECT fingerprint FROM blocked_fingerprints ORDER BY timestamp DESC, fingerprint",
+                paramsv![],
+                |row| row.get::<_, String>(0),
+                |rows| {
+                    rows.map(|row| Ok(row?.parse::<Fingerprint>()?))
+                        .collect::<Result<Vec<_>>>()
+                },
+            )
+            .await
+    }
+
+    /// Returns true if any of the given fingerprints is blocked.
+    pub(crate) async fn is_any_fingerprint_blocked(
+        &self,
+        fingerprints: impl IntoIterator<Item = &Fingerprint>,
+    ) -> Result<bool> {
+        for fingerprint in fingerprints {
+            if self
+                .sql
+                .exists(
+                    "SELECT COUNT(*) FROM blocked_fingerprints WHERE fingerprint=?",
+                    paramsv![fingerprint.hex()],
+                )
+                .await?
+            {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Merges contacts whose addresses are equal according to [`addr_cmp`],
+    /// as they may be created eg. by imports.
+    ///
+    /// Of each set of duplicates, the contact with most messages is kept;
+    /// messages, chat memberships and other references are moved to it
+    /// and the other contacts are deleted.
+    /// Missing names are taken from the duplicates, the highest origin is kept
+    /// and the contact is blocked if any duplicate was blocked.
+    /// Messages of extra 1:1 chats are moved to the 1:1 chat of the kept contact.
+    /// Verification is stored per address and thus not affected.
+    ///
+    /// Returns the number of deleted contacts.
+    pub async fn dedupe_contacts(&self) -> Result<usize> {
+        let contacts = self
+            .sql
+            .query_map(
+                "SELECT c.id, c.addr, c.origin,
+                        (SELECT COUNT(*) FROM msgs m WHERE m.from_id=c.id OR m.to_id=c.id)
+                   FROM contacts c
+                  WHERE c.id>?
+                  ORDER BY c.id",
+                paramsv![ContactId::LAST_SPECIAL],
+                |row| {
+                    let id: ContactId = row.get(0)?;
+                    let addr: String = row.get(1)?;
+                    let origin: Origin = row.get(2)?;
+                    let msg_cnt: i64 = row.get(3)?;
+                    Ok((id, addr, origin, msg_cnt))
+                },
+                |rows| rows.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+            )
+            .await?;
+
+        let mut groups: BTreeMap<String, Vec<(ContactId, Origin, i64)>> = BTreeMap::new();
+        for (id, addr, origin, msg_cnt) in contacts {
+            groups
+                .entry(addr_normalize(&addr).to_lowercase())
+                .or_default()
+                .push((id, origin, msg_cnt));
+        }
+
+        let mut merged = 0;
+        for mut group in groups.into_values().filter(|group| group.len() > 1) {
+            // Most messages first; on ties, keep the oldest contact.
+            group.sort_by_key(|(id, _, msg_cnt)| (std::cmp::Reverse(*msg_cnt), *id));
+            let origin = group.iter().map(|(_, origin, _)| *origin).max();
+            let ((keep_id, _, _), duplicates) = match group.split_first() {
+                Some((keep, duplicates)) => (*keep, duplicates),
+                None => continue,
+            };
+            let duplicate_ids: Vec<ContactId> = duplicates.iter().map(|(id, _, _)| *id).collect();
+            merged += duplicate_ids.len();
+
+            self.sql
+                .transaction(move |transaction| {
+                    for duplicate_id in duplicate_ids {
+                        transaction.execute(
+                            "UPDATE contacts
+                                SET name=CASE WHEN name='' THEN (SELECT name FROM contacts WHERE id=?2) ELSE name END,
+                                    authname=CASE WHEN authname='' THEN (SELECT authname FROM contacts WHERE id=?2) ELSE authname END,
+                                    blocked=MAX(blocked, (SELECT blocked FROM contacts WHERE id=?2))
+                              WHERE id=?1",
+                            paramsv![keep_id, duplicate_id],
+                        )?;
+                        if let (Some(keep_chat_id), Some(duplicate_chat_id)) = (
+                            lookup_single_chat(transaction, keep_id)?,
+                            lookup_single_chat(transaction, duplicate_id)?,
+                        ) {
+                            transaction.execute(
+                                "UPDATE msgs SET chat_id=? WHERE chat_id=?",
+                                paramsv![keep_chat_id, duplicate_chat_id],
+                            )?;
+                            transaction.execute(
+                                "DELETE FROM chats_contacts WHERE chat_id=?",
+                                paramsv![duplicate_chat_id],
+                            )?;
+                            transaction.execute(
+                                "DELETE FROM chats WHERE id=?",
+                                paramsv![duplicate_chat_id],
+                            )?;
+                        }
+                        transaction.execute(
+                            "UPDATE msgs SET from_id=? WHERE from_id=?",
+                            paramsv![keep_id, duplicate_id],
+                        )?;
+                        transaction.execute(
+                            "UPDATE msgs SET to_id=? WHERE to_id=?",
+                            paramsv![keep_id, duplicate_id],
+                        )?;
+                        transaction.execute(
+                            "UPDATE locations SET from_id=? WHERE from_id=?",
+                            paramsv![keep_id, duplicate_id],
+                        )?;
+                        transaction.execute(
+                            "UPDATE msgs_mdns SET contact_id=? WHERE contact_id=?",
+                            paramsv![keep_id, duplicate_id],
+                        )?;
+                        transaction.execute(
+                            "UPDATE OR IGNORE mentions SET contact_id=? WHERE contact_id=?",
+                            paramsv![keep_id, duplicate_id],
+                        )?;
+                        transaction.execute(
+                            "DELETE FROM mentions WHERE contact_id=?",
+                            paramsv![duplicate_id],
+                        )?;
+                        // Do not add the kept contact twice to chats containing both.
+                        transaction.execute(
+                            "UPDATE chats_contacts SET contact_id=?1
+                              WHERE contact_id=?2
+                                AND chat_id NOT IN (SELECT chat_id FROM chats_contacts WHERE contact_id=?1)",
+                            paramsv![keep_id, duplicate_id],
+                        )?;
+                        transaction.execute(
+                            "DELETE FROM chats_contacts WHERE contact_id=?",
+                            paramsv![duplicate_id],
+                        )?;
+                        transaction.execute(
+                            "DELETE FROM contacts WHERE id=?",
+                            paramsv![duplicate_id],
+                        )?;
+                    }
+                    transaction.execute(
+                        "UPDATE contacts SET origin=? WHERE id=?",
+                        paramsv![origin, keep_id],
+                    )?;
+                    // The 1:1 chat of a blocked contact is blocked as well.
+                    transaction.execute(
+                        "UPDATE chats SET blocked=?
+                          WHERE type=?
+                            AND id IN (SELECT chat_id FROM chats_contacts WHERE contact_id=?3)
+                            AND (SELECT blocked FROM contacts WHERE id=?3)!=0",
+                        paramsv![Blocked::Yes, Chattype::Single, keep_id],
+                    )?;
+                    Ok(())
+                })
+                .await?;
+        }
+
+        if merged > 0 {
+            info!(self, "Merged {} duplicate contacts.", merged);
+            self.emit_event(EventType::ContactsChanged(None));
+        }
+        Ok(merged)
+    }
+}
+
+/// Returns the 1:1 chat with the contact, if any.
+fn lookup_single_chat(
+    transaction: &rusqlite::Transaction<'_>,
+    contact_id: ContactId,
+) -> rusqlite::Result<Option<ChatId>> {
+    let mut stmt = transaction.prepare(
+        "SELECT c.id FROM chats c
+           JOIN chats_contacts cc ON c.id=cc.chat_id
+          WHERE c.type=? AND cc.contact_id=?
+          ORDER BY c.id
+          LIMIT 1",
+    )?;
+    let mut rows = stmt.query(paramsv![Chattype::Single, contact_id])?;
+    rows.next()?.map(|row| row.get(0)).transpose()
+}
+
 pub fn addr_cmp(addr1: &str, addr2: &str) -> bool {
     let norm1 = addr_normalize(addr1).to_lowercase();
     let norm2 = addr_normalize(addr2).to_lowercase();
@@ -1443,10 +1743,12 @@ mod tests {
 
     use super::*;
 
-    use crate::chat::{get_chat_contacts, send_text_msg, Chat};
+    use crate::chat::{get_chat_contacts, send_text_msg, Chat, ProtectionStatus};
     use crate::chatlist::Chatlist;
+    use crate::constants::DC_CHAT_ID_TRASH;
     use crate::dc_receive_imf::dc_receive_imf;
     use crate::message::Message;
+    use crate::peerstate::PeerstateKeyType;
     use crate::test_utils::{self, TestContext};
 
     #[test]
@@ -2059,6 +2361,45 @@ async fn test_lookup_id_by_addr() {
         assert_eq!(id, Some(ContactId::SELF));
     }
 
+    #[async_std::test]
+    async fn test_lookup_contact_id_by_addr() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        assert_eq!(t.lookup_contact_id_by_addr("bob@example.net").await?, None);
+        assert_eq!(t.lookup_contact_id_by_addr("  ").await?, None);
+
+        let bob_id = Contact::create(&t, "Bob", "bob@example.net").await?;
+        for addr in [
+            "bob@example.net",
+            "Bob@Example.NET",
+            "  bob@example.net\n",
+            "mailto:BOB@example.net",
+        ] {
+            assert_eq!(t.lookup_contact_id_by_addr(addr).await?, Some(bob_id));
+        }
+        assert_eq!(t.lookup_contact_id_by_addr("bob@example.org").await?, None);
+
+        // Blocked contacts and contacts of any origin are found as well.
+        Contact::block(&t, bob_id).await?;
+        assert_eq!(
+            t.lookup_contact_id_by_addr("bob@example.net").await?,
+            Some(bob_id)
+        );
+        assert_eq!(
+            t.lookup_contact_id_by_addr("ALICE@example.org").await?,
+            Some(ContactId::SELF)
+        );
+        assert_eq!(
+            t.sql
+                .count(
+                    "SELECT COUNT(*) FROM contacts WHERE addr='bob@example.org'",
+                    paramsv![]
+                )
+                .await?,
+            0
+        );
+        Ok(())
+    }
+
     #[async_std::test]
     async fn test_contact_get_color() -> Result<()> {
         let t = TestContext::new().await;
@@ -2075,6 +2416,13 @@ async fn test_contact_get_color() -> Result<()> {
         let contact_id = Contact::create(&t, "Name", "nAme@exAmple.NET").await?;
         let color3 = Contact::get_by_id(&t, contact_id).await?.get_color();
         assert_eq!(color3, color1);
+
+        // the color is stable across calls and differs for different addresses
+        let contact = Contact::get_by_id(&t, contact_id).await?;
+        assert_eq!(contact.get_color(), contact.get_color());
+        let contact_id = Contact::create(&t, "name", "other@example.net").await?;
+        let color4 = Contact::get_by_id(&t, contact_id).await?.get_color();
+        assert_ne!(color4, color1);
         Ok(())
     }
 
@@ -2133,6 +2481,7 @@ async fn test_synchronize_status() -> Result<()> {
         let bob = TestContext::new_bob().await;
 
         let default_status = alice1.get_config(Config::Selfstatus).await?;
+        alice2.set_config_bool(Config::SendSyncMsgs, true).await?;
 
         alice1
             .set_config(Config::Selfstatus, Some("New status"))
@@ -2184,6 +2533,15 @@ async fn test_synchronize_status() -> Result<()> {
             Some("New status".to_string())
         );
 
+        // The received status is not synchronized back.
+        assert_eq!(
+            alice2
+                .sql
+                .count("SELECT COUNT(*) FROM multi_device_sync", paramsv![])
+                .await?,
+            0
+        );
+
         Ok(())
     }
 
@@ -2276,4 +2634,178 @@ async fn test_last_seen() -> Result<()> {
 
         Ok(())
     }
+
+    #[async_std::test]
+    async fn test_has_key() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+        let bob = TestContext::new_bob().await;
+
+        let contact_id = Contact::create(&alice, "Bob", "bob@example.net").await?;
+        let contact = Contact::load_from_db(&alice, contact_id).await?;
+        assert!(!contact.has_key(&alice).await?);
+        assert!(!contact.is_verified_key_contact(&alice).await?);
+
+        let self_contact = Contact::load_from_db(&alice, ContactId::SELF).await?;
+        assert!(self_contact.has_key(&alice).await?);
+        assert!(self_contact.is_verified_key_contact(&alice).await?);
+
+        // Alice learns Bob's key from a message.
+        let chat_id = bob.create_chat(&alice).await.id;
+        alice.recv_msg(&bob.send_text(chat_id, "hi").await).await;
+        assert!(contact.has_key(&alice).await?);
+        assert!(!contact.is_verified_key_contact(&alice).await?);
+
+        let mut peerstate = Peerstate::from_addr(&alice, "bob@example.net")
+            .await?
+            .unwrap();
+        let fingerprint = peerstate.public_key_fingerprint.clone().unwrap();
+        assert!(peerstate.set_verified(
+            PeerstateKeyType::PublicKey,
+            &fingerprint,
+            PeerstateVerifiedStatus::BidirectVerified
+        ));
+        peerstate.save_to_db(&alice.sql, false).await?;
+        assert!(contact.has_key(&alice).await?);
+        assert!(contact.is_verified_key_contact(&alice).await?);
+
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn test_block_by_fingerprint() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+        let bob = TestContext::new_bob().await;
+
+        // Bob learns Alice's key so that his messages are signed.
+        let alice_chat_id = alice.create_chat(&bob).await.id;
+        bob.recv_msg(&alice.send_text(alice_chat_id, "hi").await)
+            .await;
+        let bob_chat_id = bob.create_chat(&alice).await.id;
+
+        let fingerprint = test_utils::bob_keypair().public.fingerprint();
+        alice.block_by_fingerprint(&fingerprint).await?;
+        assert_eq!(
+            alice.get_blocked_fingerprints().await?,
+            vec![fingerprint.clone()]
+        );
+
+        let sent = bob.send_text(bob_chat_id, "blocked").await;
+        let received = alice.recv_msg_opt(&sent).await.unwrap();
+        assert_eq!(received.chat_id, DC_CHAT_ID_TRASH);
+
+        alice.unblock_fingerprint(&fingerprint).await?;
+        assert!(alice.get_blocked_fingerprints().await?.is_empty());
+
+        let msg = alice
+            .recv_msg(&bob.send_text(bob_chat_id, "unblocked").await)
+            .await;
+        assert_eq!(msg.get_text(), Some("unblocked".to_string()));
+        assert_eq!(msg.chat_id, alice_chat_id);
+
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn test_get_chats() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+        let bob = TestContext::new_bob().await;
+        let contact = alice.add_or_lookup_contact(&bob).await;
+        assert!(contact.get_chats(&alice).await?.is_empty());
+
+        let single_id = alice.create_chat(&bob).await.id;
+        let group_id =
+            chat::create_group_chat(&alice, ProtectionStatus::Unprotected, "group").await?;
+        chat::add_contact_to_chat(&alice, group_id, contact.id).await?;
+        let left_id =
+            chat::create_group_chat(&alice, ProtectionStatus::Unprotected, "left").await?;
+        chat::add_contact_to_chat(&alice, left_id, contact.id).await?;
+        chat::remove_contact_from_chat(&alice, left_id, ContactId::SELF).await?;
+
+        assert_eq!(contact.get_chats(&alice).await?, vec![single_id, group_id]);
+
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn test_dedupe_contacts() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        let id1 = Contact::create(&t, "Bob", "bob@example.net").await?;
+        let mut duplicate_ids = Vec::new();
+        for addr in &["Bob@Example.NET", "BOB@EXAMPLE.NET"] {
+            let row_id = t
+                .sql
+                .insert(
+                    "INSERT INTO contacts (name, addr, origin) VALUES ('Bob', ?, ?)",
+                    paramsv![addr, Origin::ManuallyCreated],
+                )
+                .await?;
+            duplicate_ids.push(ContactId::new(u32::try_from(row_id)?));
+        }
+        let id3 = duplicate_ids.pop().unwrap();
+        let id2 = duplicate_ids.pop().unwrap();
+
+        // The second contact has most messages and is kept.
+        let chat_id = ChatId::create_for_contact(&t, id2).await?;
+        send_text_msg(&t, chat_id, "hi".to_string()).await?;
+        let group_id = chat::create_group_chat(&t, ProtectionStatus::Unprotected, "group").await?;
+        chat::add_contact_to_chat(&t, group_id, id1).await?;
+        chat::add_contact_to_chat(&t, group_id, id3).await?;
+
+        assert_eq!(t.dedupe_contacts().await?, 2);
+        assert_eq!(t.dedupe_contacts().await?, 0);
+        assert!(Contact::load_from_db(&t, id1).await.is_err());
+        assert!(Contact::load_from_db(&t, id3).await.is_err());
+        assert_eq!(
+            Contact::lookup_id_by_addr(&t, "bob@example.net", Origin::Unknown).await?,
+            Some(id2)
+        );
+        assert_eq!(
+            get_chat_contacts(&t, group_id).await?,
+            vec![id2, ContactId::SELF]
+        );
+        assert_eq!(chat_id.get_msg_cnt(&t).await?, 1);
+
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn test_dedupe_contacts_blocked_with_chats() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        let id1 = Contact::create(&t, "", "claire@example.net").await?;
+        let row_id = t
+            .sql
+            .insert(
+                "INSERT INTO contacts (name, authname, addr, origin)
+                 VALUES ('Claire', 'Claire C.', 'Claire@Example.NET', ?)",
+                paramsv![Origin::IncomingUnknownFrom],
+            )
+            .await?;
+        let id2 = ContactId::new(u32::try_from(row_id)?);
+
+        // Both contacts have a 1:1 chat, the first one has more messages and is kept.
+        let chat_id1 = ChatId::create_for_contact(&t, id1).await?;
+        send_text_msg(&t, chat_id1, "hi".to_string()).await?;
+        send_text_msg(&t, chat_id1, "how are you?".to_string()).await?;
+        let chat_id2 = ChatId::create_for_contact(&t, id2).await?;
+        send_text_msg(&t, chat_id2, "hi again".to_string()).await?;
+        t.sql
+            .execute("UPDATE contacts SET blocked=1 WHERE id=?", paramsv![id2])
+            .await?;
+
+        assert_eq!(t.dedupe_contacts().await?, 1);
+        assert!(Contact::load_from_db(&t, id2).await.is_err());
+        let contact = Contact::load_from_db(&t, id1).await?;
+        assert_eq!(contact.get_name(), "Claire");
+        assert_eq!(contact.get_authname(), "Claire C.");
+        assert_eq!(contact.origin, Origin::ManuallyCreated);
+        assert!(contact.is_blocked());
+
+        assert!(Chat::load_from_db(&t, chat_id2).await.is_err());
+        let chat = Chat::load_from_db(&t, chat_id1).await?;
+        assert_eq!(chat.blocked, Blocked::Yes);
+        assert_eq!(chat_id1.get_msg_cnt(&t).await?, 3);
+        assert_eq!(get_chat_contacts(&t, chat_id1).await?, vec![id1]);
+
+        Ok(())
+    }
 }
@@ -1,9 +1,11 @@
 //! Context module.
 
+use std::cmp::min;
 use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
 use std::ffi::OsString;
 use std::ops::Deref;
-use std::time::{Instant, SystemTime};
+use std::time::{Duration, Instant, SystemTime};
 
 use anyhow::{ensure, Result};
 use async_std::{
@@ -11,19 +13,27 @@
     path::{Path, PathBuf},
     sync::{Arc, Mutex, RwLock},
 };
+use futures::stream::{self, Stream, TryStreamExt};
+use num_traits::FromPrimitive;
 
-use crate::chat::{get_chat_cnt, ChatId};
+use crate::chat::{get_chat_cnt, Chat, ChatId};
 use crate::config::Config;
-use crate::constants::DC_VERSION_STR;
-use crate::contact::Contact;
-use crate::dc_tools::{duration_to_str, time};
+use crate::constants::{
+    Blocked, Chattype, NotificationPreview, DC_DESIRED_TEXT_LEN, DC_ELLIPSIS, DC_VERSION_STR,
+};
+use crate::contact::{Contact, ContactId};
+use crate::dc_tools::{dc_truncate, duration_to_str, time};
+use crate::dehtml::dehtml;
 use crate::events::{Event, EventEmitter, EventType, Events};
 use crate::key::{DcKey, SignedPublicKey};
 use crate::login_param::LoginParam;
-use crate::message::{self, MessageState, MsgId};
+use crate::message::{self, Message, MessageState, MsgId, Viewtype};
+use crate::mimeparser::MimeMessage;
 use crate::quota::QuotaInfo;
 use crate::scheduler::Scheduler;
+use crate::simplify::simplify;
 use crate::sql::Sql;
+use crate::stock_str;
 
 #[derive(Clone, Debug)]
 pub struct Context {
@@ -38,6 +48,129 @@ fn deref(&self) -> &Self::Target {
     }
 }
 
+/// Number of results loaded at once by [`Context::search_msgs_stream`].
+const SEARCH_PAGE_SIZE: usize = 100;
+
+/// Information needed to show a notification for a message,
+/// see [`Context::notification_info`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotificationInfo {
+    /// Whether a notification should be shown at all.
+    pub notify: bool,
+
+    /// Text to show in the notification, see [`Context::get_notification_text`].
+    pub text: String,
+
+    /// Sender of the message.
+    pub from_id: ContactId,
+
+    /// Chat the message belongs to.
+    pub chat_id: ChatId,
+}
+
+/// Information about a context, see [`Context::get_info_struct`].
+///
+/// Configuration values that are not set are `None`.
+#[derive(Debug, Clone)]
+pub struct ContextInfo {
+    /// Whether the account is used by a bot, see [`Config::Bot`].
+    pub bot: bool,
+    /// Number of chats.
+    pub number_of_chats: usize,
+    /// Number of messages in unblocked chats.
+    pub number_of_chat_messages: usize,
+    /// Number of messages in contact requests.
+    pub messages_in_contact_requests: usize,
+    /// Number of contacts, not counting special contacts.
+    pub number_of_contacts: usize,
+    /// Path to the database file.
+    pub database_dir: PathBuf,
+    /// Version of the database schema.
+    pub database_version: i32,
+    /// Whether the database is encrypted, `None` if the database is closed.
+    pub database_encrypted: Option<bool>,
+    /// SQLite journal mode.
+    pub journal_mode: String,
+    /// Path to the blob directory.
+    pub blobdir: PathBuf,
+    /// See [`Config::Displayname`].
+    pub display_name: Option<String>,
+    /// See [`Config::Selfavatar`].
+    pub selfavatar: Option<String>,
+    /// Whether the account is configured.
+    pub is_configured: bool,
+    /// See [`Config::Socks5Enabled`].
+    pub socks5_enabled: bool,
+    /// Login parameters entered by the user, without passwords.
+    pub entered_account_settings: String,
+    /// Login parameters used after configuration, without passwords.
+    pub used_account_settings: String,
+    /// Addresses used before the current one.
+    pub secondary_addrs: Vec<String>,
+    /// See [`Config::FetchExistingMsgs`].
+    pub fetch_existing_msgs: bool,
+    /// Whether existing messages have already been fetched.
+    pub fetched_existing_msgs: bool,
+    /// See [`Config::ShowEmails`].
+    pub show_emails: i32,
+    /// See [`Config::DownloadLimit`].
+    pub download_limit: i32,
+    /// See [`Config::SentboxWatch`].
+    pub sentbox_watch: bool,
+    /// See [`Config::MvboxMove`].
+    pub mvbox_move: bool,
+    /// See [`Config::OnlyFetchMvbox`].
+    pub only_fetch_mvbox: bool,
+    /// See [`Config::DisableMvbox`].
+    pub disable_mvbox: bool,
+    /// Version of the folder configuration.
+    pub folders_configured: i32,
+    /// Configured sent folder.
+    pub configured_sentbox_folder: Option<String>,
+    /// Configured folder for chat messages.
+    pub configured_mvbox_folder: Option<String>,
+    /// See [`Config::MdnsEnabled`].
+    pub mdns_enabled: bool,
+    /// Whether read receipts are requested for outgoing messages.
+    pub request_receipts: bool,
+    /// Whether read receipts are sent for incoming messages.
+    pub send_receipts: bool,
+    /// See [`Config::E2eeEnabled`].
+    pub e2ee_enabled: bool,
+    /// See [`Config::Language`].
+    pub language: Option<String>,
+    /// See [`Config::KeyGenType`].
+    pub key_gen_type: i32,
+    /// See [`Config::KeyRotationDays`].
+    pub key_rotation_days: i32,
+    /// See [`Config::BccSelf`].
+    pub bcc_self: bool,
+    /// See [`Config::SendSyncMsgs`].
+    pub send_sync_msgs: bool,
+    /// Number of own private keys.
+    pub private_key_count: usize,
+    /// Number of peerstates.
+    pub public_key_count: usize,
+    /// Fingerprint of the own public key, or a description of the error if it cannot be loaded.
+    pub fingerprint: String,
+    /// See [`Config::WebrtcInstance`].
+    pub webrtc_instance: Option<String>,
+    /// See [`Config::MediaQuality`].
+    pub media_quality: i32,
+    /// See [`Config::DeleteDeviceAfter`].
+    pub delete_device_after: i64,
+    /// See [`Config::DeleteServerAfter`].
+    pub delete_server_after: i64,
+    /// See [`Config::LastHousekeeping`].
+    pub last_housekeeping: i64,
+    /// See [`Config::ScanAllFoldersDebounceSecs`].
+    pub scan_all_folders_debounce_secs: i64,
+    /// See [`Config::QuotaExceeding`].
+    pub quota_exceeding: i32,
+    /// Time since the context was created.
+    pub uptime: Duration,
+}
+
 #[derive(Debug)]
 pub struct InnerContext {
     /// Blob directory path
@@ -52,6 +185,9 @@ pub struct InnerContext {
     /// Mutex to prevent a race condition when a "your pw is wrong" warning is sent, resulting in multiple messeges being sent.
     pub(crate) wrong_pw_warning_mutex: Mutex<()>,
     pub(crate) translated_stockstrings: RwLock<HashMap<usize, String>>,
+    /// Translations used for stock strings missing in `translated_stockstrings`,
+    /// eg. `pt` for a `pt-BR` account.
+    pub(crate) fallback_stockstrings: RwLock<HashMap<usize, String>>,
     pub(crate) events: Events,
 
     pub(crate) scheduler: RwLock<Option<Scheduler>>,
@@ -62,6 +198,11 @@ pub struct InnerContext {
 
     pub(crate) last_full_folder_scan: Mutex<Option<Instant>>,
 
+    /// Receivers of the progress of joining a secure-join as `(inviter, progress)`,
+    /// the same as sent with [`EventType::SecurejoinJoinerProgress`].
+    /// Closed channels are removed on the next progress update.
+    pub(crate) joiner_progress_senders: Mutex<Vec<Sender<(ContactId, usize)>>>,
+
     /// ID for this `Context` in the current process.
     ///
     /// This allows for multiple `Context`s open in a single process where each context can
@@ -184,11 +325,13 @@ pub(crate) async fn with_blobdir(
             oauth2_mutex: Mutex::new(()),
             wrong_pw_warning_mutex: Mutex::new(()),
             translated_stockstrings: RwLock::new(HashMap::new()),
+            fallback_stockstrings: RwLock::new(HashMap::new()),
             events: Events::default(),
             scheduler: RwLock::new(None),
             quota: RwLock::new(None),
             creation_time: std::time::SystemTime::now(),
             last_full_folder_scan: Mutex::new(None),
+            joiner_progress_senders: Mutex::new(Vec::new()),
             last_error: RwLock::new("".to_string()),
         };
 
@@ -270,8 +413,19 @@ pub fn emit_msgs_changed(&self, chat_id: ChatId, msg_id: MsgId) {
     }
 
     /// Emits an IncomingMsg event with specified chat and message ids
-    pub fn emit_incoming_msg(&self, chat_id: ChatId, msg_id: MsgId) {
-        self.emit_event(EventType::IncomingMsg { chat_id, msg_id });
+    pub fn emit_incoming_msg(
+        &self,
+        chat_id: ChatId,
+        msg_id: MsgId,
+        chat_type: Chattype,
+        is_mention: bool,
+    ) {
+        self.emit_event(EventType::IncomingMsg {
+            chat_id,
+            msg_id,
+            chat_type,
+            is_mention,
+        });
     }
 
     /// Returns a receiver for emitted events.
@@ -333,193 +487,209 @@ pub(crate) async fn shall_stop_ongoing(&self) -> bool {
         }
     }
 
+    /// Frees memory held by caches, to be called by the UI when the OS signals memory pressure.
+    ///
+    /// Drops the cached quota information, which is reloaded when needed,
+    /// and shrinks the caches of idle database connections.
+    /// It is safe to call this function at any time, also if the database is not open.
+    pub async fn on_low_memory(&self) -> Result<()> {
+        self.quota.write().await.take();
+        self.sql.shrink_memory().await
+    }
+
     /*******************************************************************************
      * UI chat/message related API
      ******************************************************************************/
 
-    pub async fn get_info(&self) -> Result<BTreeMap<&'static str, String>> {
-        let unset = "0";
-        let l = LoginParam::load_candidate_params(self).await?;
-        let l2 = LoginParam::load_configured_params(self).await?;
-        let secondary_addrs = self.get_secondary_self_addrs().await?.join(", ");
-        let displayname = self.get_config(Config::Displayname).await?;
-        let chats = get_chat_cnt(self).await? as usize;
-        let unblocked_msgs = message::get_unblocked_msg_cnt(self).await as usize;
-        let request_msgs = message::get_request_msg_cnt(self).await as usize;
-        let contacts = Contact::get_real_cnt(self).await? as usize;
-        let is_configured = self.get_config_int(Config::Configured).await?;
-        let socks5_enabled = self.get_config_int(Config::Socks5Enabled).await?;
-        let dbversion = self
-            .sql
-            .get_raw_config_int("dbversion")
-            .await?
-            .unwrap_or_default();
-        let journal_mode = self
-            .sql
-            .query_get_value("PRAGMA journal_mode;", paramsv![])
-            .await?
-            .unwrap_or_else(|| "unknown".to_string());
-        let e2ee_enabled = self.get_config_int(Config::E2eeEnabled).await?;
-        let mdns_enabled = self.get_config_int(Config::MdnsEnabled).await?;
-        let bcc_self = self.get_config_int(Config::BccSelf).await?;
-        let send_sync_msgs = self.get_config_int(Config::SendSyncMsgs).await?;
-
-        let prv_key_cnt = self
-            .sql
-            .count("SELECT COUNT(*) FROM keypairs;", paramsv![])
-            .await?;
-
-        let pub_key_cnt = self
-            .sql
-            .count("SELECT COUNT(*) FROM acpeerstates;", paramsv![])
-            .await?;
-        let fingerprint_str = match SignedPublicKey::load_self(self).await {
+    /// Returns information about the context, see [`ContextInfo`].
+    pub async fn get_info_struct(&self) -> Result<ContextInfo> {
+        let fingerprint = match SignedPublicKey::load_self(self).await {
             Ok(key) => key.fingerprint().hex(),
             Err(err) => format!("<key failure: {}>", err),
         };
 
-        let sentbox_watch = self.get_config_int(Config::SentboxWatch).await?;
-        let mvbox_move = self.get_config_int(Config::MvboxMove).await?;
-        let only_fetch_mvbox = self.get_config_int(Config::OnlyFetchMvbox).await?;
-        let folders_configured = self
-            .sql
-            .get_raw_config_int("folders_configured")
-            .await?
-            .unwrap_or_default();
-
-        let configured_sentbox_folder = self
-            .get_config(Config::ConfiguredSentboxFolder)
-            .await?
-            .unwrap_or_else(|| "<unset>".to_string());
-        let configured_mvbox_folder = self
-            .get_config(Config::ConfiguredMvboxFolder)
-            .await?
-            .unwrap_or_else(|| "<unset>".to_string());
-
-        let mut res = get_info();
-
-        // insert values
-        res.insert("bot", self.get_config_int(Config::Bot).await?.to_string());
-        res.insert("number_of_chats", chats.to_string());
-        res.insert("number_of_chat_messages", unblocked_msgs.to_string());
-        res.insert("messages_in_contact_requests", request_msgs.to_string());
-        res.insert("number_of_contacts", contacts.to_string());
-        res.insert("database_dir", self.get_dbfile().display().to_string());
-        res.insert("database_version", dbversion.to_string());
-        res.insert(
-            "database_encrypted",
-            self.sql
-                .is_encrypted()
-                .await
-                .map_or_else(|| "closed".to_string(), |b| b.to_string()),
-        );
-        res.insert("journal_mode", journal_mode);
-        res.insert("blobdir", self.get_blobdir().display().to_string());
-        res.insert("display_name", displayname.unwrap_or_else(|| unset.into()));
-        res.insert(
-            "selfavatar",
-            self.get_config(Config::Selfavatar)
+        Ok(ContextInfo {
+            bot: self.get_config_bool(Config::Bot).await?,
+            number_of_chats: get_chat_cnt(self).await?,
+            number_of_chat_messages: message::get_unblocked_msg_cnt(self).await,
+            messages_in_contact_requests: message::get_request_msg_cnt(self).await,
+            number_of_contacts: Contact::get_real_cnt(self).await?,
+            database_dir: self.get_dbfile().to_path_buf(),
+            database_version: self
+                .sql
+                .get_raw_config_int("dbversion")
                 .await?
-                .unwrap_or_else(|| "<unset>".to_string()),
-        );
-        res.insert("is_configured", is_configured.to_string());
-        res.insert("socks5_enabled", socks5_enabled.to_string());
-        res.insert("entered_account_settings", l.to_string());
-        res.insert("used_account_settings", l2.to_string());
-        res.insert("secondary_addrs", secondary_addrs);
-        res.insert(
-            "fetch_existing_msgs",
-            self.get_config_int(Config::FetchExistingMsgs)
+                .unwrap_or_default(),
+            database_encrypted: self.sql.is_encrypted().await,
+            journal_mode: self
+                .sql
+                .query_get_value("PRAGMA journal_mode;", paramsv![])
                 .await?
-                .to_string(),
-        );
-        res.insert(
-            "fetched_existing_msgs",
-            self.get_config_bool(Config::FetchedExistingMsgs)
+                .unwrap_or_else(|| "unknown".to_string()),
+            blobdir: self.get_blobdir().to_path_buf(),
+            display_name: self.get_config(Config::Displayname).await?,
+            selfavatar: self.get_config(Config::Selfavatar).await?,
+            is_configured: self.get_config_bool(Config::Configured).await?,
+            socks5_enabled: self.get_config_bool(Config::Socks5Enabled).await?,
+            entered_account_settings: LoginParam::load_candidate_params(self).await?.to_string(),
+            used_account_settings: LoginParam::load_configured_params(self).await?.to_string(),
+            secondary_addrs: self.get_secondary_self_addrs().await?,
+            fetch_existing_msgs: self.get_config_bool(Config::FetchExistingMsgs).await?,
+            fetched_existing_msgs: self.get_config_bool(Config::FetchedExistingMsgs).await?,
+            show_emails: self.get_config_int(Config::ShowEmails).await?,
+            download_limit: self.get_config_int(Config::DownloadLimit).await?,
+            sentbox_watch: self.get_config_bool(Config::SentboxWatch).await?,
+            mvbox_move: self.get_config_bool(Config::MvboxMove).await?,
+            only_fetch_mvbox: self.get_config_bool(Config::OnlyFetchMvbox).await?,
+            disable_mvbox: self.get_config_bool(Config::DisableMvbox).await?,
+            folders_configured: self
+                .sql
+                .get_raw_config_int("folders_configured")
                 .await?
-                .to_string(),
-        );
-        res.insert(
-            "show_emails",
-            self.get_config_int(Config::ShowEmails).await?.to_string(),
-        );
+                .unwrap_or_default(),
+            configured_sentbox_folder: self.get_config(Config::ConfiguredSentboxFolder).await?,
+            configured_mvbox_folder: self.get_config(Config::ConfiguredMvboxFolder).await?,
+            mdns_enabled: self.get_config_bool(Config::MdnsEnabled).await?,
+            request_receipts: self.should_request_mdns().await?,
+            send_receipts: self.should_send_mdns().await?,
+            e2ee_enabled: self.get_config_bool(Config::E2eeEnabled).await?,
+            language: self.get_config(Config::Language).await?,
+            key_gen_type: self.get_config_int(Config::KeyGenType).await?,
+            key_rotation_days: self.get_config_int(Config::KeyRotationDays).await?,
+            bcc_self: self.get_config_bool(Config::BccSelf).await?,
+            send_sync_msgs: self.get_config_bool(Config::SendSyncMsgs).await?,
+            private_key_count: self
+                .sql
+                .count("SELECT COUNT(*) FROM keypairs;", paramsv![])
+                .await?,
+            public_key_count: self
+                .sql
+                .count("SELECT COUNT(*) FROM acpeerstates;", paramsv![])
+                .await?,
+            fingerprint,
+            webrtc_instance: self.get_config(Config::WebrtcInstance).await?,
+            media_quality: self.get_config_int(Config::MediaQuality).await?,
+            delete_device_after: self.get_config_i64(Config::DeleteDeviceAfter).await?,
+            delete_server_after: self.get_config_i64(Config::DeleteServerAfter).await?,
+            last_housekeeping: self.get_config_i64(Config::LastHousekeeping).await?,
+            scan_all_folders_debounce_secs: self
+                .get_config_i64(Config::ScanAllFoldersDebounceSecs)
+                .await?,
+            quota_exceeding: self.get_config_int(Config::QuotaExceeding).await?,
+            uptime: self.creation_time.elapsed().unwrap_or_default(),
+        })
+    }
+
+    /// Returns information about the context as key-value pairs
+    /// on top of the information returned by [`get_info`].
+    ///
+    /// The values are taken from [`Context::get_info_struct`].
+    pub async fn get_info(&self) -> Result<BTreeMap<&'static str, String>> {
+        let info = self.get_info_struct().await?;
+        let unset = |value: Option<String>| value.unwrap_or_else(|| "<unset>".to_string());
+        let int = |value: bool| (value as i32).to_string();
+
+        let mut res = get_info();
+        res.insert("bot", int(info.bot));
+        res.insert("number_of_chats", info.number_of_chats.to_string());
         res.insert(
-            "download_limit",
-            self.get_config_int(Config::DownloadLimit)
-                .await?
-                .to_string(),
+            "number_of_chat_messages",
+            info.number_of_chat_messages.to_string(),
         );
-        res.insert("sentbox_watch", sentbox_watch.to_string());
-        res.insert("mvbox_move", mvbox_move.to_string());
-        res.insert("only_fetch_mvbox", only_fetch_mvbox.to_string());
-        res.insert("folders_configured", folders_configured.to_string());
-        res.insert("configured_sentbox_folder", configured_sentbox_folder);
-        res.insert("configured_mvbox_folder", configured_mvbox_folder);
-        res.insert("mdns_enabled", mdns_enabled.to_string());
-        res.insert("e2ee_enabled", e2ee_enabled.to_string());
         res.insert(
-            "key_gen_type",
-            self.get_config_int(Config::KeyGenType).await?.to_string(),
+            "messages_in_contact_requests",
+            info.messages_in_contact_requests.to_string(),
         );
-        res.insert("bcc_self", bcc_self.to_string());
-        res.insert("send_sync_msgs", send_sync_msgs.to_string());
-        res.insert("private_key_count", prv_key_cnt.to_string());
-        res.insert("public_key_count", pub_key_cnt.to_string());
-        res.insert("fingerprint", fingerprint_str);
+        res.insert("number_of_contacts", info.number_of_contacts.to_string());
+        res.insert("database_dir", info.database_dir.display().to_string());
+        res.insert("database_version", info.database_version.to_string());
         res.insert(
-            "webrtc_instance",
-            self.get_config(Config::WebrtcInstance)
-                .await?
-                .unwrap_or_else(|| "<unset>".to_string()),
+            "database_encrypted",
+            info.database_encrypted
+                .map_or_else(|| "closed".to_string(), |b| b.to_string()),
         );
+        res.insert("journal_mode", info.journal_mode);
+        res.insert("blobdir", info.blobdir.display().to_string());
         res.insert(
-            "media_quality",
-            self.get_config_int(Config::MediaQuality).await?.to_string(),
+            "display_name",
+            info.display_name.unwrap_or_else(|| "0".to_string()),
         );
+        res.insert("selfavatar", unset(info.selfavatar));
+        res.insert("is_configured", int(info.is_configured));
+        res.insert("socks5_enabled", int(info.socks5_enabled));
+        res.insert("entered_account_settings", info.entered_account_settings);
+        res.insert("used_account_settings", info.used_account_settings);
+        res.insert("secondary_addrs", info.secondary_addrs.join(", "));
+        res.insert("fetch_existing_msgs", int(info.fetch_existing_msgs));
         res.insert(
-            "delete_device_after",
-            self.get_config_int(Config::DeleteDeviceAfter)
-                .await?
-                .to_string(),
+            "fetched_existing_msgs",
+            info.fetched_existing_msgs.to_string(),
         );
+        res.insert("show_emails", info.show_emails.to_string());
+        res.insert("download_limit", info.download_limit.to_string());
+        res.insert("sentbox_watch", int(info.sentbox_watch));
+        res.insert("mvbox_move", int(info.mvbox_move));
+        res.insert("only_fetch_mvbox", int(info.only_fetch_mvbox));
+        res.insert("disable_mvbox", int(info.disable_mvbox));
+        res.insert("folders_configured", info.folders_configured.to_string());
         res.insert(
-            "delete_server_after",
-            self.get_config_int(Config::DeleteServerAfter)
-                .await?
-                .to_string(),
+            "configured_sentbox_folder",
+            unset(info.configured_sentbox_folder),
         );
         res.insert(
-            "last_housekeeping",
-            self.get_config_int(Config::LastHousekeeping)
-                .await?
-                .to_string(),
+            "configured_mvbox_folder",
+            unset(info.configured_mvbox_folder),
         );
+        res.insert("mdns_enabled", int(info.mdns_enabled));
+        res.insert("request_receipts", int(info.request_receipts));
+        res.insert("send_receipts", int(info.send_receipts));
+        res.insert("e2ee_enabled", int(info.e2ee_enabled));
+        res.insert("language", unset(info.language));
+        res.insert("key_gen_type", info.key_gen_type.to_string());
+        res.insert("key_rotation_days", info.key_rotation_days.to_string());
+        res.insert("bcc_self", int(info.bcc_self));
+        res.insert("send_sync_msgs", int(info.send_sync_msgs));
+        res.insert("private_key_count", info.private_key_count.to_string());
+        res.insert("public_key_count", info.public_key_count.to_string());
+        res.insert("fingerprint", info.fingerprint);
+        res.insert("webrtc_instance", unset(info.webrtc_instance));
+        res.insert("media_quality", info.media_quality.to_string());
+        res.insert("delete_device_after", info.delete_device_after.to_string());
+        res.insert("delete_server_after", info.delete_server_after.to_string());
+        res.insert("last_housekeeping", info.last_housekeeping.to_string());
         res.insert(
             "scan_all_folders_debounce_secs",
-            self.get_config_int(Config::ScanAllFoldersDebounceSecs)
-                .await?
-                .to_string(),
-        );
-        res.insert(
-            "quota_exceeding",
-            self.get_config_int(Config::QuotaExceeding)
-                .await?
-                .to_string(),
+            info.scan_all_folders_debounce_secs.to_string(),
         );
-
-        let elapsed = self.creation_time.elapsed();
-        res.insert("uptime", duration_to_str(elapsed.unwrap_or_default()));
+        res.insert("quota_exceeding", info.quota_exceeding.to_string());
+        res.insert("uptime", duration_to_str(info.uptime));
 
         Ok(res)
     }
 
     /// Get a list of fresh, unmuted messages in unblocked chats.
     ///
+    /// If [`Config::NotifyOnMention`] is set,
+    /// messages mentioning the user are also returned for muted chats.
+    ///
     /// The list starts with the most recent message
     /// and is typically used to show notifications.
     /// Moreover, the number of returned messages
     /// can be used for a badge counter on the app icon.
     pub async fn get_fresh_msgs(&self) -> Result<Vec<MsgId>> {
+        self.get_fresh_msgs_ex(None).await
+    }
+
+    /// Same as [`Context::get_fresh_msgs`], but only returns messages from the given chat.
+    ///
+    /// If the chat is muted or blocked, the list is empty,
+    /// except for mentions if [`Config::NotifyOnMention`] is set.
+    pub async fn get_fresh_msgs_in_chat(&self, chat_id: ChatId) -> Result<Vec<MsgId>> {
+        self.get_fresh_msgs_ex(Some(chat_id)).await
+    }
+
+    async fn get_fresh_msgs_ex(&self, chat_id: Option<ChatId>) -> Result<Vec<MsgId>> {
+        let notify_on_mention = self.get_config_bool(Config::NotifyOnMention).await?;
         let list = self
             .sql
             .query_map(
@@ -533,12 +703,21 @@ pub async fn get_fresh_msgs(&self) -> Result<Vec<MsgId>> {
                     " WHERE m.state=?",
                     "   AND m.hidden=0",
                     "   AND m.chat_id>9",
+                    "   AND (? IS NULL OR m.chat_id=?)",
                     "   AND ct.blocked=0",
                     "   AND c.blocked=0",
-                    "   AND NOT(c.muted_until=-1 OR c.muted_until>?)",
+                    "   AND (NOT(c.muted_until=-1 OR c.muted_until>?)",
+                    "        OR (? AND m.id IN (SELECT msg_id FROM mentions WHERE contact_id=?)))",
                     " ORDER BY m.timestamp DESC,m.id DESC;"
                 ),
-                paramsv![MessageState::InFresh, time()],
+                paramsv![
+                    MessageState::InFresh,
+                    chat_id,
+                    chat_id,
+                    time(),
+                    notify_on_mention,
+                    ContactId::SELF
+                ],
                 |row| row.get::<_, MsgId>(0),
                 |rows| {
                     let mut list = Vec::new();
@@ -552,26 +731,204 @@ pub async fn get_fresh_msgs(&self) -> Result<Vec<MsgId>> {
         Ok(list)
     }
 
+    /// Returns the chats that are contact requests,
+    /// the chat with the most recent message first.
+    ///
+    /// Use [`ChatId::accept`] or [`ChatId::block`] to handle the requests.
+    pub async fn get_contact_request_chats(&self) -> Result<Vec<ChatId>> {
+        let list = self
+            .sql
+            .query_map(
+                "SELECT c.id
+                 FROM chats c
+                 LEFT JOIN msgs m
+                        ON m.id=(SELECT id FROM msgs
+                                  WHERE chat_id=c.id AND hidden=0
+                                  ORDER BY timestamp DESC, id DESC LIMIT 1)
+                 WHERE c.blocked=?
+                   AND c.id>9
+                 ORDER BY IFNULL(m.timestamp,c.created_timestamp) DESC, c.id DESC;",
+                paramsv![Blocked::Request],
+                |row| row.get::<_, ChatId>(0),
+                |rows| {
+                    rows.collect::<std::result::Result<Vec<_>, _>>()
+                        .map_err(Into::into)
+                },
+            )
+            .await?;
+        Ok(list)
+    }
+
+    /// Returns the text to show in a notification for the message `msg_id`.
+    ///
+    /// Depending on [`Config::NotificationPreview`], this is the message summary
+    /// prefixed by the sender in groups, only the sender or a generic "New message" text.
+    pub async fn get_notification_text(&self, msg_id: MsgId) -> Result<String> {
+        let msg = Message::load_from_db(self, msg_id).await?;
+        let preview =
+            NotificationPreview::from_i32(self.get_config_int(Config::NotificationPreview).await?)
+                .unwrap_or_default();
+        let text = match preview {
+            NotificationPreview::Full => {
+                let summary = msg.get_summary(self, None).await?;
+                match summary.prefix {
+                    Some(prefix) => format!("{}: {}", prefix, summary.text),
+                    None => summary.text,
+                }
+            }
+            NotificationPreview::SenderOnly => {
+                let contact = Contact::get_by_id(self, msg.get_from_id()).await?;
+                stock_str::message_from(self, msg.get_sender_name(&contact)).await
+            }
+            NotificationPreview::None => stock_str::new_message(self).await,
+        };
+        Ok(text)
+    }
+
+    /// Returns whether and how to notify about the message `msg_id`.
+    ///
+    /// Notifications are shown for fresh messages in unblocked chats from unblocked contacts.
+    /// Info messages, such as "member added", are silent and never notify.
+    /// Muted chats only notify if [`Config::NotifyOnMention`] is set
+    /// and the message mentions the user,
+    /// matching the messages returned by [`Context::get_fresh_msgs`].
+    pub async fn notification_info(&self, msg_id: MsgId) -> Result<NotificationInfo> {
+        let msg = Message::load_from_db(self, msg_id).await?;
+        let chat = Chat::load_from_db(self, msg.chat_id).await?;
+        let contact = Contact::get_by_id(self, msg.from_id).await?;
+
+        let notify = if msg.state != MessageState::InFresh
+            || msg.hidden
+            || msg.is_info()
+            || chat.blocked != Blocked::Not
+            || contact.is_blocked()
+        {
+            false
+        } else if chat.is_muted() {
+            self.get_config_bool(Config::NotifyOnMention).await?
+                && msg.get_mentions(self).await?.contains(&ContactId::SELF)
+        } else {
+            true
+        };
+
+        Ok(NotificationInfo {
+            notify,
+            text: self.get_notification_text(msg_id).await?,
+            from_id: msg.from_id,
+            chat_id: msg.chat_id,
+        })
+    }
+
     /// Searches for messages containing the query string.
     ///
     /// If `chat_id` is provided this searches only for messages in this chat, if `chat_id`
     /// is `None` this searches messages from all chats.
     pub async fn search_msgs(&self, chat_id: Option<ChatId>, query: &str) -> Result<Vec<MsgId>> {
+        self.search_msgs_stream(chat_id, query, None)
+            .try_collect()
+            .await
+    }
+
+    /// Same as [`Context::search_msgs`], but only returns messages sent by `from_id`.
+    pub async fn search_msgs_by_contact(
+        &self,
+        chat_id: Option<ChatId>,
+        from_id: ContactId,
+        query: &str,
+    ) -> Result<Vec<MsgId>> {
+        self.search_msgs_paged(chat_id, Some(from_id), query, None, SEARCH_PAGE_SIZE)
+            .try_collect()
+            .await
+    }
+
+    /// Same as [`Context::search_msgs`], but returns the results as a stream.
+    ///
+    /// The database is queried page by page as the stream is polled,
+    /// so the first results are available before the whole search is done
+    /// and dropping the stream cancels the search.
+    ///
+    /// If `limit` is `None`, all results are returned when searching a single chat
+    /// and at most 1000 results are returned when searching all chats.
+    /// The stream ends after the first error.
+    pub fn search_msgs_stream(
+        &self,
+        chat_id: Option<ChatId>,
+        query: &str,
+        limit: Option<usize>,
+    ) -> impl Stream<Item = Result<MsgId>> + '_ {
+        self.search_msgs_paged(chat_id, None, query, limit, SEARCH_PAGE_SIZE)
+    }
+
+    fn search_msgs_paged(
+        &self,
+        chat_id: Option<ChatId>,
+        from_id: Option<ContactId>,
+        query: &str,
+        limit: Option<usize>,
+        page_size: usize,
+    ) -> impl Stream<Item = Result<MsgId>> + '_ {
         let real_query = query.trim();
-        if real_query.is_empty() {
-            return Ok(Vec::new());
-        }
+        let limit = if real_query.is_empty() {
+            0
+        } else if let Some(limit) = limit {
+            limit
+        } else if chat_id.is_some() {
+            usize::MAX
+        } else {
+            // To speed up incremental search, where queries for few characters usually return lots
+            // of unwanted results that are discarded moments later, we added `LIMIT 1000`.
+            // According to some tests, this limit speeds up eg. 2 character searches by factor 10.
+            // The limit is documented and UI may add a hint when getting 1000 results.
+            1000
+        };
         let str_like_in_text = format!("%{}%", real_query);
 
+        stream::try_unfold(
+            (None, limit),
+            move |(after, remaining): (Option<(i64, MsgId)>, usize)| {
+                let str_like_in_text = str_like_in_text.clone();
+                async move {
+                    if remaining == 0 {
+                        return Ok(None);
+                    }
+                    let page_limit = min(remaining, page_size);
+                    let page = self
+                        .search_msgs_page(chat_id, from_id, &str_like_in_text, after, page_limit)
+                        .await?;
+                    let remaining = if page.len() < page_limit {
+                        0
+                    } else {
+                        remaining - page.len()
+                    };
+                    let after = page.last().copied();
+                    let ids: Vec<Result<MsgId>> = page.into_iter().map(|(_, id)| Ok(id)).collect();
+                    Result::<_>::Ok(Some((stream::iter(ids), (after, remaining))))
+                }
+            },
+        )
+        .try_flatten()
+    }
+
+    /// Returns up to `limit` messages matching `str_like_in_text`, and `from_id` if set,
+    /// that are sorted after the `after` position as `(timestamp, id)` pairs.
+    async fn search_msgs_page(
+        &self,
+        chat_id: Option<ChatId>,
+        from_id: Option<ContactId>,
+        str_like_in_text: &str,
+        after: Option<(i64, MsgId)>,
+        limit: usize,
+    ) -> Result<Vec<(i64, MsgId)>> {
+        let limit = i64::try_from(limit).unwrap_or(i64::MAX);
         let do_query = |query, params| {
             self.sql.query_map(
                 query,
                 params,
-                |row| row.get::<_, MsgId>("id"),
+                |row| Ok((row.get::<_, i64>("timestamp")?, row.get::<_, MsgId>("id")?)),
                 |rows| {
                     let mut ret = Vec::new();
-                    for id in rows {
-                        ret.push(id?);
+                    for row in rows {
+                        ret.push(row?);
                     }
                     Ok(ret)
                 },
@@ -579,6 +936,7 @@ pub async fn search_msgs(&self, chat_id: Option<ChatId>, query: &str) -> Result<
         };
 
         let list = if let Some(chat_id) = chat_id {
+            let (timestamp, id) = after.unwrap_or((i64::MIN, MsgId::new(0)));
             do_query(
                 "SELECT m.id AS id, m.timestamp AS timestamp
                  FROM msgs m
@@ -588,8 +946,20 @@ pub async fn search_msgs(&self, chat_id: Option<ChatId>, query: &str) -> Result<
                    AND m.hidden=0
                    AND ct.blocked=0
                    AND txt LIKE ?
-                 ORDER BY m.timestamp,m.id;",
-                paramsv![chat_id, str_like_in_text],
+                   AND (? IS NULL OR m.from_id=?)
+                   AND (m.timestamp>? OR (m.timestamp=? AND m.id>?))
+                 ORDER BY m.timestamp,m.id
+                 LIMIT ?;",
+                paramsv![
+                    chat_id,
+                    str_like_in_text,
+                    from_id,
+                    from_id,
+                    timestamp,
+                    timestamp,
+                    id,
+                    limit
+                ],
             )
             .await?
         } else {
@@ -598,11 +968,7 @@ pub async fn search_msgs(&self, chat_id: Option<ChatId>, query: &str) -> Result<
             //
             // Unlike chat view, sorting by `timestamp` is not necessary but slows down the query by
             // ~25% according to benchmarks.
-            //
-            // To speed up incremental search, where queries for few characters usually return lots
-            // of unwanted results that are discarded moments later, we added `LIMIT 1000`.
-            // According to some tests, this limit speeds up eg. 2 character searches by factor 10.
-            // The limit is documented and UI may add a hint when getting 1000 results.
+            let id = after.map_or(MsgId::new(u32::MAX), |(_, id)| id);
             do_query(
                 "SELECT m.id AS id, m.timestamp AS timestamp
                  FROM msgs m
@@ -615,8 +981,11 @@ pub async fn search_msgs(&self, chat_id: Option<ChatId>, query: &str) -> Result<
                    AND c.blocked=0
                    AND ct.blocked=0
                    AND m.txt LIKE ?
-                 ORDER BY m.id DESC LIMIT 1000",
-                paramsv![str_like_in_text],
+                   AND (? IS NULL OR m.from_id=?)
+                   AND m.id<?
+                 ORDER BY m.id DESC
+                 LIMIT ?",
+                paramsv![str_like_in_text, from_id, from_id, id, limit],
             )
             .await?
         };
@@ -624,6 +993,125 @@ pub async fn search_msgs(&self, chat_id: Option<ChatId>, query: &str) -> Result<
         Ok(list)
     }
 
+    /// Rebuilds the searchable text of received text messages from their stored raw text.
+    ///
+    /// Only messages that lost their text, e.g. due to a faulty migration or import,
+    /// but still have the raw text are repaired, other messages are not touched.
+    /// If the MIME message is stored, it is parsed again as on receiving,
+    /// otherwise the raw text is converted from HTML if needed, simplified and truncated.
+    /// Progress is reported via [`EventType::ReindexProgress`].
+    pub async fn reindex_search(&self) -> Result<()> {
+        let rows = self
+            .sql
+            .query_map(
+                "SELECT id, txt_raw, msgrmsg, CAST(mime_headers AS BLOB) FROM msgs
+                 WHERE chat_id>9 AND type=? AND txt='' AND txt_raw!=''",
+                paramsv![Viewtype::Text],
+                |row| {
+                    let msg_id: MsgId = row.get(0)?;
+                    let txt_raw: String = row.get(1)?;
+                    let msgrmsg: i32 = row.get(2)?;
+                    let mime_headers: Option<Vec<u8>> = row.get(3)?;
+                    Ok((msg_id, txt_raw, msgrmsg, mime_headers.unwrap_or_default()))
+                },
+                |rows| rows.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+            )
+            .await?;
+
+        let total = rows.len();
+        let mut last_progress = 0;
+        for (i, (msg_id, txt_raw, msgrmsg, mime_headers)) in rows.into_iter().enumerate() {
+            let txt = match self.reparse_text(msg_id, &mime_headers).await {
+                Some(txt) => txt,
+                None => text_from_raw(txt_raw, msgrmsg == 1),
+            };
+            self.sql
+                .execute("UPDATE msgs SET txt=? WHERE id=?", paramsv![txt, msg_id])
+                .await?;
+
+            let progress = 1 + 998 * i / total;
+            if progress > last_progress {
+                self.emit_event(EventType::ReindexProgress(progress));
+                last_progress = progress;
+            }
+        }
+
+        if total > 0 {
+            self.emit_msgs_changed_without_ids();
+        }
+        self.emit_event(EventType::ReindexProgress(1000));
+        Ok(())
+    }
+
+    /// Returns the text of the first text part of a stored MIME message, if any.
+    async fn reparse_text(&self, msg_id: MsgId, mime_headers: &[u8]) -> Option<String> {
+        if mime_headers.is_empty() {
+            return None;
+        }
+        match MimeMessage::from_bytes(self, mime_headers).await {
+            Ok(mime_message) => mime_message
+                .parts
+                .into_iter()
+                .find(|part| part.typ == Viewtype::Text)
+                .map(|part| part.msg),
+            Err(err) => {
+                warn!(self, "Cannot parse MIME message of {}: {}", msg_id, err);
+                None
+            }
+        }
+    }
+
+    /// Searches for chats whose name or member names or addresses contain the query string.
+    ///
+    /// Chats named exactly like the query are returned first,
+    /// followed by chats whose name contains the query and chats matched by members only.
+    /// Within these groups, newer chats come first.
+    pub async fn search_chats(&self, query: &str) -> Result<Vec<ChatId>> {
+        let real_query = query.trim();
+        if real_query.is_empty() {
+            return Ok(Vec::new());
+        }
+        let str_like = format!("%{}%", real_query);
+
+        self.sql
+            .query_map(
+                "SELECT c.id AS id,
+                        MIN(CASE WHEN LOWER(c.name)=LOWER(?1) THEN 0
+                                 WHEN c.name LIKE ?2 THEN 1
+                                 ELSE 2 END) AS rank
+                 FROM chats c
+                 LEFT JOIN chats_contacts cc
+                        ON cc.chat_id=c.id AND cc.contact_id>9
+                 LEFT JOIN contacts ct
+                        ON ct.id=cc.contact_id
+                 WHERE c.id>9
+                   AND c.blocked=0
+                   AND (c.name LIKE ?2
+                        OR ct.name LIKE ?2
+                        OR ct.authname LIKE ?2
+                        OR ct.addr LIKE ?2)
+                 GROUP BY c.id
+                 ORDER BY rank, c.id DESC",
+                paramsv![real_query, str_like],
+                |row| row.get::<_, ChatId>("id"),
+                |rows| rows.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+            )
+            .await
+    }
+
+    /// Returns the unblocked 1:1 chat with `contact_id`, creating it if needed.
+    ///
+    /// A contact request chat is accepted, see [`ChatId::create_for_contact`].
+    /// Special contacts other than [`ContactId::SELF`] are rejected.
+    pub async fn get_or_create_chat_by_contact(&self, contact_id: ContactId) -> Result<ChatId> {
+        ensure!(
+            !contact_id.is_special() || contact_id == ContactId::SELF,
+            "Cannot get chat for special contact {}",
+            contact_id
+        );
+        ChatId::create_for_contact(self, contact_id).await
+    }
+
     pub async fn is_inbox(&self, folder_name: &str) -> Result<bool> {
         let inbox = self.get_config(Config::ConfiguredInboxFolder).await?;
         Ok(inbox.as_deref() == Some(folder_name))
@@ -658,23 +1146,78 @@ pub fn get_version_str() -> &'static str {
     &DC_VERSION_STR
 }
 
+/// Converts the raw text stored for a received message to its displayed text,
+/// the same way as [`MimeMessage`] does when receiving.
+///
+/// `txt_raw` is the subject and the unsimplified text separated by an empty line.
+/// As the MIME type is not stored, HTML is detected by a leading tag;
+/// `format=flowed` cannot be detected and is not unflowed.
+fn text_from_raw(txt_raw: String, is_chat_message: bool) -> String {
+    let raw = match txt_raw.split_once("\n\n") {
+        Some((_subject, raw)) => raw.to_string(),
+        None => txt_raw,
+    };
+    let raw = if raw.trim_start().starts_with('<') {
+        dehtml(&raw).unwrap_or(raw)
+    } else {
+        raw
+    };
+    let (txt, ..) = simplify(raw, is_chat_message);
+    if txt.chars().count() > DC_DESIRED_TEXT_LEN + DC_ELLIPSIS.len() {
+        dc_truncate(&txt, DC_DESIRED_TEXT_LEN).to_string()
+    } else {
+        txt
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     use crate::chat::{
-        get_chat_contacts, get_chat_msgs, send_msg, set_muted, Chat, ChatId, MuteDuration,
+        add_contact_to_chat, create_group_chat, get_chat_contacts, get_chat_msgs, marknoticed_chat,
+        send_msg, set_muted, Chat, ChatId, MuteDuration, ProtectionStatus,
     };
-    use crate::contact::ContactId;
+    use crate::contact::Origin;
     use crate::dc_receive_imf::dc_receive_imf;
     use crate::dc_tools::dc_create_outgoing_rfc724_mid;
-    use crate::message::{Message, Viewtype};
     use crate::test_utils::TestContext;
     use anyhow::Context as _;
     use std::time::Duration;
     use strum::IntoEnumIterator;
     use tempfile::tempdir;
 
+    #[async_std::test]
+    async fn test_get_or_create_chat_by_contact() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        let contact_id = Contact::create(&t, "Bob", "bob@example.net").await?;
+
+        let chat_id = t.get_or_create_chat_by_contact(contact_id).await?;
+        assert_eq!(t.get_or_create_chat_by_contact(contact_id).await?, chat_id);
+        let chat = Chat::load_from_db(&t, chat_id).await?;
+        assert_eq!(chat.blocked, Blocked::Not);
+
+        // A contact request is accepted.
+        let contact_id = Contact::create(&t, "Fiona", "fiona@example.net").await?;
+        let request_id =
+            ChatId::create_for_contact_with_blocked(&t, contact_id, Blocked::Request).await?;
+        assert_eq!(
+            t.get_or_create_chat_by_contact(contact_id).await?,
+            request_id
+        );
+        let chat = Chat::load_from_db(&t, request_id).await?;
+        assert_eq!(chat.blocked, Blocked::Not);
+
+        let self_chat_id = t.get_or_create_chat_by_contact(ContactId::SELF).await?;
+        assert!(self_chat_id.is_self_talk(&t).await?);
+
+        assert!(t
+            .get_or_create_chat_by_contact(ContactId::DEVICE)
+            .await
+            .is_err());
+        Ok(())
+    }
+
     #[async_std::test]
     async fn test_wrong_db() -> Result<()> {
         let tmp = tempfile::tempdir()?;
@@ -694,6 +1237,47 @@ async fn test_get_fresh_msgs() {
         assert!(fresh.is_empty())
     }
 
+    #[async_std::test]
+    async fn test_get_contact_request_chats() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        assert!(t.get_contact_request_chats().await?.is_empty());
+
+        let mut chat_ids = Vec::new();
+        for (i, addr) in ["bob@example.net", "claire@example.org"].iter().enumerate() {
+            let msg = format!(
+                "From: {}\n\
+                 To: alice@example.org\n\
+                 Message-ID: <request{}@example.net>\n\
+                 Chat-Version: 1.0\n\
+                 Date: Sun, 22 Mar 2020 22:3{}:57 +0000\n\
+                 \n\
+                 hello\n",
+                addr, i, i
+            );
+            dc_receive_imf(&t, msg.as_bytes(), false).await?;
+            chat_ids.push(t.get_last_msg().await.chat_id);
+        }
+        let claire_chat_id = chat_ids.pop().unwrap();
+        let bob_chat_id = chat_ids.pop().unwrap();
+        assert_eq!(
+            t.get_contact_request_chats().await?,
+            vec![claire_chat_id, bob_chat_id]
+        );
+
+        bob_chat_id.accept(&t).await?;
+        assert_eq!(t.get_contact_request_chats().await?, vec![claire_chat_id]);
+        let chat = Chat::load_from_db(&t, bob_chat_id).await?;
+        assert_eq!(chat.blocked, Blocked::Not);
+
+        claire_chat_id.block(&t).await?;
+        assert!(t.get_contact_request_chats().await?.is_empty());
+        let claire_id = Contact::lookup_id_by_addr(&t, "claire@example.org", Origin::Unknown)
+            .await?
+            .unwrap();
+        assert!(Contact::load_from_db(&t, claire_id).await?.is_blocked());
+        Ok(())
+    }
+
     async fn receive_msg(t: &TestContext, chat: &Chat) {
         let members = get_chat_contacts(t, chat.id).await.unwrap();
         let contact = Contact::load_from_db(t, *members.first().unwrap())
@@ -764,6 +1348,66 @@ async fn test_get_fresh_msgs_and_muted_chats() {
         assert_eq!(t.get_fresh_msgs().await.unwrap().len(), 9); // claire is counted again
     }
 
+    #[async_std::test]
+    async fn test_get_fresh_msgs_in_chat() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        let bob = t.create_chat_with_contact("", "bob@g.it").await;
+        let claire = t.create_chat_with_contact("", "claire@g.it").await;
+        assert!(t.get_fresh_msgs_in_chat(bob.id).await?.is_empty());
+
+        receive_msg(&t, &bob).await;
+        receive_msg(&t, &claire).await;
+        receive_msg(&t, &bob).await;
+        let bob_msgs = t.get_fresh_msgs_in_chat(bob.id).await?;
+        assert_eq!(bob_msgs.len(), 2);
+        assert_eq!(t.get_fresh_msgs_in_chat(claire.id).await?.len(), 1);
+        assert_eq!(
+            t.get_fresh_msgs()
+                .await?
+                .into_iter()
+                .filter(|msg_id| bob_msgs.contains(msg_id))
+                .collect::<Vec<_>>(),
+            bob_msgs
+        );
+
+        // Muted chats have no fresh messages.
+        set_muted(&t, claire.id, MuteDuration::Forever).await?;
+        assert!(t.get_fresh_msgs_in_chat(claire.id).await?.is_empty());
+        assert_eq!(t.get_fresh_msgs_in_chat(bob.id).await?, bob_msgs);
+
+        marknoticed_chat(&t, bob.id).await?;
+        assert!(t.get_fresh_msgs_in_chat(bob.id).await?.is_empty());
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn test_get_fresh_msgs_notify_on_mention() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        let bob = t.create_chat_with_contact("", "bob@g.it").await;
+        set_muted(&t, bob.id, MuteDuration::Forever).await?;
+        receive_msg(&t, &bob).await;
+        dc_receive_imf(
+            &t,
+            b"From: bob@g.it\n\
+              To: alice@example.org\n\
+              Message-ID: <mention@g.it>\n\
+              Chat-Version: 1.0\n\
+              Date: Sun, 22 Mar 2020 22:37:58 +0000\n\
+              \n\
+              hello @alice@example.org\n",
+            false,
+        )
+        .await?;
+        assert_eq!(t.get_fresh_msgs().await?.len(), 0);
+
+        t.set_config_bool(Config::NotifyOnMention, true).await?;
+        let fresh = t.get_fresh_msgs().await?;
+        assert_eq!(fresh.len(), 1);
+        let msg = Message::load_from_db(&t, fresh[0]).await?;
+        assert_eq!(msg.get_mentions(&t).await?, vec![ContactId::SELF]);
+        Ok(())
+    }
+
     #[async_std::test]
     async fn test_get_fresh_msgs_and_muted_until() {
         let t = TestContext::new_alice().await;
@@ -884,6 +1528,35 @@ async fn test_get_info() {
         assert!(info.get("database_dir").is_some());
     }
 
+    #[async_std::test]
+    async fn test_get_info_struct() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        t.set_config_bool(Config::BccSelf, false).await?;
+
+        let info = t.get_info_struct().await?;
+        assert!(info.is_configured);
+        assert!(!info.bcc_self);
+        assert!(info.e2ee_enabled);
+        assert_eq!(info.private_key_count, 1);
+        assert_eq!(info.language, None);
+        assert_eq!(info.database_dir.as_path(), t.get_dbfile());
+        assert_eq!(
+            info.fingerprint,
+            SignedPublicKey::load_self(&t).await?.fingerprint().hex()
+        );
+
+        let map = t.get_info().await?;
+        assert_eq!(map.get("is_configured").unwrap(), "1");
+        assert_eq!(map.get("bcc_self").unwrap(), "0");
+        assert_eq!(map.get("language").unwrap(), "<unset>");
+        assert_eq!(map.get("fingerprint").unwrap(), &info.fingerprint);
+        assert_eq!(
+            map.get("number_of_chats").unwrap(),
+            &info.number_of_chats.to_string()
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_get_info_no_context() {
         let info = get_info();
@@ -940,6 +1613,338 @@ async fn test_get_info_completeness() {
         }
     }
 
+    #[async_std::test]
+    async fn test_get_notification_text() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+        let bob = TestContext::new_bob().await;
+        bob.set_config(Config::Displayname, Some("Bob")).await?;
+        let bob_chat_id = bob.create_chat(&alice).await.id;
+        let sent = bob.send_text(bob_chat_id, "Secret plans").await;
+        let msg = alice.recv_msg(&sent).await;
+
+        assert_eq!(alice.get_notification_text(msg.id).await?, "Secret plans");
+
+        alice
+            .set_config(Config::NotificationPreview, Some("1"))
+            .await?;
+        assert_eq!(
+            alice.get_notification_text(msg.id).await?,
+            "Message from Bob"
+        );
+
+        alice
+            .set_config(Config::NotificationPreview, Some("2"))
+            .await?;
+        assert_eq!(alice.get_notification_text(msg.id).await?, "New message");
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn test_notification_info() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        let bob = t.create_chat_with_contact("", "bob@g.it").await;
+        set_muted(&t, bob.id, MuteDuration::Forever).await?;
+        t.set_config_bool(Config::NotifyOnMention, true).await?;
+
+        receive_msg(&t, &bob).await;
+        let msg = t.get_last_msg_in(bob.id).await;
+        let info = t.notification_info(msg.id).await?;
+        assert!(!info.notify);
+        assert_eq!(info.chat_id, bob.id);
+        assert_eq!(info.from_id, msg.from_id);
+
+        // Mentions notify even in muted chats.
+        dc_receive_imf(
+            &t,
+            b"From: bob@g.it\n\
+              To: alice@example.org\n\
+              Message-ID: <mention@g.it>\n\
+              Chat-Version: 1.0\n\
+              Date: Sun, 22 Mar 2020 22:37:58 +0000\n\
+              \n\
+              hello @alice@example.org\n",
+            false,
+        )
+        .await?;
+        let msg = t.get_last_msg_in(bob.id).await;
+        let info = t.notification_info(msg.id).await?;
+        assert!(info.notify);
+        assert_eq!(info.text, "hello @alice@example.org");
+
+        // Info messages are silent, even in unmuted chats.
+        set_muted(&t, bob.id, MuteDuration::NotMuted).await?;
+        dc_receive_imf(
+            &t,
+            b"From: bob@g.it\n\
+              To: alice@example.org\n\
+              Message-ID: <timer@g.it>\n\
+              Chat-Version: 1.0\n\
+              Chat-Content: ephemeral-timer-changed\n\
+              Ephemeral-Timer: 60\n\
+              Date: Sun, 22 Mar 2020 22:37:59 +0000\n\
+              \n\
+              Message deletion timer is set to 1 minute.\n",
+            false,
+        )
+        .await?;
+        let msg = t.get_last_msg_in(bob.id).await;
+        assert!(msg.is_info());
+        assert!(!t.notification_info(msg.id).await?.notify);
+
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn test_search_chats() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+        let bob_chat = alice
+            .create_chat_with_contact("Bob", "bob@example.org")
+            .await;
+        let marketing =
+            create_group_chat(&alice, ProtectionStatus::Unprotected, "Marketing").await?;
+        let marketing_team =
+            create_group_chat(&alice, ProtectionStatus::Unprotected, "Marketing team").await?;
+        let sales = create_group_chat(&alice, ProtectionStatus::Unprotected, "Sales").await?;
+        let bob_id = Contact::create(&alice, "", "bob@example.org").await?;
+        add_contact_to_chat(&alice, sales, bob_id).await?;
+
+        assert!(alice.search_chats("  ").await?.is_empty());
+        assert!(alice.search_chats("engineering").await?.is_empty());
+
+        // Exact name matches come first.
+        assert_eq!(
+            alice.search_chats("marketing").await?,
+            vec![marketing, marketing_team]
+        );
+        assert_eq!(alice.search_chats("team").await?, vec![marketing_team]);
+
+        // Chats are found by member name and address.
+        assert_eq!(alice.search_chats("bob").await?, vec![bob_chat.id, sales]);
+        assert_eq!(
+            alice.search_chats("example.org").await?,
+            vec![sales, bob_chat.id]
+        );
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn test_on_low_memory() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        *t.quota.write().await = Some(QuotaInfo {
+            recent: Ok(BTreeMap::new()),
+            modified: time(),
+        });
+
+        t.on_low_memory().await?;
+        assert!(t.quota.read().await.is_none());
+
+        // The database is still usable.
+        assert_eq!(
+            t.get_config(Config::Addr).await?,
+            Some("alice@example.org".to_string())
+        );
+        t.on_low_memory().await?;
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn test_reindex_search() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+        let bob = TestContext::new_bob().await;
+        let chat_id = bob.create_chat(&alice).await.id;
+        let sent = bob.send_text(chat_id, "lorem ipsum").await;
+        let msg = alice.recv_msg(&sent).await;
+        assert_eq!(alice.search_msgs(None, "ipsum").await?, vec![msg.id]);
+
+        alice
+            .sql
+            .execute("UPDATE msgs SET txt='' WHERE id=?", paramsv![msg.id])
+            .await?;
+        assert!(alice.search_msgs(None, "ipsum").await?.is_empty());
+
+        alice.reindex_search().await?;
+        alice
+            .evtracker
+            .get_matching(|evt| matches!(evt, EventType::ReindexProgress(1000)))
+            .await;
+        assert_eq!(alice.search_msgs(None, "ipsum").await?, vec![msg.id]);
+        assert_eq!(
+            Message::load_from_db(&alice, msg.id).await?.get_text(),
+            Some("lorem ipsum".to_string())
+        );
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn test_reindex_search_html() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        t.set_config(Config::ShowEmails, Some("2")).await?;
+        dc_receive_imf(
+            &t,
+            b"From: bob@example.net\n\
+              To: alice@example.org\n\
+              Subject: html\n\
+              Message-ID: <html@example.net>\n\
+              Date: Sun, 22 Mar 2020 22:37:57 +0000\n\
+              Content-Type: text/html; charset=utf-8\n\
+              \n\
+              <html><body><p>lorem ipsum</p></body></html>\n",
+            false,
+        )
+        .await?;
+        let msg = t.get_last_msg().await;
+        assert_eq!(msg.get_text(), Some("lorem ipsum".to_string()));
+
+        // the stored MIME message is parsed again
+        t.sql
+            .execute("UPDATE msgs SET txt='' WHERE id=?", paramsv![msg.id])
+            .await?;
+        t.reindex_search().await?;
+        assert_eq!(
+            Message::load_from_db(&t, msg.id).await?.get_text(),
+            Some("lorem ipsum".to_string())
+        );
+
+        // without MIME message, the raw text is converted from HTML
+        t.sql
+            .execute(
+                "UPDATE msgs SET txt='', mime_headers=NULL WHERE id=?",
+                paramsv![msg.id],
+            )
+            .await?;
+        t.reindex_search().await?;
+        assert_eq!(
+            Message::load_from_db(&t, msg.id).await?.get_text(),
+            Some("lorem ipsum".to_string())
+        );
+        assert_eq!(t.search_msgs(None, "<p>").await?, Vec::new());
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn test_search_msgs_stream() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+        let chat = alice
+            .create_chat_with_contact("Bob", "bob@example.org")
+            .await;
+        let mut ids = Vec::new();
+        for i in 0..5 {
+            let mut msg = Message::new(Viewtype::Text);
+            msg.set_text(Some(format!("foo {}", i)));
+            ids.push(send_msg(&alice, chat.id, &mut msg).await?);
+        }
+
+        // Results span several pages and are in the same order as returned by `search_msgs()`.
+        let res: Vec<MsgId> = alice
+            .search_msgs_paged(Some(chat.id), None, "foo", None, 2)
+            .try_collect()
+            .await?;
+        assert_eq!(res, ids);
+        assert_eq!(res, alice.search_msgs(Some(chat.id), "foo").await?);
+
+        let res: Vec<MsgId> = alice
+            .search_msgs_paged(None, None, "foo", None, 2)
+            .try_collect()
+            .await?;
+        assert_eq!(res, ids.iter().rev().copied().collect::<Vec<_>>());
+        assert_eq!(res, alice.search_msgs(None, "foo").await?);
+
+        // The limit can be set explicitly.
+        let res: Vec<MsgId> = alice
+            .search_msgs_paged(None, None, "foo", Some(3), 2)
+            .try_collect()
+            .await?;
+        assert_eq!(res, ids.iter().rev().take(3).copied().collect::<Vec<_>>());
+
+        // The first result is available without loading all results.
+        let mut stream = Box::pin(alice.search_msgs_stream(Some(chat.id), "foo", None));
+        assert_eq!(stream.try_next().await?, ids.first().copied());
+
+        let res: Vec<MsgId> = alice
+            .search_msgs_stream(None, " ", None)
+            .try_collect()
+            .await?;
+        assert!(res.is_empty());
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn test_search_msgs_by_contact() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+        let bob_id = Contact::create(&alice, "Bob", "bob@example.net").await?;
+        let bob_chat_id = ChatId::create_for_contact(&alice, bob_id).await?;
+        let claire_id = Contact::create(&alice, "Claire", "claire@example.net").await?;
+        let claire_chat_id = ChatId::create_for_contact(&alice, claire_id).await?;
+
+        let mut msg = Message::new(Viewtype::Text);
+        msg.set_text(Some("foo from alice".to_string()));
+        let alice_msg_id = send_msg(&alice, bob_chat_id, &mut msg).await?;
+
+        let receive = |from: &str, mid: &str, text: &str| {
+            format!(
+                "From: {}\n\
+                 To: alice@example.org\n\
+                 Chat-Version: 1.0\n\
+                 Message-ID: <{}>\n\
+                 Date: Fri, 29 Jan 2021 21:37:55 +0000\n\
+                 \n\
+                 {}\n",
+                from, mid, text
+            )
+        };
+        dc_receive_imf(
+            &alice,
+            receive("bob@example.net", "1@example.net", "foo from bob").as_bytes(),
+            false,
+        )
+        .await?;
+        let bob_msg = alice.get_last_msg_in(bob_chat_id).await;
+        assert_eq!(bob_msg.get_from_id(), bob_id);
+        dc_receive_imf(
+            &alice,
+            receive("bob@example.net", "2@example.net", "bar from bob").as_bytes(),
+            false,
+        )
+        .await?;
+        dc_receive_imf(
+            &alice,
+            receive("claire@example.net", "3@example.net", "foo from claire").as_bytes(),
+            false,
+        )
+        .await?;
+        let claire_msg = alice.get_last_msg_in(claire_chat_id).await;
+        assert_eq!(claire_msg.get_from_id(), claire_id);
+
+        assert_eq!(
+            alice.search_msgs_by_contact(None, bob_id, "foo").await?,
+            vec![bob_msg.id]
+        );
+        assert_eq!(
+            alice
+                .search_msgs_by_contact(Some(bob_chat_id), bob_id, "foo")
+                .await?,
+            vec![bob_msg.id]
+        );
+        assert_eq!(
+            alice
+                .search_msgs_by_contact(Some(bob_chat_id), ContactId::SELF, "foo")
+                .await?,
+            vec![alice_msg_id]
+        );
+        assert!(alice
+            .search_msgs_by_contact(Some(bob_chat_id), claire_id, "foo")
+            .await?
+            .is_empty());
+        assert_eq!(
+            alice.search_msgs_by_contact(None, claire_id, "foo").await?,
+            vec![claire_msg.id]
+        );
+
+        // Without contact, messages from all senders are found.
+        assert_eq!(alice.search_msgs(None, "foo").await?.len(), 3);
+        Ok(())
+    }
+
     #[async_std::test]
     async fn test_search_msgs() -> Result<()> {
         let alice = TestContext::new_alice().await;
@@ -12,7 +12,7 @@
 
 use crate::chat::{self, Chat, ChatId, ChatIdBlocked, ProtectionStatus};
 use crate::config::Config;
-use crate::constants::{Blocked, Chattype, ShowEmails, DC_CHAT_ID_TRASH};
+use crate::constants::{AutoAcceptGroupInvites, Blocked, Chattype, ShowEmails, DC_CHAT_ID_TRASH};
 use crate::contact;
 use crate::contact::{
     may_be_valid_addr, normalize_name, Contact, ContactId, Origin, VerifiedStatus,
@@ -26,6 +26,7 @@
 use crate::imap::markseen_on_imap_table;
 use crate::location;
 use crate::log::LogExt;
+use crate::mention;
 use crate::message::{
     self, rfc724_mid_exists, Message, MessageState, MessengerMessage, MsgId, Viewtype,
 };
@@ -38,6 +39,10 @@
 use crate::sql;
 use crate::stock_str;
 
+/// Number of seconds the `Date` header of a message may be in the future
+/// before the message is flagged with [`Message::had_future_timestamp`].
+pub(crate) const FUTURE_TIMESTAMP_TOLERANCE: i64 = 60 * 60;
+
 /// This is the struct that is returned after receiving one email (aka MIME message).
 ///
 /// One email with multiple attachments can end up as multiple chat messages, but they
@@ -124,7 +129,11 @@ pub(crate) async fn dc_receive_imf_inner(
     let replace_partial_download =
         if let Some(old_msg_id) = message::rfc724_mid_exists(context, rfc724_mid).await? {
             let msg = Message::load_from_db(context, old_msg_id).await?;
-            if msg.download_state() != DownloadState::Done && is_partial_download.is_none() {
+            let partially_downloaded = matches!(
+                msg.download_state(),
+                DownloadState::Available | DownloadState::Failure | DownloadState::InProgress
+            );
+            if partially_downloaded && is_partial_download.is_none() {
                 // the mesage was partially downloaded before and is fully downloaded now.
                 info!(
                     context,
@@ -173,10 +182,24 @@ pub(crate) async fn dc_receive_imf_inner(
     .await?;
 
     let rcvd_timestamp = dc_smeared_time(context).await;
-    let sent_timestamp = mime_parser
+    let claimed_timestamp = mime_parser
         .get_header(HeaderDef::Date)
-        .and_then(|value| mailparse::dateparse(value).ok())
-        .map_or(rcvd_timestamp, |value| min(value, rcvd_timestamp));
+        .and_then(|value| mailparse::dateparse(value).ok());
+    let sent_timestamp =
+        claimed_timestamp.map_or(rcvd_timestamp, |value| min(value, rcvd_timestamp));
+    if let Some(claimed_timestamp) = claimed_timestamp {
+        if claimed_timestamp > rcvd_timestamp.saturating_add(FUTURE_TIMESTAMP_TOLERANCE) {
+            warn!(
+                context,
+                "Message {} is dated {} seconds in the future, using receive time.",
+                rfc724_mid,
+                claimed_timestamp - rcvd_timestamp
+            );
+            for part in &mut mime_parser.parts {
+                part.param.set_int(Param::HadFutureTimestamp, 1);
+            }
+        }
+    }
 
     // Add parts
     let received_msg = add_parts(
@@ -324,9 +347,22 @@ pub(crate) async fn dc_receive_imf_inner(
         context.emit_msgs_changed(chat_id, MsgId::new(0));
     } else if !chat_id.is_trash() {
         let fresh = received_msg.state == MessageState::InFresh;
+        let notify = if incoming && fresh {
+            let chat_type = Chat::load_from_db(context, chat_id).await?.typ;
+            let mut is_mention = false;
+            for part in &mime_parser.parts {
+                if mention::is_self_mentioned(context, &part.msg).await? {
+                    is_mention = true;
+                    break;
+                }
+            }
+            Some((chat_type, is_mention))
+        } else {
+            None
+        };
         for msg_id in &received_msg.msg_ids {
-            if incoming && fresh {
-                context.emit_incoming_msg(chat_id, *msg_id);
+            if let Some((chat_type, is_mention)) = notify {
+                context.emit_incoming_msg(chat_id, *msg_id, chat_type, is_mention);
             } else {
                 context.emit_msgs_changed(chat_id, *msg_id);
             };
@@ -494,6 +530,15 @@ async fn add_parts(
             ChatIdBlocked::lookup_by_contact(context, from_id).await?
         };
 
+        if chat_id.is_none()
+            && context
+                .is_any_fingerprint_blocked(&mime_parser.signatures)
+                .await?
+        {
+            chat_id = Some(DC_CHAT_ID_TRASH);
+            info!(context, "Message is signed by a blocked key (TRASH)");
+        }
+
         if chat_id.is_none() && mime_parser.failure_report.is_some() {
             chat_id = Some(DC_CHAT_ID_TRASH);
             info!(context, "Message belongs to an NDN (TRASH)",);
@@ -518,6 +563,9 @@ async fn add_parts(
                     id: _,
                     blocked: Blocked::Not,
                 }) => Blocked::Not,
+                _ if is_auto_accepted_group_invite(context, mime_parser, from_id).await? => {
+                    Blocked::Not
+                }
                 _ => Blocked::Request,
             };
 
@@ -823,6 +871,12 @@ async fn add_parts(
         }
     }
 
+    if mime_parser.is_system_message == SystemMessage::AutocryptAnnouncement {
+        // The Autocrypt header is already applied at this point.
+        chat_id = Some(DC_CHAT_ID_TRASH);
+        info!(context, "Message is an Autocrypt announcement (TRASH)");
+    }
+
     if is_mdn {
         chat_id = Some(DC_CHAT_ID_TRASH);
     }
@@ -932,6 +986,16 @@ async fn add_parts(
         // hour, only the message about the change to 1
         // week is left.
         ephemeral_timer = EphemeralTimer::Disabled;
+    } else if let Some(value) = mime_parser.get_header(HeaderDef::ChatMessageEphemeralTimer) {
+        // The timer applies to this message only, the chat's timer is not changed.
+        match value.parse::<EphemeralTimer>() {
+            Ok(EphemeralTimer::Disabled) => {}
+            Ok(timer) => ephemeral_timer = timer,
+            Err(err) => warn!(
+                context,
+                "can't parse message ephemeral timer \"{}\": {}", value, err
+            ),
+        }
     }
 
     // if a chat is protected and the message is fully downloaded, check additional properties
@@ -1081,6 +1145,21 @@ async fn add_parts(
         if is_system_message != SystemMessage::Unknown {
             param.set_int(Param::Cmd, is_system_message as i32);
         }
+        // Remember the affected member so the change can be queried later,
+        // see `chat::get_chat_audit_log()`.
+        let member_header = match is_system_message {
+            SystemMessage::MemberAddedToGroup => Some(HeaderDef::ChatGroupMemberAdded),
+            SystemMessage::MemberRemovedFromGroup => Some(HeaderDef::ChatGroupMemberRemoved),
+            _ => None,
+        };
+        if let Some(addr) = member_header.and_then(|header| mime_parser.get_header(header)) {
+            param.set(Param::Arg, addr);
+        }
+        if is_system_message == SystemMessage::MemberAddedToGroup {
+            if let Some(addrs) = mime_parser.get_header(HeaderDef::ChatGroupMembersAdded) {
+                param.set(Param::Arg3, addrs);
+            }
+        }
 
         let ephemeral_timestamp = if in_fresh {
             0
@@ -1131,6 +1210,8 @@ async fn add_parts(
             ephemeral_timestamp,
             if is_partial_download.is_some() {
                 DownloadState::Available
+            } else if mime_parser.decrypting_failed {
+                DownloadState::Undecipherable
             } else {
                 DownloadState::Done
             },
@@ -1143,6 +1224,12 @@ async fn add_parts(
     }
     drop(conn);
 
+    if !chat_id.is_special() {
+        for (part, msg_id) in mime_parser.parts.iter().zip(&created_db_entries) {
+            mention::save_mentions(context, *msg_id, chat_id, &part.msg).await?;
+        }
+    }
+
     chat_id.unarchive_if_not_muted(context).await?;
 
     info!(
@@ -1345,6 +1432,32 @@ async fn is_probably_private_reply(
     Ok(true)
 }
 
+/// Returns true if the message is a group message from a sender
+/// whose group invites are accepted according to [`Config::AutoAcceptGroupInvites`].
+async fn is_auto_accepted_group_invite(
+    context: &Context,
+    mime_parser: &MimeMessage,
+    from_id: ContactId,
+) -> Result<bool> {
+    if from_id == ContactId::SELF || mime_parser.get_header(HeaderDef::ChatGroupId).is_none() {
+        return Ok(false);
+    }
+    let mode = AutoAcceptGroupInvites::from_i32(
+        context
+            .get_config_int(Config::AutoAcceptGroupInvites)
+            .await?,
+    )
+    .unwrap_or_default();
+    match mode {
+        AutoAcceptGroupInvites::Off => Ok(false),
+        AutoAcceptGroupInvites::VerifiedOnly => {
+            let contact = Contact::load_from_db(context, from_id).await?;
+            Ok(contact.is_verified(context).await? == VerifiedStatus::BidirectVerified)
+        }
+        AutoAcceptGroupInvites::All => Ok(true),
+    }
+}
+
 /// This function tries to extract the group-id from the message and returns the corresponding
 /// chat_id. If the chat does not exist, it is created. If there is no group-id and there are more
 /// than two members, a new ad hoc group is created.
@@ -1414,11 +1527,19 @@ async fn self_explicitly_added(
         context: &Context,
         mime_parser: &&mut MimeMessage,
     ) -> Result<bool> {
-        let ret = match mime_parser.get_header(HeaderDef::ChatGroupMemberAdded) {
-            Some(member_addr) => context.is_self_addr(member_addr).await?,
-            None => false,
-        };
-        Ok(ret)
+        if let Some(member_addr) = mime_parser.get_header(HeaderDef::ChatGroupMemberAdded) {
+            if context.is_self_addr(member_addr).await? {
+                return Ok(true);
+            }
+        }
+        if let Some(member_addrs) = mime_parser.get_header(HeaderDef::ChatGroupMembersAdded) {
+            for member_addr in split_addr_list(member_addrs) {
+                if context.is_self_addr(&member_addr).await? {
+                    return Ok(true);
+                }
+            }
+        }
+        Ok(false)
     }
 
     if chat_id.is_none()
@@ -1545,12 +1666,24 @@ async fn apply_group_changes(
             .get_header(HeaderDef::ChatGroupMemberAdded)
             .cloned()
         {
-            better_msg = Some(stock_str::msg_add_member(context, &added_member, from_id).await);
+            better_msg = Some(
+                match mime_parser.get_header(HeaderDef::ChatGroupMembersAdded) {
+                    Some(added_members) => {
+                        stock_str::msg_add_members(
+                            context,
+                            &split_addr_list(added_members),
+                            from_id,
+                        )
+                        .await
+                    }
+                    None => stock_str::msg_add_member(context, &added_member, from_id).await,
+                },
+            );
             recreate_member_list = true;
         } else if let Some(old_name) = mime_parser.get_header(HeaderDef::ChatGroupNameChanged) {
             if let Some(grpname) = mime_parser
                 .get_header(HeaderDef::ChatGroupName)
-                .filter(|grpname| grpname.len() < 200)
+                .filter(|grpname| grpname.chars().count() <= chat::MAX_CHAT_NAME_CHARS)
             {
                 if chat_id
                     .update_timestamp(context, Param::GroupNameTimestamp, sent_timestamp)
@@ -2069,6 +2202,15 @@ async fn check_verified_properties(
     Ok(())
 }
 
+/// Splits the comma-separated address list of the `Chat-Group-Members-Added` header.
+fn split_addr_list(addrs: &str) -> Vec<String> {
+    addrs
+        .split(',')
+        .map(|addr| addr.trim().to_string())
+        .filter(|addr| !addr.is_empty())
+        .collect()
+}
+
 /// Returns the last message referenced from `References` header if it is in the database.
 ///
 /// For Delta Chat messages it is the last message in the chat of the sender.
@@ -2212,10 +2354,49 @@ mod tests {
     use crate::chat::{get_chat_msgs, ChatItem, ChatVisibility};
     use crate::chatlist::Chatlist;
     use crate::constants::DC_GCL_NO_SPECIALS;
+    use crate::dc_tools::time;
     use crate::imap::prefetch_should_download;
     use crate::message::Message;
     use crate::test_utils::{get_chat_msg, TestContext, TestContextManager};
 
+    #[async_std::test]
+    async fn test_future_timestamp() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        let next_year = (chrono::Utc::now() + chrono::Duration::days(365)).to_rfc2822();
+        let raw = format!(
+            "From: bob@example.net\n\
+             To: alice@example.org\n\
+             Message-ID: <future@example.net>\n\
+             Date: {}\n\
+             Chat-Version: 1.0\n\
+             \n\
+             Hello from the future\n",
+            next_year
+        );
+        dc_receive_imf(&t, raw.as_bytes(), false).await?;
+        let msg = t.get_last_msg().await;
+        assert_eq!(msg.get_text(), Some("Hello from the future".to_string()));
+        assert!(msg.had_future_timestamp());
+        assert!(msg.get_timestamp() <= time() + 60);
+        assert!(msg.timestamp_sort <= time() + 60);
+
+        let raw = format!(
+            "From: bob@example.net\n\
+             To: alice@example.org\n\
+             Message-ID: <present@example.net>\n\
+             Date: {}\n\
+             Chat-Version: 1.0\n\
+             \n\
+             Hello from the present\n",
+            chrono::Utc::now().to_rfc2822()
+        );
+        dc_receive_imf(&t, raw.as_bytes(), false).await?;
+        let msg = t.get_last_msg().await;
+        assert_eq!(msg.get_text(), Some("Hello from the present".to_string()));
+        assert!(!msg.had_future_timestamp());
+        Ok(())
+    }
+
     #[async_std::test]
     async fn test_grpid_simple() {
         let context = TestContext::new().await;
@@ -4569,7 +4750,9 @@ async fn test_incoming_contact_request() -> Result<()> {
                 .get_matching(|evt| matches!(evt, EventType::IncomingMsg { .. }))
                 .await;
             match event {
-                EventType::IncomingMsg { chat_id, msg_id } => {
+                EventType::IncomingMsg {
+                    chat_id, msg_id, ..
+                } => {
                     assert_eq!(msg.chat_id, chat_id);
                     assert_eq!(msg.id, msg_id);
                     return Ok(());
@@ -4579,6 +4762,39 @@ async fn test_incoming_contact_request() -> Result<()> {
         }
     }
 
+    #[async_std::test]
+    async fn test_incoming_msg_mention() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+        let bob = TestContext::new_bob().await;
+        let bob_chat_id =
+            chat::create_group_chat(&bob, ProtectionStatus::Unprotected, "group").await?;
+        let alice_contact = bob.add_or_lookup_contact(&alice).await;
+        chat::add_contact_to_chat(&bob, bob_chat_id, alice_contact.id).await?;
+
+        let sent = bob
+            .send_text(bob_chat_id, "what do you think, alice@example.org?")
+            .await;
+        let msg = alice.recv_msg(&sent).await;
+        let event = alice
+            .evtracker
+            .get_matching(|evt| matches!(evt, EventType::IncomingMsg { .. }))
+            .await;
+        match event {
+            EventType::IncomingMsg {
+                msg_id,
+                chat_type,
+                is_mention,
+                ..
+            } => {
+                assert_eq!(msg_id, msg.id);
+                assert_eq!(chat_type, Chattype::Group);
+                assert!(is_mention);
+            }
+            _ => unreachable!(),
+        }
+        Ok(())
+    }
+
     #[async_std::test]
     async fn test_get_parent_message() -> Result<()> {
         let t = TestContext::new_alice().await;
@@ -4987,4 +5203,69 @@ async fn test_no_private_reply_to_blocked_account() -> Result<()> {
 
         Ok(())
     }
+
+    async fn receive_group_invite(mode: AutoAcceptGroupInvites, verified: bool) -> Result<Blocked> {
+        let mut tcm = TestContextManager::new().await;
+        let alice = tcm.alice().await;
+        let bob = tcm.bob().await;
+        alice
+            .set_config(
+                Config::AutoAcceptGroupInvites,
+                Some(&(mode as u8).to_string()),
+            )
+            .await?;
+
+        // Alice learns Bob's key, the 1:1 chat stays a contact request.
+        let bob_chat_id = bob.create_chat(&alice).await.id;
+        alice
+            .recv_msg(&bob.send_text(bob_chat_id, "hi").await)
+            .await;
+        if verified {
+            let mut peerstate = Peerstate::from_addr(&alice, "bob@example.net")
+                .await?
+                .unwrap();
+            let fingerprint = peerstate.public_key_fingerprint.clone().unwrap();
+            assert!(peerstate.set_verified(
+                PeerstateKeyType::PublicKey,
+                &fingerprint,
+                PeerstateVerifiedStatus::BidirectVerified
+            ));
+            peerstate.save_to_db(&alice.sql, false).await?;
+        }
+
+        let group_id =
+            chat::create_group_chat(&bob, ProtectionStatus::Unprotected, "Group").await?;
+        chat::add_to_chat_contacts_table(
+            &bob,
+            group_id,
+            bob.add_or_lookup_contact(&alice).await.id,
+        )
+        .await?;
+        alice
+            .recv_msg(&bob.send_text(group_id, "Hello all!").await)
+            .await;
+
+        let received = alice.get_last_msg().await;
+        let chat = Chat::load_from_db(&alice, received.chat_id).await?;
+        assert_eq!(chat.typ, Chattype::Group);
+        Ok(chat.blocked)
+    }
+
+    #[async_std::test]
+    async fn test_auto_accept_group_invites() -> Result<()> {
+        use AutoAcceptGroupInvites::*;
+
+        assert_eq!(receive_group_invite(Off, false).await?, Blocked::Request);
+        assert_eq!(receive_group_invite(Off, true).await?, Blocked::Request);
+        assert_eq!(
+            receive_group_invite(VerifiedOnly, false).await?,
+            Blocked::Request
+        );
+        assert_eq!(
+            receive_group_invite(VerifiedOnly, true).await?,
+            Blocked::Not
+        );
+        assert_eq!(receive_group_invite(All, false).await?, Blocked::Not);
+        Ok(())
+    }
 }
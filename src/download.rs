@@ -6,6 +6,7 @@
 use std::collections::BTreeMap;
 
 use crate::config::Config;
+use crate::constants::DC_CHAT_ID_TRASH;
 use crate::context::Context;
 use crate::dc_tools::time;
 use crate::imap::{Imap, ImapActionResult};
@@ -50,6 +51,9 @@ pub enum DownloadState {
     Done = 0,
     Available = 10,
     Failure = 20,
+    /// The message was downloaded completely but cannot be decrypted,
+    /// downloading it again does not help.
+    Undecipherable = 30,
     InProgress = 1000,
 }
 
@@ -69,6 +73,48 @@ pub(crate) async fn download_limit(&self) -> Result<Option<u32>> {
             Ok(Some(max(MIN_DOWNLOAD_LIMIT, download_limit as u32)))
         }
     }
+
+    /// Returns the IDs of all partially downloaded messages,
+    /// that can be downloaded using [`MsgId::download_full`].
+    ///
+    /// This includes messages where a previous download failed.
+    pub async fn get_partially_downloaded_msgs(&self) -> Result<Vec<MsgId>> {
+        self.sql
+            .query_map(
+                "SELECT id FROM msgs
+                 WHERE download_state IN (?, ?) AND chat_id!=? AND hidden=0
+                 ORDER BY timestamp, id",
+                paramsv![
+                    DownloadState::Available,
+                    DownloadState::Failure,
+                    DC_CHAT_ID_TRASH
+                ],
+                |row| row.get::<_, MsgId>(0),
+                |ids| ids.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+            )
+            .await
+    }
+
+    /// Returns the number of bytes needed to fully download
+    /// all messages returned by [`Context::get_partially_downloaded_msgs`].
+    ///
+    /// UIs may use this to warn before downloading on metered networks.
+    pub async fn estimate_pending_download_bytes(&self) -> Result<u64> {
+        let bytes: i64 = self
+            .sql
+            .query_get_value(
+                "SELECT SUM(bytes) FROM msgs
+                 WHERE download_state IN (?, ?) AND chat_id!=? AND hidden=0",
+                paramsv![
+                    DownloadState::Available,
+                    DownloadState::Failure,
+                    DC_CHAT_ID_TRASH
+                ],
+            )
+            .await?
+            .unwrap_or_default();
+        Ok(u64::try_from(bytes)?)
+    }
 }
 
 impl MsgId {
@@ -77,6 +123,9 @@ pub async fn download_full(self, context: &Context) -> Result<()> {
         let msg = Message::load_from_db(context, self).await?;
         match msg.download_state() {
             DownloadState::Done => return Err(anyhow!("Nothing to download.")),
+            DownloadState::Undecipherable => {
+                return Err(anyhow!("Message is downloaded but cannot be decrypted."))
+            }
             DownloadState::InProgress => return Err(anyhow!("Download already in progress.")),
             DownloadState::Available | DownloadState::Failure => {
                 self.update_download_state(context, DownloadState::InProgress)
@@ -245,6 +294,8 @@ pub(crate) async fn create_stub_from_partial_download(
         self.parts.push(Part {
             typ: Viewtype::Text,
             msg: text,
+            // Remember the full size, see `Context::estimate_pending_download_bytes`.
+            bytes: org_bytes as usize,
             ..Default::default()
         });
 
@@ -274,6 +325,10 @@ fn test_downloadstate_values() {
             DownloadState::from_i32(10).unwrap()
         );
         assert_eq!(DownloadState::Failure, DownloadState::from_i32(20).unwrap());
+        assert_eq!(
+            DownloadState::Undecipherable,
+            DownloadState::from_i32(30).unwrap()
+        );
         assert_eq!(
             DownloadState::InProgress,
             DownloadState::from_i32(1000).unwrap()
@@ -376,6 +431,129 @@ async fn test_partial_receive_imf() -> Result<()> {
         Ok(())
     }
 
+    #[async_std::test]
+    async fn test_get_partially_downloaded_msgs() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        assert!(t.get_partially_downloaded_msgs().await?.is_empty());
+        assert_eq!(t.estimate_pending_download_bytes().await?, 0);
+
+        for rfc724_mid in &["first@example.org", "second@example.org"] {
+            dc_receive_imf_inner(
+                &t,
+                rfc724_mid,
+                format!(
+                    "From: Bob <bob@example.org>\n\
+                     To: Alice <alice@example.org>\n\
+                     Chat-Version: 1.0\n\
+                     Subject: subject\n\
+                     Message-ID: <{}>\n\
+                     Date: Sun, 14 Nov 2021 00:10:00 +0000\
+                     Content-Type: text/plain",
+                    rfc724_mid
+                )
+                .as_bytes(),
+                false,
+                Some(100000),
+                false,
+            )
+            .await?;
+        }
+        let partial_msgs = t.get_partially_downloaded_msgs().await?;
+        assert_eq!(partial_msgs.len(), 2);
+        assert_eq!(t.estimate_pending_download_bytes().await?, 200000);
+        for msg_id in &partial_msgs {
+            let msg = Message::load_from_db(&t, *msg_id).await?;
+            assert_eq!(msg.download_state(), DownloadState::Available);
+        }
+
+        // Messages being downloaded are not listed.
+        partial_msgs[0].download_full(&t).await?;
+        assert_eq!(
+            t.get_partially_downloaded_msgs().await?,
+            vec![partial_msgs[1]]
+        );
+        assert_eq!(t.estimate_pending_download_bytes().await?, 100000);
+
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn test_download_state_transitions() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        let header = "From: Bob <bob@example.org>\n\
+                      To: Alice <alice@example.org>\n\
+                      Chat-Version: 1.0\n\
+                      Subject: subject\n\
+                      Message-ID: <transitions@example.org>\n\
+                      Date: Sun, 14 Nov 2021 00:10:00 +0000\
+                      Content-Type: text/plain";
+
+        dc_receive_imf_inner(
+            &t,
+            "transitions@example.org",
+            header.as_bytes(),
+            false,
+            Some(100000),
+            false,
+        )
+        .await?;
+        let msg_id = t.get_last_msg().await.id;
+        let msg = Message::load_from_db(&t, msg_id).await?;
+        assert_eq!(msg.download_state(), DownloadState::Available);
+
+        msg_id.download_full(&t).await?;
+        let msg = Message::load_from_db(&t, msg_id).await?;
+        assert_eq!(msg.download_state(), DownloadState::InProgress);
+        assert!(msg_id.download_full(&t).await.is_err());
+
+        // The downloaded message replaces the partial one.
+        dc_receive_imf_inner(
+            &t,
+            "transitions@example.org",
+            format!("{}\n\n100k text...", header).as_bytes(),
+            false,
+            None,
+            false,
+        )
+        .await?;
+        let msg = t.get_last_msg().await;
+        assert_eq!(msg.download_state(), DownloadState::Done);
+        assert!(msg.id.download_full(&t).await.is_err());
+
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn test_download_state_undecipherable() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+        let bob = TestContext::new_bob().await;
+        let fiona = TestContext::new_fiona().await;
+
+        // Bob learns Alice's key, so his message to her is encrypted.
+        let alice_chat_id = alice.create_chat(&bob).await.id;
+        bob.recv_msg(&alice.send_text(alice_chat_id, "hi").await)
+            .await;
+        let bob_chat_id = bob.create_chat(&alice).await.id;
+        let sent = bob.send_text(bob_chat_id, "secret").await;
+
+        let msg = alice.recv_msg(&sent).await;
+        assert_eq!(msg.download_state(), DownloadState::Done);
+
+        // Fiona does not have Alice's key.
+        let msg = fiona.recv_msg(&sent).await;
+        assert_eq!(msg.download_state(), DownloadState::Undecipherable);
+        assert!(msg.id.download_full(&fiona).await.is_err());
+        assert!(fiona.get_partially_downloaded_msgs().await?.is_empty());
+
+        // Receiving the same mail again, e.g. from another folder, keeps the message.
+        assert!(fiona.recv_msg_opt(&sent).await.is_none());
+        let msg2 = fiona.get_last_msg().await;
+        assert_eq!(msg2.id, msg.id);
+        assert_eq!(msg2.download_state(), DownloadState::Undecipherable);
+
+        Ok(())
+    }
+
     #[async_std::test]
     async fn test_partial_download_and_ephemeral() -> Result<()> {
         let t = TestContext::new_alice().await;
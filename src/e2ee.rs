@@ -1,9 +1,9 @@
 //! End-to-end encryption support.
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
-use anyhow::{format_err, Context as _, Result};
-use mailparse::ParsedMail;
+use anyhow::{bail, ensure, format_err, Context as _, Result};
+use mailparse::{MailHeader, ParsedMail};
 use num_traits::FromPrimitive;
 
 use crate::aheader::{Aheader, EncryptPreference};
@@ -11,7 +11,10 @@
 use crate::context::Context;
 use crate::headerdef::HeaderDef;
 use crate::headerdef::HeaderDefMap;
-use crate::key::{DcKey, Fingerprint, SignedPublicKey, SignedSecretKey};
+use crate::key::{
+    load_self_public_keyring, load_self_secret_keyring, DcKey, Fingerprint, SignedPublicKey,
+    SignedSecretKey,
+};
 use crate::keyring::Keyring;
 use crate::peerstate::{Peerstate, PeerstateVerifiedStatus};
 use crate::pgp;
@@ -129,6 +132,154 @@ pub async fn encrypt(
 
         Ok(ctext)
     }
+
+    /// Encrypts the passed in `mail` separately for each recipient.
+    ///
+    /// Unlike [`EncryptHelper::encrypt`], each ciphertext is encrypted to a single recipient key,
+    /// so recipients, e.g. of a broadcast list, do not learn about each other's keys.
+    /// The own key is not added, the sender's copy has to be encrypted separately.
+    ///
+    /// Returns a map from recipient address to ciphertext.
+    pub async fn encrypt_per_recipient(
+        self,
+        context: &Context,
+        min_verified: PeerstateVerifiedStatus,
+        mail_to_encrypt: lettre_email::PartBuilder,
+        peerstates: Vec<(Option<Peerstate>, &str)>,
+    ) -> Result<HashMap<String, String>> {
+        let sign_key = SignedSecretKey::load_self(context).await?;
+        let raw_message = mail_to_encrypt.build().as_string().into_bytes();
+
+        let mut ctexts = HashMap::new();
+        for (peerstate, addr) in peerstates
+            .into_iter()
+            .filter_map(|(state, addr)| state.map(|s| (s, addr)))
+        {
+            let key = peerstate
+                .take_key(min_verified)
+                .with_context(|| format!("proper enc-key for {} missing, cannot encrypt", addr))?;
+            let mut keyring: Keyring<SignedPublicKey> = Keyring::new();
+            keyring.add(key);
+            let ctext = pgp::pk_encrypt(&raw_message, keyring, Some(sign_key.clone())).await?;
+            ctexts.insert(addr.to_string(), ctext);
+        }
+
+        Ok(ctexts)
+    }
+}
+
+impl Context {
+    /// Learns peerstates from the Autocrypt headers of messages already in the database.
+    ///
+    /// Messages are not downloaded again, only messages with stored MIME headers are scanned.
+    /// Therefore, an error is returned if [`Config::SaveMimeHeaders`] is not enabled;
+    /// messages received before enabling it are not scanned.
+    /// Messages downloaded due to [`Config::FetchExistingMsgs`] are processed on reception anyway.
+    /// Headers are applied in the order the messages were sent.
+    ///
+    /// Returns the number of peerstates that did not exist before.
+    pub async fn scan_existing_for_keys(&self) -> Result<usize> {
+        ensure!(
+            self.get_config_bool(Config::SaveMimeHeaders).await?,
+            "Cannot scan existing messages for keys, MIME headers are not saved"
+        );
+        // Only the Autocrypt headers are kept, the stored MIME messages may be large.
+        let aheaders = self
+            .sql
+            .query_map(
+                "SELECT CAST(mime_headers AS BLOB), timestamp_sent FROM msgs
+                 WHERE chat_id>9 AND from_id>9 AND LENGTH(mime_headers)>0
+                 ORDER BY timestamp_sent, id",
+                paramsv![],
+                |row| {
+                    let headers: Vec<u8> = row.get(0)?;
+                    let timestamp_sent: i64 = row.get(1)?;
+                    Ok((headers, timestamp_sent))
+                },
+                |rows| {
+                    let mut senders = HashSet::new();
+                    let mut aheaders = Vec::new();
+                    for row in rows {
+                        let (raw, timestamp_sent) = row?;
+                        let headers = match mailparse::parse_headers(&raw) {
+                            Ok((headers, _)) => headers,
+                            Err(err) => {
+                                warn!(self, "Cannot parse stored MIME headers: {}", err);
+                                continue;
+                            }
+                        };
+                        let from = get_from_addr(&headers);
+                        if from.is_empty() || senders.contains(&from) {
+                            continue;
+                        }
+                        match Aheader::from_headers(&from, &headers) {
+                            Ok(Some(header)) => {
+                                senders.insert(from.clone());
+                                aheaders.push((from, header, timestamp_sent));
+                            }
+                            Ok(None) => {}
+                            Err(err) => warn!(self, "Failed to parse Autocrypt header: {}", err),
+                        }
+                    }
+                    Ok(aheaders)
+                },
+            )
+            .await?;
+
+        let mut learned = 0;
+        for (from, header, timestamp_sent) in aheaders {
+            if Peerstate::from_addr(self, &from).await?.is_some() {
+                continue;
+            }
+            Peerstate::from_header(&header, timestamp_sent)
+                .save_to_db(&self.sql, true)
+                .await?;
+            learned += 1;
+        }
+        info!(
+            self,
+            "Learned {} peerstates from existing messages.", learned
+        );
+        Ok(learned)
+    }
+
+    /// Encrypts arbitrary bytes to the user's own key.
+    ///
+    /// The data is signed with the self secret key and returned as an armored
+    /// OpenPGP message, suitable for storing sensitive app data or backups.
+    /// Use [`Context::decrypt_blob_from_self`] to get the data back.
+    pub async fn encrypt_blob_to_self(&self, data: &[u8]) -> Result<String> {
+        let helper = EncryptHelper::new(self).await?;
+        let mut keyring: Keyring<SignedPublicKey> = Keyring::new();
+        keyring.add(helper.public_key);
+        let sign_key = SignedSecretKey::load_self(self).await?;
+        pgp::pk_encrypt(data, keyring, Some(sign_key)).await
+    }
+
+    /// Decrypts data previously encrypted with [`Context::encrypt_blob_to_self`].
+    ///
+    /// Fails if the data cannot be decrypted with the self secret keys
+    /// or if it is not signed by one of the self keys.
+    /// Keys replaced by key rotation are still accepted.
+    pub async fn decrypt_blob_from_self(&self, armored: &str) -> Result<Vec<u8>> {
+        let private_keyring = load_self_secret_keyring(self).await?;
+        let public_keyring = load_self_public_keyring(self).await?;
+
+        let (plain, signatures) = pgp::pk_decrypt(
+            armored.as_bytes().to_vec(),
+            private_keyring,
+            &public_keyring,
+        )
+        .await?;
+        if !public_keyring
+            .keys()
+            .iter()
+            .any(|key| signatures.contains(&DcKey::fingerprint(key)))
+        {
+            bail!("Blob is not signed by the self key");
+        }
+        Ok(plain)
+    }
 }
 
 /// Tries to decrypt a message, but only if it is structured as an
@@ -144,31 +295,8 @@ pub async fn try_decrypt(
     mail: &ParsedMail<'_>,
     message_time: i64,
 ) -> Result<(Option<Vec<u8>>, HashSet<Fingerprint>)> {
-    let from = mail
-        .headers
-        .get_header(HeaderDef::From_)
-        .and_then(|from_addr| mailparse::addrparse_header(from_addr).ok())
-        .and_then(|from| from.extract_single_info())
-        .map(|from| from.addr)
-        .unwrap_or_default();
-
-    let mut peerstate = Peerstate::from_addr(context, &from).await?;
-
-    // Apply Autocrypt header
-    match Aheader::from_headers(&from, &mail.headers) {
-        Ok(Some(ref header)) => {
-            if let Some(ref mut peerstate) = peerstate {
-                peerstate.apply_header(header, message_time);
-                peerstate.save_to_db(&context.sql, false).await?;
-            } else {
-                let p = Peerstate::from_header(header, message_time);
-                p.save_to_db(&context.sql, true).await?;
-                peerstate = Some(p);
-            }
-        }
-        Ok(None) => {}
-        Err(err) => warn!(context, "Failed to parse Autocrypt header: {}", err),
-    }
+    let from = get_from_addr(&mail.headers);
+    let mut peerstate = apply_autocrypt_header(context, &from, &mail.headers, message_time).await?;
 
     // Possibly perform decryption
     let mut public_keyring_for_validate: Keyring<SignedPublicKey> = Keyring::new();
@@ -204,6 +332,45 @@ pub async fn try_decrypt(
     Ok((out_mail, signatures))
 }
 
+/// Returns the address of the `From:` header, or an empty string.
+fn get_from_addr(headers: &[MailHeader]) -> String {
+    headers
+        .get_header(HeaderDef::From_)
+        .and_then(|from_addr| mailparse::addrparse_header(from_addr).ok())
+        .and_then(|from| from.extract_single_info())
+        .map(|from| from.addr)
+        .unwrap_or_default()
+}
+
+/// Applies the Autocrypt header of a message from `from` to the sender's peerstate,
+/// creating the peerstate if needed.
+///
+/// Returns the peerstate of `from`, if there is any.
+async fn apply_autocrypt_header(
+    context: &Context,
+    from: &str,
+    headers: &[MailHeader],
+    message_time: i64,
+) -> Result<Option<Peerstate>> {
+    let mut peerstate = Peerstate::from_addr(context, from).await?;
+
+    match Aheader::from_headers(from, headers) {
+        Ok(Some(ref header)) => {
+            if let Some(ref mut peerstate) = peerstate {
+                peerstate.apply_header(header, message_time);
+                peerstate.save_to_db(&context.sql, false).await?;
+            } else {
+                let p = Peerstate::from_header(header, message_time);
+                p.save_to_db(&context.sql, true).await?;
+                peerstate = Some(p);
+            }
+        }
+        Ok(None) => {}
+        Err(err) => warn!(context, "Failed to parse Autocrypt header: {}", err),
+    }
+    Ok(peerstate)
+}
+
 /// Returns a reference to the encrypted payload of a valid PGP/MIME message.
 ///
 /// Returns `None` if the message is not a valid PGP/MIME message.
@@ -299,7 +466,7 @@ async fn decrypt_if_autocrypt_message(
         Some(res) => res,
     };
     info!(context, "Detected Autocrypt-mime message");
-    let private_keyring: Keyring<SignedSecretKey> = Keyring::new_self(context)
+    let private_keyring = load_self_secret_keyring(context)
         .await
         .context("failed to get own keyring")?;
 
@@ -411,14 +578,91 @@ pub async fn ensure_secret_key_exists(context: &Context) -> Result<String> {
 #[cfg(test)]
 mod tests {
     use crate::chat;
+    use crate::constants::KeyGenType;
     use crate::dc_receive_imf::dc_receive_imf;
+    use crate::dc_tools::{time, EmailAddress};
+    use crate::key::KeyPair;
     use crate::message::{Message, Viewtype};
     use crate::param::Param;
     use crate::peerstate::ToSave;
-    use crate::test_utils::{bob_keypair, TestContext};
+    use crate::test_utils::{bob_keypair, fiona_keypair, TestContext};
 
     use super::*;
 
+    #[async_std::test]
+    async fn test_encrypt_blob_to_self() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        let payload = b"secret app data \x00\x01\x02";
+
+        let armored = t.encrypt_blob_to_self(payload).await?;
+        assert!(armored.starts_with("-----BEGIN PGP MESSAGE-----"));
+        assert_eq!(t.decrypt_blob_from_self(&armored).await?, payload.to_vec());
+
+        // Bob cannot decrypt Alice's blob.
+        let bob = TestContext::new_bob().await;
+        assert!(bob.decrypt_blob_from_self(&armored).await.is_err());
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn test_decrypt_blob_from_self_after_key_rotation() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        let payload = b"secret app data";
+        let armored = t.encrypt_blob_to_self(payload).await?;
+
+        t.set_config(Config::KeyRotationDays, Some("1")).await?;
+        t.sql
+            .execute(
+                "UPDATE keypairs SET created=? WHERE is_default=1",
+                paramsv![time() - 2 * 24 * 60 * 60],
+            )
+            .await?;
+        assert!(crate::key::rotate_keypair_if_needed(&t).await?);
+
+        assert_eq!(t.decrypt_blob_from_self(&armored).await?, payload.to_vec());
+        let armored = t.encrypt_blob_to_self(payload).await?;
+        assert_eq!(t.decrypt_blob_from_self(&armored).await?, payload.to_vec());
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn test_scan_existing_for_keys() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+        let bob = TestContext::new_bob().await;
+        alice.set_config_bool(Config::SaveMimeHeaders, true).await?;
+        let bob_chat_id = bob.create_chat(&alice).await.id;
+        alice
+            .recv_msg(&bob.send_text(bob_chat_id, "hi").await)
+            .await;
+        assert!(Peerstate::from_addr(&alice, "bob@example.net")
+            .await?
+            .is_some());
+
+        alice
+            .sql
+            .execute("DELETE FROM acpeerstates", paramsv![])
+            .await?;
+        assert!(Peerstate::from_addr(&alice, "bob@example.net")
+            .await?
+            .is_none());
+
+        assert_eq!(alice.scan_existing_for_keys().await?, 1);
+        let peerstate = Peerstate::from_addr(&alice, "bob@example.net")
+            .await?
+            .unwrap();
+        assert_eq!(peerstate.public_key, Some(bob_keypair().public));
+
+        // Nothing new is learned on the second scan.
+        assert_eq!(alice.scan_existing_for_keys().await?, 0);
+
+        // Without saved MIME headers, there is nothing to scan.
+        alice
+            .set_config_bool(Config::SaveMimeHeaders, false)
+            .await?;
+        assert!(alice.scan_existing_for_keys().await.is_err());
+        Ok(())
+    }
+
     mod ensure_secret_key_exists {
         use super::*;
 
@@ -569,8 +813,16 @@ async fn test_encrypted_no_autocrypt() -> anyhow::Result<()> {
 
     fn new_peerstates(prefer_encrypt: EncryptPreference) -> Vec<(Option<Peerstate>, &'static str)> {
         let addr = "bob@foo.bar";
-        let pub_key = bob_keypair().public;
-        let peerstate = Peerstate {
+        let peerstate = new_peerstate(addr, bob_keypair().public, prefer_encrypt);
+        vec![(Some(peerstate), addr)]
+    }
+
+    fn new_peerstate(
+        addr: &str,
+        pub_key: SignedPublicKey,
+        prefer_encrypt: EncryptPreference,
+    ) -> Peerstate {
+        Peerstate {
             addr: addr.into(),
             last_seen: 13,
             last_seen_autocrypt: 14,
@@ -584,8 +836,54 @@ fn new_peerstates(prefer_encrypt: EncryptPreference) -> Vec<(Option<Peerstate>,
             verified_key_fingerprint: Some(pub_key.fingerprint()),
             to_save: Some(ToSave::All),
             fingerprint_changed: false,
-        };
-        vec![(Some(peerstate), addr)]
+        }
+    }
+
+    #[async_std::test]
+    async fn test_encrypt_per_recipient() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        let claire = pgp::create_keypair(
+            EmailAddress::new("claire@example.com")?,
+            KeyGenType::Default,
+        )?;
+        let recipients: Vec<KeyPair> = vec![bob_keypair(), fiona_keypair(), claire];
+        let addrs: Vec<String> = recipients.iter().map(|k| k.addr.to_string()).collect();
+        let peerstates = recipients
+            .iter()
+            .zip(addrs.iter())
+            .map(|(keypair, addr)| {
+                let peerstate =
+                    new_peerstate(addr, keypair.public.clone(), EncryptPreference::Mutual);
+                (Some(peerstate), addr.as_str())
+            })
+            .collect();
+
+        let mail = lettre_email::PartBuilder::new().body("hidden recipients");
+        let ctexts = EncryptHelper::new(&t)
+            .await?
+            .encrypt_per_recipient(&t, PeerstateVerifiedStatus::Unverified, mail, peerstates)
+            .await?;
+        assert_eq!(ctexts.len(), 3);
+        let distinct: HashSet<&String> = ctexts.values().collect();
+        assert_eq!(distinct.len(), 3);
+
+        for recipient in &recipients {
+            let ctext = ctexts.get(&recipient.addr.to_string()).unwrap();
+            for keypair in &recipients {
+                let mut secret_keyring = Keyring::new();
+                secret_keyring.add(keypair.secret.clone());
+                let res =
+                    pgp::pk_decrypt(ctext.as_bytes().to_vec(), secret_keyring, &Keyring::new())
+                        .await;
+                if keypair.addr == recipient.addr {
+                    let (plain, _) = res?;
+                    assert!(String::from_utf8_lossy(&plain).contains("hidden recipients"));
+                } else {
+                    assert!(res.is_err());
+                }
+            }
+        }
+        Ok(())
     }
 
     #[async_std::test]
@@ -395,6 +395,13 @@ pub(crate) async fn delete_expired_messages(context: &Context, now: i64) -> Resu
     }
 
     if updated {
+        context
+            .sql
+            .execute(
+                "DELETE FROM mentions WHERE msg_id IN (SELECT id FROM msgs WHERE chat_id=?)",
+                paramsv![DC_CHAT_ID_TRASH],
+            )
+            .await?;
         context.emit_msgs_changed_without_ids();
     }
 
@@ -575,6 +582,7 @@ mod tests {
     use crate::dc_receive_imf::dc_receive_imf;
     use crate::dc_tools::MAX_SECONDS_TO_LEND_FROM_FUTURE;
     use crate::download::DownloadState;
+    use crate::message::markseen_msgs;
     use crate::test_utils::TestContext;
     use crate::{
         chat::{self, Chat, ChatItem},
@@ -742,6 +750,48 @@ async fn test_ephemeral_enable_disable() -> Result<()> {
         Ok(())
     }
 
+    #[async_std::test]
+    async fn test_ephemeral_timer_per_message() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+        let bob = TestContext::new_bob().await;
+
+        let chat_alice = alice.create_chat(&bob).await.id;
+        let chat_bob = bob.create_chat(&alice).await.id;
+        chat_alice
+            .set_ephemeral_timer(&alice, Timer::Enabled { duration: 3600 })
+            .await?;
+        bob.recv_msg(&alice.pop_sent_msg().await).await;
+
+        let mut msg = Message::new(Viewtype::Text);
+        msg.set_text(Some("self-destructing".to_string()));
+        msg.set_ephemeral_timer(60);
+        let sent = alice.send_msg(chat_alice, &mut msg).await;
+        let msg = Message::load_from_db(&alice, sent.sender_msg_id).await?;
+        assert_eq!(msg.get_ephemeral_timer(), Timer::Enabled { duration: 60 });
+        assert!(msg.get_ephemeral_timestamp() <= time() + 60);
+
+        let msg = bob.recv_msg(&sent).await;
+        assert_eq!(msg.get_ephemeral_timer(), Timer::Enabled { duration: 60 });
+        assert_eq!(msg.get_ephemeral_timestamp(), 0);
+        // The chat's timer is not changed.
+        assert_eq!(
+            chat_bob.get_ephemeral_timer(&bob).await?,
+            Timer::Enabled { duration: 3600 }
+        );
+
+        // The timer starts when the message is read.
+        markseen_msgs(&bob, vec![msg.id]).await?;
+        let msg = Message::load_from_db(&bob, msg.id).await?;
+        assert!(msg.get_ephemeral_timestamp() > 0);
+        assert!(msg.get_ephemeral_timestamp() <= time() + 60);
+
+        // Other messages still use the chat's timer.
+        let sent = alice.send_text(chat_alice, "normal").await;
+        let msg = bob.recv_msg(&sent).await;
+        assert_eq!(msg.get_ephemeral_timer(), Timer::Enabled { duration: 3600 });
+        Ok(())
+    }
+
     /// Test that timer is enabled even if the message explicitly enabling the timer is lost.
     #[async_std::test]
     async fn test_ephemeral_enable_lost() -> Result<()> {
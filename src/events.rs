@@ -4,9 +4,11 @@
 use async_std::path::PathBuf;
 
 use crate::chat::ChatId;
+use crate::constants::Chattype;
 use crate::contact::ContactId;
 use crate::ephemeral::Timer as EphemeralTimer;
 use crate::message::MsgId;
+use crate::securejoin::SecurejoinFailure;
 use crate::webxdc::StatusUpdateSerial;
 
 #[derive(Debug)]
@@ -179,6 +181,13 @@ pub enum EventType {
     IncomingMsg {
         chat_id: ChatId,
         msg_id: MsgId,
+
+        /// Type of the chat the message was received in,
+        /// so that UIs can e.g. pick a different notification sound for groups.
+        chat_type: Chattype,
+
+        /// Whether the message text mentions the user by name or address.
+        is_mention: bool,
     },
 
     /// Messages were seen or noticed.
@@ -244,12 +253,25 @@ pub enum EventType {
         comment: Option<String>,
     },
 
-    /// Inform about the import/export progress started by imex().
+    /// Inform about the import/export progress started by imex()
+    /// or about the progress of continue_key_transfer().
     ///
     /// @param data1 (usize) 0=error, 1-999=progress in permille, 1000=success and done
     /// @param data2 0
     ImexProgress(usize),
 
+    /// Inform about the number of bytes written to a backup exported by imex().
+    ///
+    /// Emitted alongside [`EventType::ImexProgress`] to show the progress of large backups
+    /// in bytes rather than in permille. `done` never exceeds `total`.
+    ImexBytesProgress {
+        /// Number of bytes written so far.
+        done: u64,
+
+        /// Total number of bytes to write.
+        total: u64,
+    },
+
     /// A file has been exported. A file has been written by imex().
     /// This event may be sent multiple times by a single call to imex().
     ///
@@ -259,6 +281,12 @@ pub enum EventType {
     /// @param data2 0
     ImexFileWritten(PathBuf),
 
+    /// Inform about the progress of rebuilding the search index started by reindex_search().
+    ///
+    /// @param data1 (usize) 1-999=progress in permille, 1000=success and done
+    /// @param data2 0
+    ReindexProgress(usize),
+
     /// Progress information of a secure-join handshake from the view of the inviter
     /// (Alice, the person who shows the QR code).
     ///
@@ -282,13 +310,25 @@ pub enum EventType {
     /// may take some time, is executed.
     /// @param data1 (int) ID of the inviting contact.
     /// @param data2 (int) Progress as:
+    ///     0=error, the handshake was aborted.
     ///     400=vg-/vc-request-with-auth sent, typically shown as "alice@addr verified, introducing myself."
     ///     (Bob has verified alice and waits until Alice does the same for him)
+    ///     1000=vc-contact-confirm or vg-member-added received, the protocol is finished.
     SecurejoinJoinerProgress {
         contact_id: ContactId,
         progress: usize,
     },
 
+    /// A Secure-Join handshake message failed verification
+    /// and an info message was added to the chat with the contact.
+    ///
+    /// @param data1 (int) ID of the contact the handshake is done with.
+    /// @param data2 (int) The reason as one of the DC_SECUREJOIN_FAILURE_* constants.
+    SecurejoinFailed {
+        contact_id: ContactId,
+        reason: SecurejoinFailure,
+    },
+
     /// The connectivity to the server changed.
     /// This means that you should refresh the connectivity view
     /// and possibly the connectivtiy HTML; see dc_get_connectivity() and
@@ -297,6 +337,13 @@ pub enum EventType {
 
     SelfavatarChanged,
 
+    /// A config value was changed,
+    /// either by `set_config()` or by a synchronization message from another device.
+    /// Only the name of the changed key is passed, never the value.
+    ConfigChanged {
+        key: String,
+    },
+
     WebxdcStatusUpdate {
         msg_id: MsgId,
         status_update_serial: StatusUpdateSerial,
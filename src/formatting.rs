@@ -0,0 +1,167 @@
+//! # Inline formatting hints.
+//!
+//! Messages are sent as plain text, however, some simple inline markers
+//! are commonly used for emphasis: `*bold*`, `_italic_` and `` `code` ``.
+//! This module finds these markers so that UIs can render them;
+//! the message text itself is never changed.
+
+use std::ops::Range;
+
+use crate::plaintext::LINKIFY_URL_RE;
+
+/// Style of a formatted range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    /// Text enclosed in `*`.
+    Bold,
+    /// Text enclosed in `_`.
+    Italic,
+    /// Text enclosed in `` ` ``, no other markers are recognized inside.
+    Code,
+}
+
+impl Style {
+    fn from_marker(c: char) -> Option<Style> {
+        match c {
+            '*' => Some(Style::Bold),
+            '_' => Some(Style::Italic),
+            '`' => Some(Style::Code),
+            _ => None,
+        }
+    }
+}
+
+/// Finds the formatted ranges in `text`.
+///
+/// The returned ranges are byte offsets into `text` and do not include the markers.
+/// Ranges may be nested, they are sorted by their start.
+///
+/// Markers do not span lines, are only recognized at word boundaries
+/// and are ignored inside links and if preceded by a backslash.
+pub fn parse_formatting(text: &str) -> Vec<(Range<usize>, Style)> {
+    let urls: Vec<Range<usize>> = LINKIFY_URL_RE.find_iter(text).map(|m| m.range()).collect();
+
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let mut ret = Vec::new();
+    // Open markers with the start of the enclosed text.
+    let mut open: Vec<(char, usize)> = Vec::new();
+    let mut i = 0;
+    while let Some(&(pos, c)) = chars.get(i) {
+        i += 1;
+        if c == '\n' {
+            open.clear();
+            continue;
+        }
+        if c == '\\' {
+            // Skip the escaped character.
+            i += 1;
+            continue;
+        }
+        let style = match Style::from_marker(c) {
+            Some(style) => style,
+            None => continue,
+        };
+        if urls.iter().any(|url| url.contains(&pos)) {
+            continue;
+        }
+        let prev = i.checked_sub(2).and_then(|j| chars.get(j)).map(|&(_, c)| c);
+        let next = chars.get(i).map(|&(_, c)| c);
+
+        if let Some(k) = open.iter().rposition(|&(marker, _)| marker == c) {
+            if prev.map_or(false, |p| !p.is_whitespace())
+                && next.map_or(true, |n| !n.is_alphanumeric())
+            {
+                // Markers opened after this one are left unclosed.
+                if let Some(&(_, start)) = open.split_off(k).first() {
+                    if start < pos {
+                        ret.push((start..pos, style));
+                    }
+                }
+                continue;
+            }
+        }
+
+        if next.map_or(false, |n| !n.is_whitespace()) && prev.map_or(true, |p| !p.is_alphanumeric())
+        {
+            if style == Style::Code {
+                let start = pos + c.len_utf8();
+                let mut j = i;
+                while let Some(&(end, c)) = chars.get(j) {
+                    j += 1;
+                    if c == '\n' {
+                        break;
+                    }
+                    if c == '`' {
+                        if end > start {
+                            ret.push((start..end, Style::Code));
+                        }
+                        i = j;
+                        break;
+                    }
+                }
+            } else {
+                open.push((c, pos + c.len_utf8()));
+            }
+        }
+    }
+
+    ret.sort_by_key(|(range, _)| range.start);
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_formatting() {
+        assert_eq!(parse_formatting(""), vec![]);
+        assert_eq!(parse_formatting("no formatting"), vec![]);
+        assert_eq!(parse_formatting("*bold*"), vec![(1..5, Style::Bold)]);
+        assert_eq!(
+            parse_formatting("some _italic_ text"),
+            vec![(6..12, Style::Italic)]
+        );
+        assert_eq!(parse_formatting("`a *b*`"), vec![(1..6, Style::Code)]);
+
+        // Markers inside words, surrounded by spaces or spanning lines are no markers.
+        assert_eq!(parse_formatting("snake_case_word"), vec![]);
+        assert_eq!(parse_formatting("2 * 3 * 4"), vec![]);
+        assert_eq!(parse_formatting("*not\nbold*"), vec![]);
+        assert_eq!(parse_formatting("`unclosed"), vec![]);
+        assert_eq!(parse_formatting("**"), vec![]);
+    }
+
+    #[test]
+    fn test_parse_formatting_nested() {
+        assert_eq!(
+            parse_formatting("*bold _both_*"),
+            vec![(1..12, Style::Bold), (7..11, Style::Italic)]
+        );
+        assert_eq!(
+            parse_formatting("_*both*_"),
+            vec![(1..7, Style::Italic), (2..6, Style::Bold)]
+        );
+    }
+
+    #[test]
+    fn test_parse_formatting_escaped() {
+        assert_eq!(parse_formatting(r"\*not bold\*"), vec![]);
+        assert_eq!(
+            parse_formatting(r"\*not bold\* *bold*"),
+            vec![(14..18, Style::Bold)]
+        );
+    }
+
+    #[test]
+    fn test_parse_formatting_links() {
+        assert_eq!(
+            parse_formatting("see https://example.org/some_path_here and _this_"),
+            vec![(44..48, Style::Italic)]
+        );
+        assert_eq!(
+            parse_formatting("https://example.org/_a_ *b*"),
+            vec![(25..26, Style::Bold)]
+        );
+    }
+}
@@ -49,10 +49,16 @@ pub enum HeaderDef {
     ChatVoiceMessage,
     ChatGroupMemberRemoved,
     ChatGroupMemberAdded,
+
+    /// Comma-separated list of all members added by a message,
+    /// `Chat-Group-Member-Added` only contains the first of them.
+    ChatGroupMembersAdded,
     ChatContent,
     ChatDuration,
     ChatDispositionNotificationTo,
     ChatWebrtcRoom,
+    ChatMarkdown,
+    ChatMessageEphemeralTimer,
     Autocrypt,
     AutocryptSetupMessage,
     SecureJoin,
@@ -794,13 +794,17 @@ pub(crate) async fn fetch_new_messages(
             // Get the Message-ID or generate a fake one to identify the message in the database.
             let message_id = prefetch_get_message_id(&headers).unwrap_or_else(dc_create_id);
 
-            let target = match target_folder(context, folder, is_spam_folder, &headers).await? {
+            let target_config = target_folder(context, folder, is_spam_folder, &headers).await?;
+            let target = match target_config {
                 Some(config) => match context.get_config(config).await? {
                     Some(target) => target,
                     None => folder.to_string(),
                 },
                 None => folder.to_string(),
             };
+            // The folder for copies of sent messages is not watched,
+            // so the copies are downloaded before they are moved.
+            let download_before_move = target_config == Some(Config::BccSelfFolder);
 
             context
                 .sql
@@ -820,7 +824,7 @@ pub(crate) async fn fetch_new_messages(
             // same time. Even in single device case it is possible to fail downloading the first
             // message, move it to the movebox and then download the second message before
             // downloading the first one, if downloading from inbox before moving is allowed.
-            if folder == target
+            if (folder == target || download_before_move)
                 // Never download messages directly from the spam folder.
                 // If the sender is known, the message will be moved to the Inbox or Mvbox
                 // and then we download the message from there.
@@ -1772,7 +1776,8 @@ async fn spam_target_folder(
     if needs_move_to_mvbox(context, headers).await?
         // If OnlyFetchMvbox is set, we don't want to move the message to
         // the inbox or sentbox where we wouldn't fetch it again:
-        || context.get_config_bool(Config::OnlyFetchMvbox).await?
+        || (context.get_config_bool(Config::OnlyFetchMvbox).await?
+            && !context.get_config_bool(Config::DisableMvbox).await?)
     {
         Ok(Some(Config::ConfiguredMvboxFolder))
     } else {
@@ -1788,6 +1793,10 @@ pub async fn target_folder(
     is_spam_folder: bool,
     headers: &[mailparse::MailHeader<'_>],
 ) -> Result<Option<Config>> {
+    if !is_spam_folder && needs_move_to_bcc_self_folder(context, folder, headers).await? {
+        return Ok(Some(Config::BccSelfFolder));
+    }
+
     if context.is_mvbox(folder).await? {
         return Ok(None);
     }
@@ -1801,11 +1810,40 @@ pub async fn target_folder(
     }
 }
 
+/// Returns true if the message is a copy of a chat message sent by ourself
+/// that should be moved to [`Config::BccSelfFolder`].
+async fn needs_move_to_bcc_self_folder(
+    context: &Context,
+    folder: &str,
+    headers: &[mailparse::MailHeader<'_>],
+) -> Result<bool> {
+    match context.get_config(Config::BccSelfFolder).await? {
+        Some(bcc_self_folder) if !bcc_self_folder.is_empty() && bcc_self_folder != folder => {}
+        _ => return Ok(false),
+    }
+
+    if headers.get_header_value(HeaderDef::ChatVersion).is_none()
+        || headers
+            .get_header_value(HeaderDef::AutocryptSetupMessage)
+            .is_some()
+    {
+        // non-chat messages are not copies made by bcc_self;
+        // setup messages are never moved, see needs_move_to_mvbox()
+        return Ok(false);
+    }
+
+    let (from_id, _, _) =
+        from_field_to_contact_id(context, &mimeparser::get_from(headers), true).await?;
+    Ok(from_id == ContactId::SELF)
+}
+
 async fn needs_move_to_mvbox(
     context: &Context,
     headers: &[mailparse::MailHeader<'_>],
 ) -> Result<bool> {
-    if !context.get_config_bool(Config::MvboxMove).await? {
+    if !context.get_config_bool(Config::MvboxMove).await?
+        || context.get_config_bool(Config::DisableMvbox).await?
+    {
         return Ok(false);
     }
 
@@ -2250,12 +2288,16 @@ pub async fn get_config_last_seen_uid(context: &Context, folder: &str) -> Result
 /// Whether to ignore fetching messages from a folder.
 ///
 /// This caters for the [`Config::OnlyFetchMvbox`] setting which means mails from folders
-/// not explicitly watched should not be fetched.
+/// not explicitly watched should not be fetched,
+/// and for the [`Config::DisableMvbox`] setting which means the mvbox is never fetched.
 async fn should_ignore_folder(
     context: &Context,
     folder: &str,
     is_spam_folder: bool,
 ) -> Result<bool> {
+    if context.get_config_bool(Config::DisableMvbox).await? {
+        return context.is_mvbox(folder).await;
+    }
     if !context.get_config_bool(Config::OnlyFetchMvbox).await? {
         return Ok(false);
     }
@@ -2627,6 +2669,79 @@ async fn test_target_folder_outgoing() -> Result<()> {
         Ok(())
     }
 
+    async fn bcc_self_target(
+        t: &TestContext,
+        folder: &str,
+        from: &str,
+        chat_msg: bool,
+    ) -> Result<Option<String>> {
+        let raw = format!(
+            "From: {}\n\
+             To: bob@example.net\n\
+             Subject: foo\n\
+             Message-ID: <abc@example.com>\n\
+             {}\
+             Date: Sun, 22 Mar 2020 22:37:57 +0000\n\
+             \n\
+             hello\n",
+            from,
+            if chat_msg { "Chat-Version: 1.0\n" } else { "" },
+        );
+        let (headers, _) = mailparse::parse_headers(raw.as_bytes())?;
+        Ok(
+            match target_folder(t, folder, folder == "Spam", &headers).await? {
+                Some(config) => t.get_config(config).await?,
+                None => None,
+            },
+        )
+    }
+
+    #[async_std::test]
+    async fn test_target_folder_bcc_self_folder() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        t.set_config(Config::ConfiguredMvboxFolder, Some("DeltaChat"))
+            .await?;
+        t.set_config_bool(Config::MvboxMove, true).await?;
+        t.set_config(Config::BccSelfFolder, Some("Delta Chat Sent"))
+            .await?;
+
+        // Copies of sent chat messages are moved to the configured folder,
+        // also from the mvbox.
+        for folder in ["INBOX", "Sent", "DeltaChat"] {
+            assert_eq!(
+                bcc_self_target(&t, folder, "alice@example.org", true)
+                    .await?
+                    .as_deref(),
+                Some("Delta Chat Sent")
+            );
+        }
+        assert_eq!(
+            bcc_self_target(&t, "Delta Chat Sent", "alice@example.org", true).await?,
+            None
+        );
+
+        // Other messages are not affected.
+        assert_eq!(
+            bcc_self_target(&t, "INBOX", "bob@example.net", true)
+                .await?
+                .as_deref(),
+            Some("DeltaChat")
+        );
+        assert_eq!(
+            bcc_self_target(&t, "INBOX", "alice@example.org", false).await?,
+            None
+        );
+
+        t.set_config(Config::BccSelfFolder, None).await?;
+        assert_eq!(
+            bcc_self_target(&t, "INBOX", "alice@example.org", true)
+                .await?
+                .as_deref(),
+            Some("DeltaChat")
+        );
+        Ok(())
+    }
+
     #[async_std::test]
     async fn test_target_folder_setupmsg() -> Result<()> {
         // Test setupmessages
@@ -2645,6 +2760,35 @@ async fn test_target_folder_setupmsg() -> Result<()> {
         Ok(())
     }
 
+    #[async_std::test]
+    async fn test_disable_mvbox() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        t.set_config(Config::ConfiguredMvboxFolder, Some("DeltaChat"))
+            .await?;
+        t.set_config_bool(Config::MvboxMove, true).await?;
+        t.set_config_bool(Config::OnlyFetchMvbox, true).await?;
+        assert!(t.should_watch_mvbox().await?);
+
+        t.set_config_bool(Config::DisableMvbox, true).await?;
+        assert!(!t.should_watch_mvbox().await?);
+        assert!(!scan_folders::get_watched_folder_configs(&t)
+            .await?
+            .contains(&Config::ConfiguredMvboxFolder));
+        assert!(should_ignore_folder(&t, "DeltaChat", false).await?);
+        assert!(!should_ignore_folder(&t, "INBOX", false).await?);
+
+        let (headers, _) = mailparse::parse_headers(
+            b"From: bob@example.net\n\
+              To: alice@example.org\n\
+              Chat-Version: 1.0\n\
+              Message-ID: <abc@example.com>\n\
+              \n\
+              hello\n",
+        )?;
+        assert_eq!(target_folder(&t, "INBOX", false, &headers).await?, None);
+        Ok(())
+    }
+
     #[async_std::test]
     async fn test_get_imap_search_command() -> Result<()> {
         let t = TestContext::new_alice().await;
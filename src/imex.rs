@@ -16,6 +16,7 @@
 use crate::blob::BlobObject;
 use crate::chat::{self, delete_and_reset_all_device_msgs, ChatId};
 use crate::config::Config;
+use crate::constants::DC_CHAT_ID_TRASH;
 use crate::contact::ContactId;
 use crate::context::Context;
 use crate::dc_tools::{
@@ -28,7 +29,7 @@
 use crate::log::LogExt;
 use crate::message::{Message, MsgId, Viewtype};
 use crate::mimeparser::SystemMessage;
-use crate::param::Param;
+use crate::param::{Param, Params};
 use crate::pgp;
 use crate::sql;
 use crate::stock_str;
@@ -285,10 +286,25 @@ async fn maybe_add_bcc_self_device_msg(context: &Context) -> Result<()> {
     Ok(())
 }
 
+/// Imports the key from the Autocrypt Setup Message `msg_id`
+/// created by [`initiate_key_transfer`] on another device.
+///
+/// The progress is reported by [`EventType::ImexProgress`] events,
+/// ending with 1000 on success and 0 on errors, eg. if the setup code is wrong.
 pub async fn continue_key_transfer(
     context: &Context,
     msg_id: MsgId,
     setup_code: &str,
+) -> Result<()> {
+    let res = do_continue_key_transfer(context, msg_id, setup_code).await;
+    context.emit_event(EventType::ImexProgress(if res.is_ok() { 1000 } else { 0 }));
+    res
+}
+
+async fn do_continue_key_transfer(
+    context: &Context,
+    msg_id: MsgId,
+    setup_code: &str,
 ) -> Result<()> {
     ensure!(!msg_id.is_special(), "wrong id");
 
@@ -299,9 +315,11 @@ pub async fn continue_key_transfer(
     );
 
     if let Some(filename) = msg.get_file(context) {
+        context.emit_event(EventType::ImexProgress(10));
         let file = dc_open_file_std(context, filename)?;
         let sc = normalize_setup_code(setup_code);
         let armored_key = decrypt_setup_file(&sc, file).await?;
+        context.emit_event(EventType::ImexProgress(500));
         set_self_key(context, &armored_key, true, true).await?;
         maybe_add_bcc_self_device_msg(context).await?;
 
@@ -498,6 +516,169 @@ async fn import_backup(
     Ok(())
 }
 
+/// Problems found by [`verify_backup`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct BackupReport {
+    /// Database version of the backup, `None` if the database could not be read.
+    pub db_version: Option<i32>,
+
+    /// Number of blob files contained in the backup.
+    pub blob_count: usize,
+
+    /// Human-readable descriptions of the problems found.
+    /// If this is empty, the backup can be imported.
+    pub problems: Vec<String>,
+}
+
+impl BackupReport {
+    /// Returns true if no problems were found.
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// Checks a backup file without importing it.
+///
+/// The archive structure is read completely, the contained database is opened using
+/// `passphrase` and its version is compared against the version supported by this core.
+/// Finally, all files referenced by messages and by the avatar setting
+/// are checked to be contained in the backup.
+///
+/// Structural problems are returned in the [`BackupReport`],
+/// an error is only returned if the backup cannot be read at all.
+pub async fn verify_backup(
+    context: &Context,
+    backup: &Path,
+    passphrase: Option<String>,
+) -> Result<BackupReport> {
+    let mut report = BackupReport::default();
+    let backup_file = File::open(backup).await?;
+    let archive = Archive::new(backup_file);
+
+    let unpacked_database = context.get_blobdir().join(format!(
+        "verify-{}-{}",
+        thread_rng().gen::<u32>(),
+        DBFILE_BACKUP_NAME
+    ));
+    let _d = DeleteOnDrop(unpacked_database.clone());
+    let mut has_database = false;
+    let mut blobs = Vec::new();
+
+    let mut entries = archive.entries()?;
+    while let Some(file) = entries.next().await {
+        let mut f = match file {
+            Ok(f) => f,
+            Err(err) => {
+                report
+                    .problems
+                    .push(format!("Archive is corrupted: {}", err));
+                break;
+            }
+        };
+        let path = f.path()?.into_owned();
+        let res = if path.file_name() == Some(OsStr::new(DBFILE_BACKUP_NAME)) {
+            has_database = true;
+            f.unpack(&unpacked_database).await.map(|_| ())
+        } else {
+            if let Some(name) = path.file_name() {
+                blobs.push(name.to_string_lossy().into_owned());
+            }
+            async_std::io::copy(&mut f, &mut async_std::io::sink())
+                .await
+                .map(|_| ())
+        };
+        if let Err(err) = res {
+            report
+                .problems
+                .push(format!("Cannot read {}: {}", path.display(), err));
+            break;
+        }
+    }
+    report.blob_count = blobs.len();
+
+    if !has_database {
+        report
+            .problems
+            .push("Backup does not contain a database.".to_string());
+        return Ok(report);
+    }
+    if !report.problems.is_empty() {
+        return Ok(report);
+    }
+
+    let referenced_files = match read_backup_database(&unpacked_database, passphrase) {
+        Ok((db_version, referenced_files)) => {
+            report.db_version = Some(db_version);
+            referenced_files
+        }
+        Err(err) => {
+            report
+                .problems
+                .push(format!("Cannot read backup database: {:#}", err));
+            return Ok(report);
+        }
+    };
+
+    if let Some(db_version) = report.db_version {
+        let supported_version = context
+            .sql
+            .get_raw_config_int("dbversion")
+            .await?
+            .unwrap_or_default();
+        if db_version > supported_version {
+            report.problems.push(format!(
+                "Backup database version {} is newer than the supported version {}.",
+                db_version, supported_version
+            ));
+        }
+    }
+    for file in referenced_files {
+        if let Some(name) = file.strip_prefix("$BLOBDIR/") {
+            if !blobs.iter().any(|blob| blob == name) {
+                report
+                    .problems
+                    .push(format!("File {} is missing in the backup.", name));
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+/// Returns the database version and all blob files referenced by the backup database.
+fn read_backup_database(path: &Path, passphrase: Option<String>) -> Result<(i32, Vec<String>)> {
+    let conn = rusqlite::Connection::open(path)?;
+    conn.pragma_update(None, "key", &passphrase.unwrap_or_default())
+        .context("failed to set PRAGMA key")?;
+    let db_version: String = conn
+        .query_row(
+            "SELECT value FROM config WHERE keyname='dbversion'",
+            [],
+            |row| row.get(0),
+        )
+        .context("backup passphrase is not correct or database is corrupted")?;
+    let db_version = db_version.parse().context("invalid database version")?;
+
+    let mut files = Vec::new();
+    let mut stmt = conn.prepare("SELECT param FROM msgs WHERE chat_id!=?")?;
+    let rows = stmt.query_map(paramsv![DC_CHAT_ID_TRASH], |row| row.get::<_, String>(0))?;
+    for param in rows {
+        let param: Params = param?.parse().unwrap_or_default();
+        if let Some(file) = param.get(Param::File) {
+            files.push(file.to_string());
+        }
+    }
+    let selfavatar: Option<String> = conn
+        .query_row(
+            "SELECT value FROM config WHERE keyname='selfavatar'",
+            [],
+            |row| row.get(0),
+        )
+        .ok();
+    files.extend(selfavatar);
+    Ok((db_version, files))
+}
+
 /*******************************************************************************
  * Export backup
  ******************************************************************************/
@@ -603,14 +784,30 @@ async fn export_backup_inner(
 
     let mut builder = async_tar::Builder::new(file);
 
-    builder
-        .append_path_with_name(temp_db_path, DBFILE_BACKUP_NAME)
-        .await?;
-
     let read_dir: Vec<_> = fs::read_dir(context.get_blobdir()).await?.collect().await;
     let count = read_dir.len();
     let mut written_files = 0;
 
+    // Sizes of the files to export, used to report progress in bytes.
+    let db_bytes = fs::metadata(temp_db_path).await?.len();
+    let mut total_bytes = db_bytes;
+    for entry in read_dir.iter().flatten() {
+        if let Ok(metadata) = entry.metadata().await {
+            if metadata.is_file() {
+                total_bytes += metadata.len();
+            }
+        }
+    }
+
+    builder
+        .append_path_with_name(temp_db_path, DBFILE_BACKUP_NAME)
+        .await?;
+    let mut done_bytes = db_bytes;
+    context.emit_event(EventType::ImexBytesProgress {
+        done: done_bytes,
+        total: total_bytes,
+    });
+
     let mut last_progress = 0;
     for entry in read_dir.into_iter() {
         let entry = entry?;
@@ -624,9 +821,18 @@ async fn export_backup_inner(
             continue;
         }
         let mut file = File::open(entry.path()).await?;
+        let file_bytes = file.metadata().await?.len();
         let path_in_archive = PathBuf::from(BLOBS_BACKUP_NAME).join(name);
         builder.append_file(path_in_archive, &mut file).await?;
 
+        // Files may have changed since the total was calculated,
+        // make sure not to report more than the total.
+        done_bytes = done_bytes.saturating_add(file_bytes).min(total_bytes);
+        context.emit_event(EventType::ImexBytesProgress {
+            done: done_bytes,
+            total: total_bytes,
+        });
+
         written_files += 1;
         let progress = 1000 * written_files / count;
         if progress != last_progress && progress > 10 && progress < 1000 {
@@ -893,6 +1099,36 @@ async fn test_export_and_import_key() {
         }
     }
 
+    #[async_std::test]
+    async fn test_export_backup_bytes_progress() -> Result<()> {
+        let backup_dir = tempfile::tempdir()?;
+        let t = TestContext::new_alice().await;
+        let blob_bytes = 100_000;
+        fs::write(t.get_blobdir().join("blob.bin"), vec![0u8; blob_bytes]).await?;
+
+        imex(&t, ImexMode::ExportBackup, backup_dir.path().as_ref(), None).await?;
+
+        let mut last_done = 0;
+        loop {
+            let (done, total) = match t
+                .evtracker
+                .get_matching(|evt| matches!(evt, EventType::ImexBytesProgress { .. }))
+                .await
+            {
+                EventType::ImexBytesProgress { done, total } => (done, total),
+                _ => unreachable!(),
+            };
+            assert!(total > blob_bytes as u64);
+            assert!(done >= last_done);
+            assert!(done <= total);
+            last_done = done;
+            if done == total {
+                break;
+            }
+        }
+        Ok(())
+    }
+
     #[async_std::test]
     async fn test_export_and_import_backup() -> Result<()> {
         let backup_dir = tempfile::tempdir().unwrap();
@@ -952,6 +1188,47 @@ async fn test_export_and_import_backup() -> Result<()> {
         Ok(())
     }
 
+    #[async_std::test]
+    async fn test_verify_backup() -> Result<()> {
+        let backup_dir = tempfile::tempdir()?;
+        let alice = TestContext::new_alice().await;
+        let file = alice.get_blobdir().join("hello.txt");
+        fs::write(&file, b"hello").await?;
+        let chat = alice.get_self_chat().await;
+        let mut msg = Message::new(Viewtype::File);
+        msg.set_file(file.to_str().unwrap(), None);
+        chat::send_msg(&alice, chat.id, &mut msg).await?;
+        imex(
+            &alice,
+            ImexMode::ExportBackup,
+            backup_dir.path().as_ref(),
+            None,
+        )
+        .await?;
+
+        let context2 = TestContext::new().await;
+        let backup = has_backup(&context2, backup_dir.path().as_ref()).await?;
+        let report = verify_backup(&context2, backup.as_ref(), None).await?;
+        assert!(report.is_ok(), "{:?}", report.problems);
+        assert!(report.db_version.is_some());
+        assert!(report.blob_count >= 1);
+
+        // A wrong passphrase is reported.
+        let report = verify_backup(&context2, backup.as_ref(), Some("foo".to_string())).await?;
+        assert!(!report.is_ok());
+
+        // Truncate the backup.
+        let data = fs::read(&backup).await?;
+        fs::write(&backup, &data[..data.len() / 2]).await?;
+        let report = verify_backup(&context2, backup.as_ref(), None).await?;
+        assert!(!report.is_ok());
+        assert_eq!(report.db_version, None);
+
+        // Nothing was imported.
+        assert!(!context2.is_configured().await?);
+        Ok(())
+    }
+
     #[test]
     fn test_normalize_setup_code() {
         let norm = normalize_setup_code("123422343234423452346234723482349234");
@@ -1023,6 +1300,14 @@ async fn test_key_transfer() -> Result<()> {
 
         // Transfer the key.
         continue_key_transfer(&alice2, msg.id, &setup_code).await?;
+        alice2
+            .evtracker
+            .get_matching(|evt| matches!(evt, EventType::ImexProgress(1000)))
+            .await;
+        assert_eq!(
+            SignedPublicKey::load_self(&alice2).await?.fingerprint(),
+            SignedPublicKey::load_self(&alice).await?.fingerprint()
+        );
 
         // Alice sends a message to self from the new device.
         let sent = alice2.send_text(msg.chat_id, "Test").await;
@@ -1034,4 +1319,51 @@ async fn test_key_transfer() -> Result<()> {
 
         Ok(())
     }
+
+    #[async_std::test]
+    async fn test_key_transfer_wrong_setup_code() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+
+        let alice_clone = alice.clone();
+        let key_transfer_task = async_std::task::spawn(async move {
+            let ctx = alice_clone;
+            initiate_key_transfer(&ctx).await
+        });
+        async_std::task::sleep(std::time::Duration::from_secs(1)).await;
+        let sent = alice.pop_sent_msg().await;
+        let setup_code = key_transfer_task.await?;
+
+        let alice2 = TestContext::new().await;
+        alice2.configure_addr("alice@example.org").await;
+        alice2.recv_msg(&sent).await;
+        let msg = alice2.get_last_msg().await;
+        assert!(msg.is_setupmessage());
+        let fingerprint = SignedPublicKey::load_self(&alice2).await?.fingerprint();
+
+        let wrong_code = if setup_code.starts_with('1') {
+            "2222-2222-2222-2222-2222-2222-2222-2222-2222"
+        } else {
+            "1111-1111-1111-1111-1111-1111-1111-1111-1111"
+        };
+        assert!(continue_key_transfer(&alice2, msg.id, wrong_code)
+            .await
+            .is_err());
+        alice2
+            .evtracker
+            .get_matching(|evt| matches!(evt, EventType::ImexProgress(0)))
+            .await;
+        assert_eq!(
+            SignedPublicKey::load_self(&alice2).await?.fingerprint(),
+            fingerprint
+        );
+
+        // A message that is no setup message is rejected as well.
+        let sent = alice2.send_text(msg.chat_id, "no setup message").await;
+        assert!(
+            continue_key_transfer(&alice2, sent.sender_msg_id, &setup_code)
+                .await
+                .is_err()
+        );
+        Ok(())
+    }
 }
@@ -16,6 +16,7 @@
 use crate::constants::KeyGenType;
 use crate::context::Context;
 use crate::dc_tools::{time, EmailAddress};
+use crate::keyring::Keyring;
 
 // Re-export key types
 pub use crate::pgp::KeyPair;
@@ -266,6 +267,91 @@ pub(crate) async fn load_keypair(
     })
 }
 
+/// Loads all secret keys of the user for decryption.
+///
+/// The default key comes first,
+/// followed by keys which were replaced by [rotate_keypair_if_needed].
+pub(crate) async fn load_self_secret_keyring(
+    context: &Context,
+) -> Result<Keyring<SignedSecretKey>> {
+    let mut keyring = Keyring::new_self(context).await?;
+    let old_keys = context
+        .sql
+        .query_map(
+            "SELECT private_key FROM keypairs WHERE is_default=0 ORDER BY created DESC",
+            paramsv![],
+            |row| row.get::<_, Vec<u8>>(0),
+            |rows| rows.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+        )
+        .await?;
+    for bytes in old_keys {
+        keyring.add(SignedSecretKey::from_slice(&bytes)?);
+    }
+    Ok(keyring)
+}
+
+/// Loads all public keys of the user, eg. to check own signatures.
+///
+/// The default key comes first,
+/// followed by keys which were replaced by [rotate_keypair_if_needed].
+pub(crate) async fn load_self_public_keyring(
+    context: &Context,
+) -> Result<Keyring<SignedPublicKey>> {
+    let mut keyring = Keyring::new();
+    keyring.add(SignedPublicKey::load_self(context).await?);
+    let old_keys = context
+        .sql
+        .query_map(
+            "SELECT public_key FROM keypairs WHERE is_default=0 ORDER BY created DESC",
+            paramsv![],
+            |row| row.get::<_, Vec<u8>>(0),
+            |rows| rows.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+        )
+        .await?;
+    for bytes in old_keys {
+        keyring.add(SignedPublicKey::from_slice(&bytes)?);
+    }
+    Ok(keyring)
+}
+
+/// Generates a new default keypair
+/// if the current one is older than [Config::KeyRotationDays].
+///
+/// The old keypair is kept for decryption.
+/// Returns true if a new keypair was generated.
+pub(crate) async fn rotate_keypair_if_needed(context: &Context) -> Result<bool> {
+    let rotation_days = context.get_config_int(Config::KeyRotationDays).await?;
+    if rotation_days <= 0 {
+        return Ok(false);
+    }
+    let addr = context.get_primary_self_addr().await?;
+    let _guard = context.generating_key_mutex.lock().await;
+
+    let created: Option<i64> = context
+        .sql
+        .query_get_value(
+            "SELECT created FROM keypairs WHERE addr=? AND is_default=1",
+            paramsv![addr],
+        )
+        .await?;
+    match created {
+        Some(created) if created + i64::from(rotation_days) * 24 * 60 * 60 <= time() => {}
+        _ => return Ok(false),
+    }
+
+    let addr = EmailAddress::new(&addr)?;
+    let keytype =
+        KeyGenType::from_i32(context.get_config_int(Config::KeyGenType).await?).unwrap_or_default();
+    info!(
+        context,
+        "Rotating keypair, generating new key with type {}", keytype
+    );
+    let keypair =
+        async_std::task::spawn_blocking(move || crate::pgp::create_keypair(addr, keytype)).await?;
+    store_self_keypair(context, &keypair, KeyPairUse::Default).await?;
+    Ok(true)
+}
+
 /// Use of a [KeyPair] for encryption or decryption.
 ///
 /// This is used by [store_self_keypair] to know what kind of key is
@@ -601,6 +687,46 @@ async fn test_save_self_key_twice() {
         assert_eq!(nrows().await, 1);
     }
 
+    #[async_std::test]
+    async fn test_rotate_keypair_if_needed() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+        let old_key = SignedPublicKey::load_self(&alice).await?;
+        assert!(!rotate_keypair_if_needed(&alice).await?);
+
+        alice.set_config(Config::KeyRotationDays, Some("1")).await?;
+        assert!(!rotate_keypair_if_needed(&alice).await?);
+
+        // Pretend the key was created two days ago.
+        alice
+            .sql
+            .execute(
+                "UPDATE keypairs SET created=? WHERE is_default=1",
+                paramsv![time() - 2 * 24 * 60 * 60],
+            )
+            .await?;
+        assert!(rotate_keypair_if_needed(&alice).await?);
+        assert!(!rotate_keypair_if_needed(&alice).await?);
+
+        let new_key = SignedPublicKey::load_self(&alice).await?;
+        assert_ne!(new_key, old_key);
+        assert_eq!(
+            alice
+                .sql
+                .count("SELECT COUNT(*) FROM keypairs", paramsv![])
+                .await?,
+            2
+        );
+
+        // The old key is still used for decryption.
+        let keyring = load_self_secret_keyring(&alice).await?;
+        assert_eq!(keyring.len(), 2);
+        assert_eq!(
+            keyring.keys()[1].split_public_key()?.primary_key,
+            old_key.primary_key
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_fingerprint_from_str() {
         let res = Fingerprint::new(vec![
@@ -59,6 +59,7 @@ impl<T: rusqlite::ToSql + Send + Sync> ToSql for T {}
 pub mod download;
 mod e2ee;
 pub mod ephemeral;
+pub mod formatting;
 mod imap;
 pub mod imex;
 mod scheduler;
@@ -69,6 +70,7 @@ impl<T: rusqlite::ToSql + Send + Sync> ToSql for T {}
 mod keyring;
 pub mod location;
 mod login_param;
+mod mention;
 pub mod message;
 mod mimefactory;
 pub mod mimeparser;
@@ -84,7 +86,7 @@ impl<T: rusqlite::ToSql + Send + Sync> ToSql for T {}
 mod simplify;
 mod smtp;
 pub mod stock_str;
-mod sync;
+pub mod sync;
 mod token;
 mod update_helper;
 pub mod webxdc;
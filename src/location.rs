@@ -1,4 +1,5 @@
 //! Location handling.
+use std::cmp::min;
 use std::convert::TryFrom;
 
 use anyhow::{ensure, Context as _, Result};
@@ -9,6 +10,7 @@
 use std::time::Duration;
 
 use crate::chat::{self, ChatId};
+use crate::constants::DC_CHAT_ID_TRASH;
 use crate::contact::ContactId;
 use crate::context::Context;
 use crate::dc_tools::{duration_to_str, time};
@@ -32,10 +34,47 @@ pub struct Location {
     pub independent: u32,
 }
 
+/// Mean radius of the earth in meters, used for distance calculations.
+const EARTH_RADIUS_METERS: f64 = 6_371_000.0;
+
 impl Location {
     pub fn new() -> Self {
         Default::default()
     }
+
+    /// Returns the great-circle distance to `other` in meters,
+    /// calculated using the haversine formula.
+    pub fn distance_meters(&self, other: &Location) -> f64 {
+        let lat1 = self.latitude.to_radians();
+        let lat2 = other.latitude.to_radians();
+        let dlat = lat2 - lat1;
+        let dlon = (other.longitude - self.longitude).to_radians();
+        let a = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlon / 2.0).sin().powi(2);
+        // Rounding may make `a` slightly larger than 1 for antipodal points.
+        2.0 * EARTH_RADIUS_METERS * a.sqrt().min(1.0).asin()
+    }
+
+    fn has_same_position(&self, other: &Location) -> bool {
+        self.latitude == other.latitude && self.longitude == other.longitude
+    }
+}
+
+/// Sorts `locations` by timestamp and removes consecutive locations with identical coordinates,
+/// so that the locations can be rendered as a path, eg. the locations returned by [`get_range`].
+///
+/// The sort is stable, of consecutive identical coordinates only the first location is kept.
+pub fn sort_and_dedup(locations: &mut Vec<Location>) {
+    locations.sort_by_key(|location| location.timestamp);
+    locations.dedup_by(|location, prev| location.has_same_position(prev));
+}
+
+/// Returns the length of the path along `locations` in meters.
+pub fn path_length_meters(locations: &[Location]) -> f64 {
+    locations
+        .iter()
+        .zip(locations.iter().skip(1))
+        .map(|(from, to)| from.distance_meters(to))
+        .sum()
 }
 
 #[derive(Debug, Clone, Default)]
@@ -221,6 +260,9 @@ pub async fn send_locations_to_chat(
         chat::send_msg(context, chat_id, &mut msg)
             .await
             .unwrap_or_default();
+    } else if 0 != seconds && is_sending_locations_before {
+        let stock_str = stock_str::msg_location_extended(context, now + seconds).await;
+        chat::add_info_msg(context, chat_id, &stock_str, now).await?;
     } else if 0 == seconds && is_sending_locations_before {
         let stock_str = stock_str::msg_location_disabled(context).await;
         chat::add_info_msg(context, chat_id, &stock_str, now).await?;
@@ -264,9 +306,24 @@ pub async fn is_sending_locations_to_chat(
 }
 
 pub async fn set(context: &Context, latitude: f64, longitude: f64, accuracy: f64) -> bool {
+    set_at(context, latitude, longitude, accuracy, time()).await
+}
+
+/// Same as [`set`], but stores the location with the given `timestamp`,
+/// eg. when importing a recorded trace.
+///
+/// Timestamps in the future are replaced by the current time.
+pub async fn set_at(
+    context: &Context,
+    latitude: f64,
+    longitude: f64,
+    accuracy: f64,
+    timestamp: i64,
+) -> bool {
     if latitude == 0.0 && longitude == 0.0 {
         return true;
     }
+    let timestamp = min(timestamp, time());
     let mut continue_streaming = false;
 
     if let Ok(chats) = context
@@ -291,7 +348,7 @@ pub async fn set(context: &Context, latitude: f64, longitude: f64, accuracy: f64
                         latitude,
                         longitude,
                         accuracy,
-                        time(),
+                        timestamp,
                         chat_id,
                         ContactId::SELF,
                     ]
@@ -381,6 +438,24 @@ pub async fn get_range(
     Ok(list)
 }
 
+/// Time in seconds a streamed location is considered current.
+const SHARING_WINDOW: i64 = 30 * 60;
+
+/// Returns the contacts that are currently sharing their location in the chat,
+/// i.e. that streamed a location within the last [`SHARING_WINDOW`] seconds.
+///
+/// Independent locations, such as markers and locations attached to messages, are not counted.
+pub async fn get_sharing_contacts(context: &Context, chat_id: ChatId) -> Result<Vec<ContactId>> {
+    let locations = get_range(context, Some(chat_id), None, time() - SHARING_WINDOW, 0).await?;
+    let mut contact_ids = Vec::new();
+    for location in locations {
+        if location.independent == 0 && !contact_ids.contains(&location.contact_id) {
+            contact_ids.push(location.contact_id);
+        }
+    }
+    Ok(contact_ids)
+}
+
 fn is_marker(txt: &str) -> bool {
     let mut chars = txt.chars();
     if let Some(c) = chars.next() {
@@ -484,6 +559,32 @@ fn get_kml_timestamp(utc: i64) -> String {
         .to_string()
 }
 
+/// Deletes message locations which are not referenced by any message anymore.
+///
+/// Locations attached to a message are marked as `independent`
+/// and should be deleted together with the message,
+/// but messages removed e.g. by the ephemeral timer leave them behind.
+/// Streaming locations are not bound to messages and are not touched.
+///
+/// Returns the number of deleted locations.
+pub async fn repair_orphans(context: &Context) -> Result<usize> {
+    let deleted = context
+        .sql
+        .execute(
+            "DELETE FROM locations
+             WHERE independent=1
+             AND id NOT IN
+             (SELECT location_id FROM msgs WHERE chat_id!=? AND location_id!=0)",
+            paramsv![DC_CHAT_ID_TRASH],
+        )
+        .await?;
+    if deleted > 0 {
+        info!(context, "Deleted {} orphaned locations.", deleted);
+        context.emit_event(EventType::LocationChanged(None));
+    }
+    Ok(deleted)
+}
+
 pub fn get_message_kml(timestamp: i64, latitude: f64, longitude: f64) -> String {
     format!(
         "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
@@ -728,9 +829,120 @@ mod tests {
     #![allow(clippy::indexing_slicing)]
 
     use super::*;
+    use crate::chat::{send_msg, ProtectionStatus};
+    use crate::contact::Contact;
     use crate::dc_receive_imf::dc_receive_imf;
     use crate::test_utils::TestContext;
 
+    #[async_std::test]
+    async fn test_repair_orphans() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+        let chat = alice
+            .create_chat_with_contact("Bob", "bob@example.net")
+            .await;
+
+        let mut msg = Message::new(Viewtype::Text);
+        msg.set_text(Some("meet me here".to_string()));
+        msg.set_location(52.5, 13.4);
+        let msg_id = send_msg(&alice, chat.id, &mut msg).await?;
+
+        let mut msg = Message::new(Viewtype::Text);
+        msg.set_text(Some("or here".to_string()));
+        msg.set_location(48.1, 11.6);
+        send_msg(&alice, chat.id, &mut msg).await?;
+
+        let streamed = Location {
+            latitude: 50.0,
+            longitude: 8.0,
+            timestamp: time(),
+            ..Default::default()
+        };
+        save(&alice, chat.id, ContactId::SELF, &[streamed], false).await?;
+
+        let count_locations = || async {
+            alice
+                .sql
+                .count("SELECT COUNT(*) FROM locations", paramsv![])
+                .await
+        };
+        assert_eq!(count_locations().await?, 3);
+        assert_eq!(repair_orphans(&alice).await?, 0);
+
+        // Trash the message without deleting its location.
+        msg_id.trash(&alice).await?;
+        assert_eq!(repair_orphans(&alice).await?, 1);
+        assert_eq!(count_locations().await?, 2);
+
+        // The other marker and the streamed location survive.
+        let locations = get_range(&alice, Some(chat.id), None, 0, 0).await?;
+        assert_eq!(locations.len(), 2);
+        assert!(locations.iter().any(|l| l.independent == 1));
+        assert!(locations.iter().any(|l| l.independent == 0));
+        assert_eq!(repair_orphans(&alice).await?, 0);
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn test_send_image_with_location() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+        let bob = TestContext::new_bob().await;
+        let chat = alice.create_chat(&bob).await;
+
+        let file = alice.get_blobdir().join("image.png");
+        async_std::fs::write(&file, include_bytes!("../test-data/image/avatar64x64.png")).await?;
+        let mut msg = Message::new(Viewtype::Image);
+        msg.set_file(file.to_str().unwrap(), None);
+        msg.set_location(52.5, 13.4);
+        let sent = alice.send_msg(chat.id, &mut msg).await;
+        assert!(alice.get_last_msg().await.has_location());
+
+        let bob_msg = bob.recv_msg(&sent).await;
+        assert_eq!(bob_msg.get_viewtype(), Viewtype::Image);
+        assert!(bob_msg.get_file(&bob).is_some());
+        assert!(bob_msg.has_location());
+
+        let locations = get_range(&bob, Some(bob_msg.chat_id), None, 0, 0).await?;
+        assert_eq!(locations.len(), 1);
+        let location = &locations[0];
+        assert_eq!(location.independent, 1);
+        assert!((location.latitude - 52.5).abs() < 1e-6);
+        assert!((location.longitude - 13.4).abs() < 1e-6);
+        assert_eq!(location.contact_id, bob_msg.from_id);
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn test_get_sharing_contacts() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+        let chat_id =
+            chat::create_group_chat(&alice, ProtectionStatus::Unprotected, "hike").await?;
+        let bob_id = Contact::create(&alice, "Bob", "bob@example.net").await?;
+        let claire_id = Contact::create(&alice, "Claire", "claire@example.com").await?;
+        let dave_id = Contact::create(&alice, "Dave", "dave@example.org").await?;
+        for contact_id in [bob_id, claire_id, dave_id] {
+            chat::add_contact_to_chat(&alice, chat_id, contact_id).await?;
+        }
+        assert!(get_sharing_contacts(&alice, chat_id).await?.is_empty());
+
+        let location = |timestamp| Location {
+            latitude: 50.0,
+            longitude: 8.0,
+            timestamp,
+            ..Default::default()
+        };
+        save(&alice, chat_id, bob_id, &[location(time())], false).await?;
+        save(&alice, chat_id, claire_id, &[location(time() - 60)], false).await?;
+        // Dave only sent an outdated location and a marker.
+        save(&alice, chat_id, dave_id, &[location(time() - 3600)], false).await?;
+        save(&alice, chat_id, dave_id, &[location(time())], true).await?;
+
+        let sharers = get_sharing_contacts(&alice, chat_id).await?;
+        assert_eq!(sharers.len(), 2);
+        assert!(sharers.contains(&bob_id));
+        assert!(sharers.contains(&claire_id));
+        Ok(())
+    }
+
     #[async_std::test]
     async fn test_kml_parse() {
         let context = TestContext::new().await;
@@ -781,6 +993,70 @@ async fn test_get_message_kml() {
         assert_eq!(locations_ref[0].timestamp, timestamp);
     }
 
+    fn location_at(latitude: f64, longitude: f64, timestamp: i64) -> Location {
+        Location {
+            latitude,
+            longitude,
+            timestamp,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_sort_and_dedup() {
+        let mut locations = vec![
+            location_at(1.0, 1.0, 30),
+            location_at(0.0, 0.0, 10),
+            location_at(0.0, 0.0, 20),
+            location_at(1.0, 1.0, 40),
+            location_at(0.0, 0.0, 50),
+        ];
+        sort_and_dedup(&mut locations);
+        let timestamps: Vec<i64> = locations.iter().map(|l| l.timestamp).collect();
+        assert_eq!(timestamps, vec![10, 30, 50]);
+
+        let mut locations = Vec::new();
+        sort_and_dedup(&mut locations);
+        assert!(locations.is_empty());
+    }
+
+    #[test]
+    fn test_path_length_meters() {
+        assert_eq!(path_length_meters(&[]), 0.0);
+        assert_eq!(path_length_meters(&[location_at(52.5, 13.4, 0)]), 0.0);
+
+        // one degree along a meridian is 1/360 of the circumference
+        let locations = vec![
+            location_at(0.0, 0.0, 0),
+            location_at(1.0, 0.0, 1),
+            location_at(2.0, 0.0, 2),
+        ];
+        let expected = 2.0 * 2.0 * std::f64::consts::PI * EARTH_RADIUS_METERS / 360.0;
+        assert!((path_length_meters(&locations) - expected).abs() < 0.01);
+
+        // Berlin to Paris is about 878 km
+        let berlin = location_at(52.5200, 13.4050, 0);
+        let paris = location_at(48.8566, 2.3522, 0);
+        let distance = berlin.distance_meters(&paris);
+        assert!((distance - 877_500.0).abs() < 2_000.0);
+        assert_eq!(paris.distance_meters(&berlin), distance);
+
+        // antipodal points are half the circumference apart
+        let half_circumference = std::f64::consts::PI * EARTH_RADIUS_METERS;
+        for &(lat, lon) in &[
+            (0.0, 0.0),
+            (52.52, 13.405),
+            (-33.8688, 151.2093),
+            (89.9, 0.1),
+        ] {
+            let location = location_at(lat, lon, 0);
+            let antipode = location_at(-lat, lon - 180.0, 1);
+            let distance = location.distance_meters(&antipode);
+            assert!((distance - half_circumference).abs() < 1.0);
+            assert!(path_length_meters(&[location, antipode]).is_finite());
+        }
+    }
+
     #[test]
     fn test_is_marker() {
         assert!(is_marker("f"));
@@ -853,4 +1129,62 @@ async fn receive_location_kml() -> Result<()> {
         assert_eq!(locations.len(), 1);
         Ok(())
     }
+
+    #[async_std::test]
+    async fn test_set_at() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+        let chat = alice
+            .create_chat_with_contact("Bob", "bob@example.net")
+            .await;
+        send_locations_to_chat(&alice, chat.id, 1000).await?;
+
+        let now = time();
+        assert!(set_at(&alice, 1.0, 2.0, 10.0, now - 100).await);
+        assert!(set_at(&alice, 3.0, 4.0, 10.0, now - 50).await);
+
+        let locations = get_range(&alice, Some(chat.id), None, now - 200, 0).await?;
+        let locations: Vec<_> = locations
+            .into_iter()
+            .filter(|location| location.contact_id == ContactId::SELF && location.latitude > 0.5)
+            .collect();
+        assert_eq!(locations.len(), 2);
+        assert!((locations[0].latitude - 3.0).abs() < f64::EPSILON);
+        assert_eq!(locations[0].timestamp, now - 50);
+        assert!((locations[1].latitude - 1.0).abs() < f64::EPSILON);
+        assert_eq!(locations[1].timestamp, now - 100);
+
+        // Timestamps in the future are not stored.
+        assert!(set_at(&alice, 5.0, 6.0, 10.0, now + 3600).await);
+        let locations = get_range(&alice, Some(chat.id), None, 0, now + 7200).await?;
+        assert!(locations
+            .iter()
+            .all(|location| location.timestamp <= time()));
+
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn test_send_locations_to_chat_extend() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+        let chat = alice
+            .create_chat_with_contact("Bob", "bob@example.net")
+            .await;
+
+        send_locations_to_chat(&alice, chat.id, 1000).await?;
+        let msg = alice.get_last_msg_in(chat.id).await;
+        assert_eq!(msg.get_info_type(), SystemMessage::LocationStreamingEnabled);
+
+        // Enabling again while streaming extends the session.
+        send_locations_to_chat(&alice, chat.id, 3600).await?;
+        let msg = alice.get_last_msg_in(chat.id).await;
+        assert!(msg.is_info());
+        assert_ne!(msg.get_info_type(), SystemMessage::LocationStreamingEnabled);
+        assert!(msg
+            .get_text()
+            .unwrap()
+            .starts_with("Location streaming extended until "));
+        assert_eq!(chat.id.get_msg_cnt(&alice).await?, 2);
+
+        Ok(())
+    }
 }
@@ -6,14 +6,15 @@
 
 use crate::provider::{get_provider_by_id, Provider};
 use crate::{context::Context, provider::Socket};
-use anyhow::Result;
+use anyhow::{Context as _, Result};
 
 use async_std::io;
 use async_std::net::TcpStream;
 
 use async_native_tls::Certificate;
 pub use async_smtp::ServerAddress;
-use fast_socks5::client::Socks5Stream;
+use fast_socks5::client::{Config as Socks5ClientConfig, Socks5Stream};
+use fast_socks5::AuthenticationMethod;
 use once_cell::sync::Lazy;
 
 #[derive(Copy, Clone, Debug, Display, FromPrimitive, PartialEq, Eq)]
@@ -103,6 +104,36 @@ pub async fn connect(
             .await
     }
 
+    /// Checks that a SOCKS5 proxy is listening at the configured address
+    /// and accepts the configured credentials.
+    ///
+    /// Only the SOCKS5 greeting and authentication are done, no connection is requested.
+    pub(crate) async fn check(&self, timeout: Duration) -> Result<()> {
+        async_std::future::timeout(timeout, self.check_inner())
+            .await
+            .context("SOCKS5 proxy did not respond in time")?
+    }
+
+    async fn check_inner(&self) -> Result<()> {
+        let stream = TcpStream::connect((self.host.as_str(), self.port))
+            .await
+            .with_context(|| {
+                format!("cannot connect to SOCKS5 proxy {}:{}", self.host, self.port)
+            })?;
+
+        // Same handshake as for the real connections, but without requesting a target.
+        let auth = self.user_password.as_ref().map(|(username, password)| {
+            AuthenticationMethod::Password {
+                username: username.clone(),
+                password: password.clone(),
+            }
+        });
+        Socks5Stream::use_stream(stream, auth, Socks5ClientConfig::default())
+            .await
+            .context("SOCKS5 handshake failed")?;
+        Ok(())
+    }
+
     pub fn to_async_smtp_socks5_config(&self) -> async_smtp::smtp::Socks5Config {
         async_smtp::smtp::Socks5Config {
             host: self.host.clone(),
@@ -0,0 +1,245 @@
+//! # Mention detection.
+//!
+//! Mentions are written as `@name` or `@addr` in the message text
+//! and are resolved against the chat members when a message is sent or received.
+//! Resolved mentions are stored in the `mentions` table.
+
+use anyhow::Result;
+
+use crate::chat::{self, ChatId};
+use crate::config::Config;
+use crate::contact::{Contact, ContactId};
+use crate::context::Context;
+use crate::message::MsgId;
+
+/// Returns true if `text` mentions the user,
+/// i.e. contains one of the self addresses or the self display name as a whole word.
+pub(crate) async fn is_self_mentioned(context: &Context, text: &str) -> Result<bool> {
+    let text = text.to_lowercase();
+    for addr in context.get_all_self_addrs().await? {
+        if contains_word(&text, &addr.to_lowercase()) {
+            return Ok(true);
+        }
+    }
+    if let Some(displayname) = context.get_config(Config::Displayname).await? {
+        let displayname = displayname.trim().to_lowercase();
+        if !displayname.is_empty() && contains_word(&text, &displayname) {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Resolves `@name` and `@addr` mentions in `text` against the members of `chat_id`
+/// and stores them for `msg_id`, replacing previously stored mentions.
+///
+/// Mentions of trashed or deleted messages are removed when the message is.
+/// Returns the mentioned contacts.
+pub(crate) async fn save_mentions(
+    context: &Context,
+    msg_id: MsgId,
+    chat_id: ChatId,
+    text: &str,
+) -> Result<Vec<ContactId>> {
+    let mentioned = if text.contains('@') {
+        let members = get_mention_keys(context, chat_id).await?;
+        parse_mentions(text, &members)
+    } else {
+        Vec::new()
+    };
+    if mentioned.is_empty()
+        && !context
+            .sql
+            .exists(
+                "SELECT COUNT(*) FROM mentions WHERE msg_id=?",
+                paramsv![msg_id],
+            )
+            .await?
+    {
+        return Ok(mentioned);
+    }
+
+    let to_insert = mentioned.clone();
+    context
+        .sql
+        .transaction(move |transaction| {
+            transaction.execute("DELETE FROM mentions WHERE msg_id=?", paramsv![msg_id])?;
+            for contact_id in to_insert {
+                transaction.execute(
+                    "INSERT INTO mentions (msg_id, contact_id) VALUES (?, ?)",
+                    paramsv![msg_id, contact_id],
+                )?;
+            }
+            Ok(())
+        })
+        .await?;
+    Ok(mentioned)
+}
+
+/// Returns the contacts mentioned in the message `msg_id`.
+pub(crate) async fn get_mentions(context: &Context, msg_id: MsgId) -> Result<Vec<ContactId>> {
+    context
+        .sql
+        .query_map(
+            "SELECT contact_id FROM mentions WHERE msg_id=? ORDER BY contact_id",
+            paramsv![msg_id],
+            |row| row.get::<_, ContactId>(0),
+            |rows| rows.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+        )
+        .await
+}
+
+/// Returns the lowercased names and addresses by which chat members can be mentioned.
+///
+/// The user can always be mentioned, even in 1:1 chats where self is not listed as a member.
+async fn get_mention_keys(
+    context: &Context,
+    chat_id: ChatId,
+) -> Result<Vec<(ContactId, Vec<String>)>> {
+    let mut contact_ids = chat::get_chat_contacts(context, chat_id).await?;
+    if !contact_ids.contains(&ContactId::SELF) {
+        contact_ids.push(ContactId::SELF);
+    }
+    let mut res = Vec::new();
+    for contact_id in contact_ids {
+        let mut keys = Vec::new();
+        if contact_id == ContactId::SELF {
+            if let Some(displayname) = context.get_config(Config::Displayname).await? {
+                keys.push(displayname);
+            }
+            keys.extend(context.get_all_self_addrs().await?);
+        } else {
+            let contact = Contact::get_by_id(context, contact_id).await?;
+            keys.push(contact.get_display_name().to_string());
+            keys.push(contact.get_addr().to_string());
+        }
+        let keys = keys
+            .into_iter()
+            .map(|key| key.trim().to_lowercase())
+            .filter(|key| !key.is_empty())
+            .collect();
+        res.push((contact_id, keys));
+    }
+    Ok(res)
+}
+
+/// Finds `@` mentions in `text` matching one of the `members` keys.
+///
+/// An `@` directly following an alphanumeric character is part of an email address
+/// and does not start a mention. If several keys match, the longest one wins,
+/// so `@Bob Smith` is preferred over `@Bob`.
+fn parse_mentions(text: &str, members: &[(ContactId, Vec<String>)]) -> Vec<ContactId> {
+    let text = text.to_lowercase();
+    let mut res = Vec::new();
+    for (i, _) in text.match_indices('@') {
+        if text[..i]
+            .chars()
+            .next_back()
+            .map_or(false, char::is_alphanumeric)
+        {
+            continue;
+        }
+        let rest = &text[i + 1..];
+        let best = members
+            .iter()
+            .flat_map(|(contact_id, keys)| keys.iter().map(move |key| (*contact_id, key)))
+            .filter(|(_, key)| {
+                rest.starts_with(key.as_str())
+                    && !rest[key.len()..]
+                        .chars()
+                        .next()
+                        .map_or(false, char::is_alphanumeric)
+            })
+            .max_by_key(|(_, key)| key.len());
+        if let Some((contact_id, _)) = best {
+            if !res.contains(&contact_id) {
+                res.push(contact_id);
+            }
+        }
+    }
+    res
+}
+
+/// Returns true if `word` occurs in `text` not surrounded by alphanumeric characters.
+fn contains_word(text: &str, word: &str) -> bool {
+    text.match_indices(word).any(|(start, _)| {
+        let end = start + word.len();
+        let before = text[..start].chars().next_back();
+        let after = text[end..].chars().next();
+        !before.map_or(false, char::is_alphanumeric) && !after.map_or(false, char::is_alphanumeric)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat::ProtectionStatus;
+    use crate::message::{self, Message};
+    use crate::test_utils::TestContext;
+
+    #[test]
+    fn test_contains_word() {
+        assert!(contains_word("hi alice", "alice"));
+        assert!(contains_word("@alice, look", "alice"));
+        assert!(!contains_word("malice", "alice"));
+        assert!(!contains_word("alicea", "alice"));
+    }
+
+    #[test]
+    fn test_parse_mentions() {
+        let bob = ContactId::new(10);
+        let bob_smith = ContactId::new(11);
+        let members = vec![
+            (bob, vec!["bob".to_string(), "bob@example.net".to_string()]),
+            (
+                bob_smith,
+                vec!["bob smith".to_string(), "smith@example.org".to_string()],
+            ),
+        ];
+        assert_eq!(parse_mentions("hi @Bob!", &members), vec![bob]);
+        assert_eq!(parse_mentions("hi @bob smith", &members), vec![bob_smith]);
+        assert_eq!(
+            parse_mentions("@smith@example.org and @bob@example.net, @bob", &members),
+            vec![bob_smith, bob]
+        );
+        assert!(parse_mentions("mail bob@example.net", &members).is_empty());
+        assert!(parse_mentions("@bobby", &members).is_empty());
+    }
+
+    #[async_std::test]
+    async fn test_save_mentions() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+        let bob = TestContext::new_bob().await;
+        bob.set_config(Config::Displayname, Some("Bob")).await?;
+        let alice_chat_id =
+            chat::create_group_chat(&alice, ProtectionStatus::Unprotected, "group").await?;
+        let bob_id = Contact::create(&alice, "Bob", "bob@example.net").await?;
+        chat::add_contact_to_chat(&alice, alice_chat_id, bob_id).await?;
+
+        let sent = alice.send_text(alice_chat_id, "@Bob, can you check?").await;
+        let msg = Message::load_from_db(&alice, sent.sender_msg_id).await?;
+        assert_eq!(msg.get_mentions(&alice).await?, vec![bob_id]);
+
+        let bob_msg = bob.recv_msg(&sent).await;
+        assert_eq!(bob_msg.get_mentions(&bob).await?, vec![ContactId::SELF]);
+
+        // Mentions are removed with the message.
+        message::delete_msgs(&bob, &[bob_msg.id]).await?;
+        assert!(get_mentions(&bob, bob_msg.id).await?.is_empty());
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn test_is_self_mentioned() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        assert!(is_self_mentioned(&t, "ask Alice@Example.org").await?);
+        assert!(!is_self_mentioned(&t, "ask malice@example.org").await?);
+        assert!(!is_self_mentioned(&t, "see alice@example.organic").await?);
+        assert!(!is_self_mentioned(&t, "hi @alice").await?);
+
+        t.set_config(Config::Displayname, Some("Alice")).await?;
+        assert!(is_self_mentioned(&t, "hi @alice").await?);
+        assert!(!is_self_mentioned(&t, "no malice here").await?);
+        Ok(())
+    }
+}
@@ -1,6 +1,7 @@
 //! # Messages and their identifiers.
 
 use std::collections::BTreeSet;
+use std::ops::Range;
 
 use anyhow::{ensure, format_err, Context as _, Result};
 use async_std::path::{Path, PathBuf};
@@ -8,6 +9,7 @@
 use rusqlite::types::ValueRef;
 use serde::{Deserialize, Serialize};
 
+use crate::blob::BlobObject;
 use crate::chat::{self, Chat, ChatId};
 use crate::config::Config;
 use crate::constants::{
@@ -22,10 +24,13 @@
 use crate::download::DownloadState;
 use crate::ephemeral::{start_ephemeral_timers_msgids, Timer as EphemeralTimer};
 use crate::events::EventType;
+use crate::formatting::{parse_formatting, Style};
 use crate::imap::markseen_on_imap_table;
 use crate::log::LogExt;
-use crate::mimeparser::{parse_message_id, FailureReport, SystemMessage};
+use crate::mention;
+use crate::mimeparser::{parse_message_id, parse_message_ids, FailureReport, SystemMessage};
 use crate::param::{Param, Params};
+use crate::peerstate::Peerstate;
 use crate::pgp::split_armored_data;
 use crate::scheduler::InterruptInfo;
 use crate::sql;
@@ -109,6 +114,10 @@ pub async fn trash(self, context: &Context) -> Result<()> {
                 paramsv![chat_id, self],
             )
             .await?;
+        context
+            .sql
+            .execute("DELETE FROM mentions WHERE msg_id=?", paramsv![self])
+            .await?;
 
         Ok(())
     }
@@ -368,6 +377,7 @@ pub async fn load_from_db(context: &Context, id: MsgId) -> Result<Message> {
         Ok(msg)
     }
 
+    /// Returns the MIME type of the attached file, `None` if there is no file.
     pub fn get_filemime(&self) -> Option<String> {
         if let Some(m) = self.param.get(Param::MimeType) {
             return Some(m.to_string());
@@ -430,6 +440,11 @@ pub fn has_location(&self) -> bool {
     /// if you want to bind the current self-location to a message;
     /// this is done by dc_set_location() and dc_send_locations_to_chat().
     ///
+    /// The location can be set for messages of any viewtype,
+    /// eg. for an image taken at that place.
+    /// It is stored as an independent location when sending
+    /// and receivers get it via `location::get_range()`.
+    ///
     /// Typically results in the event #DC_EVENT_LOCATION_CHANGED with
     /// contact_id set to ContactId::SELF.
     ///
@@ -485,6 +500,17 @@ pub fn get_text(&self) -> Option<String> {
         self.text.as_ref().map(|s| s.to_string())
     }
 
+    /// Returns the inline formatting of the message text,
+    /// see [`crate::formatting::parse_formatting`].
+    ///
+    /// The ranges are byte offsets into the text returned by [`Message::get_text`].
+    pub fn get_formatting(&self) -> Vec<(Range<usize>, Style)> {
+        self.text
+            .as_deref()
+            .map(parse_formatting)
+            .unwrap_or_default()
+    }
+
     pub fn get_subject(&self) -> &str {
         &self.subject
     }
@@ -496,6 +522,7 @@ pub fn get_filename(&self) -> Option<String> {
             .map(|name| name.to_string_lossy().to_string())
     }
 
+    /// Returns the size of the attached file in bytes, 0 if there is no file.
     pub async fn get_filebytes(&self, context: &Context) -> u64 {
         match self.param.get_path(Param::File, context) {
             Ok(Some(path)) => dc_get_filebytes(context, &path).await,
@@ -520,6 +547,60 @@ pub fn get_showpadlock(&self) -> bool {
         self.param.get_int(Param::GuaranteeE2ee).unwrap_or_default() != 0
     }
 
+    /// Returns structured information about the message,
+    /// containing most fields of [`get_msg_info`].
+    pub async fn get_info_struct(&self, context: &Context) -> Result<MsgInfo> {
+        let timestamp_rcvd = if self.from_id == ContactId::SELF {
+            None
+        } else if self.timestamp_rcvd != 0 {
+            Some(self.timestamp_rcvd)
+        } else {
+            Some(self.timestamp_sort)
+        };
+
+        let encrypted = self.get_showpadlock();
+        let sender_fingerprint = if encrypted && self.from_id != ContactId::SELF {
+            let contact = Contact::load_from_db(context, self.from_id).await?;
+            Peerstate::from_addr(context, contact.get_addr())
+                .await?
+                .and_then(|peerstate| peerstate.public_key_fingerprint)
+                .map(|fingerprint| fingerprint.hex())
+        } else {
+            None
+        };
+
+        let server_location = context
+            .sql
+            .query_row_optional(
+                "SELECT folder, uid FROM imap WHERE rfc724_mid=? ORDER BY id LIMIT 1",
+                paramsv![self.rfc724_mid],
+                |row| {
+                    let folder: String = row.get(0)?;
+                    let uid: u32 = row.get(1)?;
+                    Ok((folder, uid))
+                },
+            )
+            .await?;
+        let (server_folder, server_uid) = match server_location {
+            Some((folder, uid)) => (Some(folder), Some(uid)),
+            None => (None, None),
+        };
+
+        Ok(MsgInfo {
+            from_id: self.from_id,
+            timestamp_sent: self.get_timestamp(),
+            timestamp_rcvd,
+            state: self.state,
+            encrypted,
+            sender_fingerprint,
+            server_folder,
+            server_uid,
+            error: self.error.clone(),
+            file_bytes: self.get_filebytes(context).await,
+            rfc724_mid: self.rfc724_mid.clone(),
+        })
+    }
+
     /// Returns true if message is Auto-Submitted.
     pub fn is_bot(&self) -> bool {
         self.param.get_bool(Param::Bot).unwrap_or_default()
@@ -533,6 +614,41 @@ pub fn get_ephemeral_timestamp(&self) -> i64 {
         self.ephemeral_timestamp
     }
 
+    /// Sets an ephemeral timer for this message only, independent of the chat's timer.
+    ///
+    /// The recipient deletes the message `seconds` after reading it.
+    /// Passing 0 removes the override, so the chat's timer is used.
+    pub fn set_ephemeral_timer(&mut self, seconds: u32) {
+        if seconds == 0 {
+            self.param.remove(Param::EphemeralTimer);
+        } else {
+            self.param.set_int(Param::EphemeralTimer, seconds as i32);
+        }
+    }
+
+    /// Sets the timestamp of an outgoing message, eg. to the original time of a bridged message,
+    /// so that the message is sorted into the correct historical position.
+    ///
+    /// By default, the current time is used.
+    /// Timestamps too far in the future are limited to the current time
+    /// plus the tolerance also used for incoming messages.
+    pub fn set_sort_timestamp(&mut self, timestamp: i64) {
+        self.param.set_i64(Param::SortTimestamp, timestamp);
+    }
+
+    /// Returns the timestamp set by [`Message::set_sort_timestamp`], if any.
+    pub(crate) fn get_sort_timestamp_override(&self) -> Option<i64> {
+        self.param.get_i64(Param::SortTimestamp)
+    }
+
+    /// Returns the ephemeral timer set by [`Message::set_ephemeral_timer`], if any.
+    pub(crate) fn get_ephemeral_timer_override(&self) -> Option<EphemeralTimer> {
+        self.param
+            .get_int(Param::EphemeralTimer)
+            .filter(|seconds| *seconds > 0)
+            .map(|seconds| EphemeralTimer::from_u32(seconds as u32))
+    }
+
     /// Returns message summary for display in the search results.
     pub async fn get_summary(&self, context: &Context, chat: Option<&Chat>) -> Result<Summary> {
         let chat_loaded: Chat;
@@ -595,6 +711,32 @@ pub fn is_forwarded(&self) -> bool {
         0 != self.param.get_int(Param::Forwarded).unwrap_or_default()
     }
 
+    /// Marks the message text as formatted using Markdown.
+    ///
+    /// Delta Chat receivers get a `Chat-Markdown` header and may render the text,
+    /// other MUAs just show the raw text.
+    pub fn set_markdown(&mut self, markdown: bool) {
+        if markdown {
+            self.param.set_int(Param::Markdown, 1);
+        } else {
+            self.param.remove(Param::Markdown);
+        }
+    }
+
+    /// Returns true if the message text is formatted using Markdown.
+    pub fn is_markdown(&self) -> bool {
+        self.param.get_bool(Param::Markdown).unwrap_or_default()
+    }
+
+    /// Returns true if the sender claimed a date too far in the future.
+    ///
+    /// The timestamps of such messages are clamped to the time of reception.
+    pub fn had_future_timestamp(&self) -> bool {
+        self.param
+            .get_bool(Param::HadFutureTimestamp)
+            .unwrap_or_default()
+    }
+
     pub fn is_info(&self) -> bool {
         let cmd = self.param.get_cmd();
         self.from_id == ContactId::INFO
@@ -606,11 +748,21 @@ pub fn get_info_type(&self) -> SystemMessage {
         self.param.get_cmd()
     }
 
+    /// Returns true if the message was generated by Delta Chat,
+    /// e.g. a group change, a securejoin handshake or a location streaming notification.
+    ///
+    /// Unlike [`Message::is_info`], this does not include locally added info messages.
     pub fn is_system_message(&self) -> bool {
         let cmd = self.param.get_cmd();
         cmd != SystemMessage::Unknown
     }
 
+    /// Returns the kind of system message,
+    /// or [`SystemMessage::Unknown`] if the message is no system message.
+    pub fn get_system_message_type(&self) -> SystemMessage {
+        self.param.get_cmd()
+    }
+
     /// Whether the message is still being created.
     ///
     /// Messages with attachments might be created before the
@@ -737,6 +889,29 @@ pub fn set_file(&mut self, file: impl AsRef<str>, filemime: Option<&str>) {
         }
     }
 
+    /// Writes `data` to the blob directory and attaches it as the file of the message.
+    ///
+    /// `name` is used to derive the name of the file in the blob directory.
+    /// If `dedup` is set, an existing file with the same name and content is reused,
+    /// see [`BlobObject::create_deduplicated`].
+    /// Otherwise, a distinct file is created,
+    /// eg. for drafts whose attachment may be changed in place later.
+    pub async fn set_file_from_bytes(
+        &mut self,
+        context: &Context,
+        name: &str,
+        data: &[u8],
+        dedup: bool,
+    ) -> Result<()> {
+        let blob = if dedup {
+            BlobObject::create_deduplicated(context, name, data).await?
+        } else {
+            BlobObject::create(context, name, data).await?
+        };
+        self.param.set(Param::File, blob.as_name());
+        Ok(())
+    }
+
     /// Set different sender name for a message.
     /// This overrides the name set by the `set_config()`-option `displayname`.
     pub fn set_override_sender_name(&mut self, name: Option<String>) {
@@ -819,6 +994,11 @@ pub async fn set_quote(&mut self, context: &Context, quote: Option<&Message>) ->
         Ok(())
     }
 
+    /// Returns true if the message quotes another message.
+    pub fn has_quote(&self) -> bool {
+        self.param.exists(Param::Quote)
+    }
+
     pub fn quoted_text(&self) -> Option<String> {
         self.param.get(Param::Quote).map(|s| s.to_string())
     }
@@ -845,6 +1025,38 @@ pub async fn parent(&self, context: &Context) -> Result<Option<Message>> {
         Ok(None)
     }
 
+    /// Returns the id of the locally stored message this message replies to.
+    ///
+    /// The `In-Reply-To` header is tried first,
+    /// then the `References` header from the latest to the earliest reference.
+    /// Returns `None` if none of the referenced messages is stored or all of them are trashed.
+    pub async fn parent_id(&self, context: &Context) -> Result<Option<MsgId>> {
+        if let Some(parent) = self.parent(context).await? {
+            return Ok(Some(parent.id));
+        }
+
+        let references: String = context
+            .sql
+            .query_get_value(
+                "SELECT mime_references FROM msgs WHERE id=?",
+                paramsv![self.id],
+            )
+            .await?
+            .unwrap_or_default();
+        for rfc724_mid in parse_message_ids(&references).iter().rev() {
+            if let Some(msg_id) = rfc724_mid_exists(context, rfc724_mid).await? {
+                if !Message::load_from_db(context, msg_id)
+                    .await?
+                    .chat_id
+                    .is_trash()
+                {
+                    return Ok(Some(msg_id));
+                }
+            }
+        }
+        Ok(None)
+    }
+
     /// Force the message to be sent in plain text.
     pub fn force_plaintext(&mut self) {
         self.param.set_int(Param::ForcePlaintext, 1);
@@ -887,6 +1099,39 @@ pub(crate) async fn update_subject(&self, context: &Context) {
     pub fn error(&self) -> Option<String> {
         self.error.clone()
     }
+
+    /// Returns the contacts mentioned in the message using `@name` or `@addr`.
+    ///
+    /// Mentions are resolved against the chat members when the message is sent or received.
+    pub async fn get_mentions(&self, context: &Context) -> Result<Vec<ContactId>> {
+        mention::get_mentions(context, self.id).await
+    }
+
+    /// Returns the neighbouring media message in the same chat.
+    ///
+    /// Only messages of one of the given `types` are considered, other messages are skipped;
+    /// at most three types can be given.
+    /// If `types` is empty, the view type of this message is used.
+    /// Returns `None` if there is no further media message in the given direction.
+    /// This is the same as [`chat::get_next_media`].
+    pub async fn get_next_media(
+        &self,
+        context: &Context,
+        direction: chat::Direction,
+        types: &[Viewtype],
+    ) -> Result<Option<MsgId>> {
+        ensure!(types.len() <= 3, "At most three view types can be given");
+        let mut types = types.iter().copied();
+        chat::get_next_media(
+            context,
+            self.id,
+            direction,
+            types.next().unwrap_or_default(),
+            types.next().unwrap_or_default(),
+            types.next().unwrap_or_default(),
+        )
+        .await
+    }
 }
 
 #[derive(
@@ -1130,6 +1375,49 @@ pub async fn get_msg_info(context: &Context, msg_id: MsgId) -> Result<String> {
     Ok(ret)
 }
 
+/// Structured information about a message,
+/// returned by [`Message::get_info_struct`].
+///
+/// Unlike [`get_msg_info`], this is meant for UIs building their own layout.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct MsgInfo {
+    /// Sender of the message.
+    pub from_id: ContactId,
+
+    /// Timestamp of sending, as returned by [`Message::get_timestamp`].
+    pub timestamp_sent: i64,
+
+    /// Timestamp of receiving, `None` for outgoing messages.
+    pub timestamp_rcvd: Option<i64>,
+
+    /// State of the message.
+    pub state: MessageState,
+
+    /// Whether the message was end-to-end encrypted and correctly signed.
+    pub encrypted: bool,
+
+    /// Fingerprint of the key currently known for the sender of an encrypted incoming message.
+    ///
+    /// The fingerprint of the signing key itself is not stored with the message.
+    pub sender_fingerprint: Option<String>,
+
+    /// IMAP folder the message is stored in, if known.
+    pub server_folder: Option<String>,
+
+    /// IMAP UID of the message in `server_folder`.
+    pub server_uid: Option<u32>,
+
+    /// Error that occurred while sending or receiving the message.
+    pub error: Option<String>,
+
+    /// Size of the attached file in bytes, 0 if there is no file.
+    pub file_bytes: u64,
+
+    /// Message-ID header of the message.
+    pub rfc724_mid: String,
+}
+
 pub fn guess_msgtype_from_suffix(path: &Path) -> Option<(Viewtype, &str)> {
     let extension: &str = &path.extension()?.to_str()?.to_lowercase();
     let info = match extension {
@@ -1361,7 +1649,7 @@ pub async fn markseen_msgs(context: &Context, msg_ids: Vec<MsgId>) -> Result<()>
             if curr_param.get_bool(Param::WantsMdn).unwrap_or_default()
                 && curr_param.get_cmd() == SystemMessage::Unknown
             {
-                let mdns_enabled = context.get_config_bool(Config::MdnsEnabled).await?;
+                let mdns_enabled = context.should_send_mdns().await?;
                 if mdns_enabled {
                     context
                         .sql
@@ -1837,6 +2125,8 @@ mod tests {
     use crate::chat::{marknoticed_chat, ChatItem};
     use crate::chatlist::Chatlist;
     use crate::dc_receive_imf::dc_receive_imf;
+    use crate::key::{DcKey, SignedPublicKey};
+    use crate::location;
     use crate::test_utils as test;
     use crate::test_utils::TestContext;
 
@@ -1879,6 +2169,82 @@ async fn test_prepare_message_and_send() {
         assert_eq!(_msg2.get_filemime(), None);
     }
 
+    #[async_std::test]
+    async fn test_get_system_message_type() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+        let bob = TestContext::new_bob().await;
+        let chat = alice.create_chat(&bob).await;
+
+        let sent = alice.send_text(chat.id, "hi").await;
+        let msg = bob.recv_msg(&sent).await;
+        assert!(!msg.is_system_message());
+        assert_eq!(msg.get_system_message_type(), SystemMessage::Unknown);
+
+        location::send_locations_to_chat(&alice, chat.id, 1000).await?;
+        let sent = alice.pop_sent_msg().await;
+        let msg = Message::load_from_db(&alice, sent.sender_msg_id).await?;
+        assert!(msg.is_system_message());
+        assert_eq!(
+            msg.get_system_message_type(),
+            SystemMessage::LocationStreamingEnabled
+        );
+
+        let msg = bob.recv_msg(&sent).await;
+        assert!(msg.is_system_message());
+        assert_eq!(
+            msg.get_system_message_type(),
+            SystemMessage::LocationStreamingEnabled
+        );
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn test_markdown() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+        let bob = TestContext::new_bob().await;
+        let chat = alice.create_chat(&bob).await;
+
+        let mut msg = Message::new(Viewtype::Text);
+        msg.set_text(Some("**bold**".to_string()));
+        msg.set_markdown(true);
+        let sent = alice.send_msg(chat.id, &mut msg).await;
+        assert!(sent.payload().contains("Chat-Markdown: 1"));
+        let received = bob.recv_msg(&sent).await;
+        assert!(received.is_markdown());
+        assert_eq!(received.get_text(), Some("**bold**".to_string()));
+
+        let mut msg = Message::new(Viewtype::Text);
+        msg.set_text(Some("*plain*".to_string()));
+        msg.set_markdown(true);
+        msg.set_markdown(false);
+        let sent = alice.send_msg(chat.id, &mut msg).await;
+        assert!(!sent.payload().contains("Chat-Markdown"));
+        assert!(!bob.recv_msg(&sent).await.is_markdown());
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn test_get_filebytes_filemime() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+        let bob = TestContext::new_bob().await;
+        let chat = alice.create_chat(&bob).await;
+
+        let file = alice.get_blobdir().join("notes.txt");
+        async_std::fs::write(&file, b"hello world").await?;
+        let mut msg = Message::new(Viewtype::File);
+        msg.set_file(file.to_str().unwrap(), Some("text/plain"));
+        let sent = alice.send_msg(chat.id, &mut msg).await;
+
+        let msg = Message::load_from_db(&alice, sent.sender_msg_id).await?;
+        assert_eq!(msg.get_filebytes(&alice).await, 11);
+        assert_eq!(msg.get_filemime(), Some("text/plain".to_string()));
+
+        let msg = bob.recv_msg(&sent).await;
+        assert_eq!(msg.get_filebytes(&bob).await, 11);
+        assert_eq!(msg.get_filemime(), Some("text/plain".to_string()));
+        Ok(())
+    }
+
     /// Tests that message cannot be prepared if account has no configured address.
     #[async_std::test]
     async fn test_prepare_not_configured() {
@@ -2034,6 +2400,97 @@ async fn test_quote() {
         assert!(quoted_msg.get_text() == msg2.quoted_text());
     }
 
+    #[async_std::test]
+    async fn test_quote_received() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+        let bob = TestContext::new_bob().await;
+        let alice_chat = alice.create_chat(&bob).await;
+        let sent = alice.send_text(alice_chat.id, "Where do we meet?").await;
+        let alice_question_id = sent.sender_msg_id;
+        let bob_msg = bob.recv_msg(&sent).await;
+        assert!(!bob_msg.has_quote());
+
+        let mut reply = Message::new(Viewtype::Text);
+        reply.set_text(Some("At the station.".to_string()));
+        reply.set_quote(&bob, Some(&bob_msg)).await?;
+        assert!(reply.has_quote());
+        let sent = bob.send_msg(bob_msg.chat_id, &mut reply).await;
+
+        let alice_msg = alice.recv_msg(&sent).await;
+        assert!(alice_msg.has_quote());
+        assert_eq!(
+            alice_msg.quoted_text(),
+            Some("Where do we meet?".to_string())
+        );
+        let quoted_msg = alice_msg.quoted_message(&alice).await?.unwrap();
+        assert_eq!(quoted_msg.id, alice_question_id);
+
+        reply.set_quote(&bob, None).await?;
+        assert!(!reply.has_quote());
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn test_parent_id() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+        let bob = TestContext::new_bob().await;
+        let alice_chat = alice.create_chat(&bob).await;
+        let sent = alice.send_text(alice_chat.id, "Where do we meet?").await;
+        let alice_question_id = sent.sender_msg_id;
+        let bob_msg = bob.recv_msg(&sent).await;
+        assert_eq!(bob_msg.parent_id(&bob).await?, None);
+
+        let sent = bob.send_text(bob_msg.chat_id, "At the station.").await;
+        let alice_msg = alice.recv_msg(&sent).await;
+        assert_eq!(alice_msg.parent_id(&alice).await?, Some(alice_question_id));
+
+        // The parent is found via `References` if `In-Reply-To` is unknown.
+        let question_mid = Message::load_from_db(&alice, alice_question_id)
+            .await?
+            .rfc724_mid;
+        dc_receive_imf(
+            &alice,
+            format!(
+                "From: Bob <bob@example.net>\n\
+                 To: alice@example.org\n\
+                 Chat-Version: 1.0\n\
+                 Message-ID: <reply2@example.net>\n\
+                 In-Reply-To: <unknown@example.net>\n\
+                 References: <{}> <unknown@example.net>\n\
+                 Date: Fri, 29 Jan 2021 21:37:55 +0000\n\
+                 \n\
+                 Or at the harbour?\n",
+                question_mid
+            )
+            .as_bytes(),
+            false,
+        )
+        .await?;
+        let alice_msg = alice.get_last_msg().await;
+        assert_eq!(alice_msg.get_text(), Some("Or at the harbour?".to_string()));
+        assert_eq!(alice_msg.parent_id(&alice).await?, Some(alice_question_id));
+
+        // Unknown parents are not resolved.
+        dc_receive_imf(
+            &alice,
+            b"From: Bob <bob@example.net>\n\
+              To: alice@example.org\n\
+              Chat-Version: 1.0\n\
+              Message-ID: <reply3@example.net>\n\
+              In-Reply-To: <unknown@example.net>\n\
+              References: <unknown@example.net>\n\
+              Date: Fri, 29 Jan 2021 21:38:55 +0000\n\
+              \n\
+              Never mind.\n",
+            false,
+        )
+        .await?;
+        let alice_msg = alice.get_last_msg().await;
+        assert_eq!(alice_msg.get_text(), Some("Never mind.".to_string()));
+        assert_eq!(alice_msg.parent_id(&alice).await?, None);
+        Ok(())
+    }
+
     #[async_std::test]
     async fn test_get_chat_id() {
         // Alice receives a message that pops up as a contact request
@@ -2179,6 +2636,86 @@ async fn test_markseen_msgs() -> Result<()> {
         Ok(())
     }
 
+    #[async_std::test]
+    async fn test_request_and_send_receipts() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+        let bob = TestContext::new_bob().await;
+        let alice_chat_id = alice.create_chat(&bob).await.id;
+
+        // Requesting receipts can be disabled separately.
+        alice.set_config(Config::RequestReceipts, Some("0")).await?;
+        let sent = alice.send_text(alice_chat_id, "no receipt please").await;
+        assert!(!sent.payload().contains("Chat-Disposition-Notification-To:"));
+        let msg = bob.recv_msg(&sent).await;
+        assert!(!msg.param.get_bool(Param::WantsMdn).unwrap_or_default());
+        let bob_chat_id = msg.chat_id;
+        bob_chat_id.accept(&bob).await?;
+
+        // `RequestReceipts` overrides `MdnsEnabled`.
+        alice.set_config(Config::MdnsEnabled, Some("0")).await?;
+        alice.set_config(Config::RequestReceipts, Some("1")).await?;
+        let sent = alice.send_text(alice_chat_id, "receipt please").await;
+        let msg = bob.recv_msg(&sent).await;
+        assert!(msg.param.get_bool(Param::WantsMdn).unwrap_or_default());
+
+        // Bob does not send receipts although `MdnsEnabled` is set.
+        bob.set_config(Config::SendReceipts, Some("0")).await?;
+        assert!(bob.get_config_bool(Config::MdnsEnabled).await?);
+        markseen_msgs(&bob, vec![msg.id]).await?;
+        assert_eq!(
+            bob.sql
+                .count("SELECT COUNT(*) FROM smtp_mdns", paramsv![])
+                .await?,
+            0
+        );
+
+        // Bob sends receipts although `MdnsEnabled` is unset.
+        bob.set_config(Config::MdnsEnabled, Some("0")).await?;
+        bob.set_config(Config::SendReceipts, Some("1")).await?;
+        let sent = alice.send_text(alice_chat_id, "another one").await;
+        let msg = bob.recv_msg(&sent).await;
+        markseen_msgs(&bob, vec![msg.id]).await?;
+        assert_eq!(
+            bob.sql
+                .count("SELECT COUNT(*) FROM smtp_mdns", paramsv![])
+                .await?,
+            1
+        );
+
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn test_set_file_from_bytes() -> Result<()> {
+        let t = TestContext::new().await;
+
+        let mut msg1 = Message::new(Viewtype::File);
+        msg1.set_file_from_bytes(&t, "report.txt", b"content", true)
+            .await?;
+        let mut msg2 = Message::new(Viewtype::File);
+        msg2.set_file_from_bytes(&t, "report.txt", b"content", true)
+            .await?;
+        let path1 = msg1.get_file(&t).unwrap();
+        assert_eq!(msg2.get_file(&t).unwrap(), path1);
+        assert_eq!(dc_read_file(&t, &path1).await?, b"content");
+
+        // Different content is not deduplicated.
+        let mut msg3 = Message::new(Viewtype::File);
+        msg3.set_file_from_bytes(&t, "report.txt", b"other content", true)
+            .await?;
+        assert_ne!(msg3.get_file(&t).unwrap(), path1);
+
+        // Without deduplication, a distinct file is created.
+        let mut msg4 = Message::new(Viewtype::File);
+        msg4.set_file_from_bytes(&t, "report.txt", b"content", false)
+            .await?;
+        let path4 = msg4.get_file(&t).unwrap();
+        assert_ne!(path4, path1);
+        assert_eq!(dc_read_file(&t, &path4).await?, b"content");
+
+        Ok(())
+    }
+
     #[async_std::test]
     async fn test_get_state() -> Result<()> {
         let alice = TestContext::new_alice().await;
@@ -2220,6 +2757,8 @@ async fn assert_state(t: &Context, msg_id: MsgId, state: MessageState) {
 
         set_msg_failed(&alice, alice_msg.id, Some("badly failed")).await;
         assert_state(&alice, alice_msg.id, MessageState::OutFailed).await;
+        let failed_msg = Message::load_from_db(&alice, alice_msg.id).await?;
+        assert_eq!(failed_msg.error(), Some("badly failed".to_string()));
 
         // check incoming message states on receiver side
         let bob_msg = bob.recv_msg(&payload).await;
@@ -2301,4 +2840,130 @@ fn test_viewtype_values() {
         );
         assert_eq!(Viewtype::Webxdc, Viewtype::from_i32(80).unwrap());
     }
+
+    #[async_std::test]
+    async fn test_get_info_struct() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+        let bob = TestContext::new_bob().await;
+
+        // Alice learns Bob's key, so her reply is encrypted.
+        let bob_chat_id = bob.create_chat(&alice).await.id;
+        let sent = bob.send_text(bob_chat_id, "hi").await;
+        let alice_chat_id = alice.recv_msg(&sent).await.chat_id;
+        let sent = alice.send_text(alice_chat_id, "hello").await;
+        let alice_msg = Message::load_from_db(&alice, sent.sender_msg_id).await?;
+        let info = alice_msg.get_info_struct(&alice).await?;
+        assert_eq!(info.from_id, ContactId::SELF);
+        assert!(info.encrypted);
+        assert_eq!(info.timestamp_rcvd, None);
+        assert_eq!(info.sender_fingerprint, None);
+
+        let msg = bob.recv_msg(&sent).await;
+        bob.sql
+            .execute(
+                "INSERT INTO imap (rfc724_mid, folder, uid, target) VALUES (?,'INBOX',42,'INBOX');",
+                paramsv![msg.rfc724_mid],
+            )
+            .await?;
+        let info = msg.get_info_struct(&bob).await?;
+        assert_eq!(info.from_id, msg.from_id);
+        assert_eq!(info.state, MessageState::InFresh);
+        assert!(info.encrypted);
+        assert_eq!(
+            info.sender_fingerprint,
+            Some(
+                SignedPublicKey::load_self(&alice)
+                    .await?
+                    .fingerprint()
+                    .hex()
+            )
+        );
+        assert!(info.timestamp_rcvd.is_some());
+        assert_eq!(info.server_folder.as_deref(), Some("INBOX"));
+        assert_eq!(info.server_uid, Some(42));
+        assert_eq!(info.error, None);
+        assert_eq!(info.file_bytes, 0);
+        assert_eq!(info.rfc724_mid, alice_msg.rfc724_mid);
+
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn test_get_next_media() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+        let bob = TestContext::new_bob().await;
+        let chat_id = alice.create_chat(&bob).await.id;
+
+        let mut ids = Vec::new();
+        for i in 0..3 {
+            let mut msg = Message::new(Viewtype::Image);
+            msg.set_file_from_bytes(
+                &alice,
+                "avatar.png",
+                include_bytes!("../test-data/image/avatar64x64.png"),
+                false,
+            )
+            .await?;
+            ids.push(alice.send_msg(chat_id, &mut msg).await.sender_msg_id);
+            alice.send_text(chat_id, &format!("text {}", i)).await;
+        }
+
+        let first = Message::load_from_db(&alice, ids[0]).await?;
+        let second = Message::load_from_db(&alice, ids[1]).await?;
+        let last = Message::load_from_db(&alice, ids[2]).await?;
+        assert_eq!(
+            first
+                .get_next_media(&alice, chat::Direction::Forward, &[])
+                .await?,
+            Some(ids[1])
+        );
+        assert_eq!(
+            second
+                .get_next_media(&alice, chat::Direction::Forward, &[Viewtype::Image])
+                .await?,
+            Some(ids[2])
+        );
+        assert_eq!(
+            second
+                .get_next_media(&alice, chat::Direction::Backward, &[])
+                .await?,
+            Some(ids[0])
+        );
+
+        // boundaries
+        assert_eq!(
+            first
+                .get_next_media(&alice, chat::Direction::Backward, &[])
+                .await?,
+            None
+        );
+        assert_eq!(
+            last.get_next_media(&alice, chat::Direction::Forward, &[])
+                .await?,
+            None
+        );
+
+        // only videos are requested, images are skipped as well
+        assert_eq!(
+            first
+                .get_next_media(&alice, chat::Direction::Forward, &[Viewtype::Video])
+                .await?,
+            None
+        );
+        assert!(first
+            .get_next_media(
+                &alice,
+                chat::Direction::Forward,
+                &[
+                    Viewtype::Image,
+                    Viewtype::Gif,
+                    Viewtype::Video,
+                    Viewtype::Audio
+                ]
+            )
+            .await
+            .is_err());
+
+        Ok(())
+    }
 }
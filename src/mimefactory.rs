@@ -10,7 +10,7 @@
 use crate::chat::Chat;
 use crate::config::Config;
 use crate::constants::{Chattype, DC_FROM_HANDSHAKE};
-use crate::contact::Contact;
+use crate::contact::{addr_cmp, Contact};
 use crate::context::{get_version_str, Context};
 use crate::dc_tools::IsNoneOrEmpty;
 use crate::dc_tools::{
@@ -182,7 +182,7 @@ pub async fn from_msg(
                 )
                 .await?;
 
-            if !msg.is_system_message() && context.get_config_bool(Config::MdnsEnabled).await? {
+            if !msg.is_system_message() && context.should_request_mdns().await? {
                 req_mdn = true;
             }
         }
@@ -325,9 +325,10 @@ fn should_force_plaintext(&self) -> bool {
             Loaded::Message { chat } => {
                 if chat.is_protected() {
                     false
-                } else if chat.typ == Chattype::Broadcast {
+                } else if chat.typ == Chattype::Broadcast && self.recipients.len() > 1 {
                     // encryption may disclose recipients;
-                    // this is probably a worse issue than not opportunistically (!) encrypting
+                    // this is probably a worse issue than not opportunistically (!) encrypting.
+                    // usually, broadcasts are sent to each recipient separately, see create_send_msg_job()
                     true
                 } else {
                     self.msg
@@ -354,9 +355,14 @@ fn should_skip_autocrypt(&self) -> bool {
     async fn should_do_gossip(&self, context: &Context) -> Result<bool> {
         match &self.loaded {
             Loaded::Message { chat } => {
-                // beside key- and member-changes, force re-gossip every 48 hours
+                // beside key- and member-changes, force re-gossip every `GossipPeriodDays`
+                let gossip_period =
+                    i64::from(context.get_config_int(Config::GossipPeriodDays).await?)
+                        * 24
+                        * 60
+                        * 60;
                 let gossiped_timestamp = chat.id.get_gossiped_timestamp(context).await?;
-                if time() > gossiped_timestamp + (2 * 24 * 60 * 60) {
+                if time() >= gossiped_timestamp + gossip_period {
                     Ok(true)
                 } else {
                     Ok(self.msg.param.get_cmd() == SystemMessage::MemberAddedToGroup)
@@ -437,6 +443,19 @@ async fn subject_str(&self, context: &Context) -> anyhow::Result<String> {
         Ok(subject)
     }
 
+    /// Restricts the recipients to `addr`, eg. to resend a message to a single member.
+    pub(crate) fn retain_recipient(&mut self, addr: &str) {
+        self.recipients.retain(|(_, a)| addr_cmp(a, addr));
+    }
+
+    /// Returns true if the message is sent to a broadcast list.
+    pub(crate) fn is_broadcast(&self) -> bool {
+        match &self.loaded {
+            Loaded::Message { chat } => chat.typ == Chattype::Broadcast,
+            Loaded::Mdn { .. } => false,
+        }
+    }
+
     pub fn recipients(&self) -> Vec<String> {
         self.recipients
             .iter()
@@ -452,10 +471,7 @@ pub async fn render(mut self, context: &Context) -> Result<RenderedEmail> {
             self.from_addr.clone(),
         );
 
-        let undisclosed_recipients = match &self.loaded {
-            Loaded::Message { chat } => chat.typ == Chattype::Broadcast,
-            Loaded::Mdn { .. } => false,
-        };
+        let undisclosed_recipients = self.is_broadcast();
 
         let mut to = Vec::new();
         if undisclosed_recipients {
@@ -616,6 +632,12 @@ pub async fn render(mut self, context: &Context) -> Result<RenderedEmail> {
                 duration.to_string(),
             ));
         }
+        if let Some(timer) = self.msg.get_ephemeral_timer_override() {
+            headers.protected.push(Header::new(
+                "Chat-Message-Ephemeral-Timer".to_string(),
+                timer.to_u32().to_string(),
+            ));
+        }
 
         // MIME header <https://datatracker.ietf.org/doc/html/rfc2045>.
         // Content-Type
@@ -880,6 +902,13 @@ async fn render_message(
                             email_to_add.into(),
                         ));
                     }
+                    let emails_to_add = self.msg.param.get(Param::Arg3).unwrap_or_default();
+                    if !emails_to_add.is_empty() {
+                        headers.protected.push(Header::new(
+                            "Chat-Group-Members-Added".into(),
+                            emails_to_add.into(),
+                        ));
+                    }
                     if 0 != self.msg.param.get_int(Param::Arg2).unwrap_or_default()
                         & DC_FROM_HANDSHAKE
                     {
@@ -930,6 +959,16 @@ async fn render_message(
                     "ephemeral-timer-changed".to_string(),
                 ));
             }
+            SystemMessage::AutocryptAnnouncement => {
+                headers.protected.push(Header::new(
+                    "Chat-Content".to_string(),
+                    "autocrypt-announcement".to_string(),
+                ));
+                headers.unprotected.push(Header::new(
+                    "Auto-Submitted".to_string(),
+                    "auto-generated".to_string(),
+                ));
+            }
             SystemMessage::LocationOnly
             | SystemMessage::MultiDeviceSync
             | SystemMessage::WebxdcStatusUpdate => {
@@ -1040,6 +1079,12 @@ async fn render_message(
             ));
         }
 
+        if self.msg.is_markdown() {
+            headers
+                .protected
+                .push(Header::new("Chat-Markdown".into(), "1".into()));
+        }
+
         if self.msg.viewtype == Viewtype::Voice
             || self.msg.viewtype == Viewtype::Audio
             || self.msg.viewtype == Viewtype::Video
@@ -1550,6 +1595,60 @@ fn test_maybe_encode_words() {
         assert_eq!(maybe_encode_words("äöü"), "=?utf-8?b?w6TDtsO8?=");
     }
 
+    #[async_std::test]
+    async fn test_gossip_period() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+        let bob = TestContext::new_bob().await;
+        let fiona = TestContext::new_fiona().await;
+        let alice_chat_id = create_group_chat(&alice, ProtectionStatus::Unprotected, "foo").await?;
+        for member in [&bob, &fiona] {
+            // Let Alice learn the keys of the members.
+            let chat_id = member.create_chat(&alice).await.id;
+            alice.recv_msg(&member.send_text(chat_id, "hi").await).await;
+            let contact_id = alice.add_or_lookup_contact(member).await.id;
+            add_contact_to_chat(&alice, alice_chat_id, contact_id).await?;
+        }
+
+        // Pretends that keys were gossiped `days` ago and renders a new message.
+        let sends_gossip = |days: i64| {
+            let alice = &alice;
+            async move {
+                alice_chat_id
+                    .set_gossiped_timestamp(alice, time() - days * 24 * 60 * 60)
+                    .await?;
+                let mut msg = Message::new(Viewtype::Text);
+                msg.set_text(Some("hello".to_string()));
+                let msg_id = chat::prepare_msg(alice, alice_chat_id, &mut msg).await?;
+                let msg = Message::load_from_db(alice, msg_id).await?;
+                let rendered = MimeFactory::from_msg(alice, &msg, false)
+                    .await?
+                    .render(alice)
+                    .await?;
+                Ok::<_, anyhow::Error>(rendered.is_gossiped)
+            }
+        };
+
+        // The first message adds the members and gossips.
+        alice.send_text(alice_chat_id, "first").await;
+        assert!(alice_chat_id.get_gossiped_timestamp(&alice).await? > 0);
+
+        assert!(!sends_gossip(0).await?);
+        assert!(!sends_gossip(1).await?);
+        assert!(sends_gossip(3).await?);
+
+        alice
+            .set_config(Config::GossipPeriodDays, Some("5"))
+            .await?;
+        assert!(!sends_gossip(3).await?);
+        assert!(sends_gossip(6).await?);
+
+        alice
+            .set_config(Config::GossipPeriodDays, Some("0"))
+            .await?;
+        assert!(sends_gossip(0).await?);
+        Ok(())
+    }
+
     #[async_std::test]
     async fn test_subject_from_mua() {
         // 1.: Receive a mail from an MUA
@@ -145,6 +145,10 @@ pub enum SystemMessage {
 
     // Webxdc info added with `info` set in `send_webxdc_status_update()`.
     WebxdcInfoMessage = 32,
+
+    /// Hidden message without content,
+    /// only sent to advertise the current Autocrypt header.
+    AutocryptAnnouncement = 40,
 }
 
 impl Default for SystemMessage {
@@ -380,6 +384,8 @@ fn parse_system_message_headers(&mut self, context: &Context) {
                 self.is_system_message = SystemMessage::ChatProtectionEnabled;
             } else if value == "protection-disabled" {
                 self.is_system_message = SystemMessage::ChatProtectionDisabled;
+            } else if value == "autocrypt-announcement" {
+                self.is_system_message = SystemMessage::AutocryptAnnouncement;
             }
         } else if self.get_header(HeaderDef::ChatGroupMemberRemoved).is_some() {
             self.is_system_message = SystemMessage::MemberRemovedFromGroup;
@@ -533,6 +539,12 @@ async fn parse_headers(&mut self, context: &Context) -> Result<()> {
             }
         }
 
+        if self.get_header(HeaderDef::ChatMarkdown).map(String::as_str) == Some("1") {
+            for part in self.parts.iter_mut() {
+                part.param.set_int(Param::Markdown, 1);
+            }
+        }
+
         self.parse_attachments();
 
         // See if an MDN is requested from the other side
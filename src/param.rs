@@ -180,6 +180,29 @@ pub enum Param {
 
     /// For Webxdc Message Instances: timestamp of summary update.
     WebxdcSummaryTimestamp = b'Q',
+
+    /// For Messages: set to 1 if the `Date` header was too far in the future
+    /// and the receive time was used instead.
+    HadFutureTimestamp = b'x',
+
+    /// For Messages: set to 1 if the text is formatted using Markdown.
+    Markdown = b'y',
+
+    /// For Contacts: latest progress of a secure-join handshake with the contact,
+    /// 0=error, 1-999=progress, 1000=success.
+    SecurejoinProgress = b'z',
+
+    /// For Messages: ephemeral timer in seconds for this message only,
+    /// overriding the timer of the chat.
+    EphemeralTimer = b'v',
+
+    /// For Messages: timestamp to use instead of the current time
+    /// when the message is created, eg. for messages bridged from other platforms.
+    SortTimestamp = b'X',
+
+    /// For Contacts: expected and actual fingerprint of the last verification
+    /// that failed because of a fingerprint mismatch, separated by a space.
+    FingerprintMismatch = b'Y',
 }
 
 /// An object for handling key=value parameter lists.
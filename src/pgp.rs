@@ -266,8 +266,6 @@ pub async fn pk_decrypt(
     private_keys_for_decryption: Keyring<SignedSecretKey>,
     public_keys_for_validation: &Keyring<SignedPublicKey>,
 ) -> Result<(Vec<u8>, HashSet<Fingerprint>)> {
-    let mut ret_signature_fingerprints: HashSet<Fingerprint> = Default::default();
-
     let msgs = async_std::task::spawn_blocking(move || {
         let cursor = Cursor::new(ctext);
         let (msg, _) = Message::from_armor_single(cursor)?;
@@ -279,6 +277,45 @@ pub async fn pk_decrypt(
     })
     .await?;
 
+    get_content_and_signatures(msgs, public_keys_for_validation)
+}
+
+/// Decrypts a message encrypted with a passphrase (SKESK packet).
+///
+/// This is used eg. when the user enters a code to decrypt a message
+/// that was not encrypted to any of the own keys.
+///
+/// Returns decrypted message and fingerprints
+/// of all keys from the `public_keys_for_validation` keyring that
+/// have valid signatures there.
+#[allow(clippy::implicit_hasher)]
+pub async fn decrypt_with_passphrase(
+    ctext: Vec<u8>,
+    passphrase: &str,
+    public_keys_for_validation: &Keyring<SignedPublicKey>,
+) -> Result<(Vec<u8>, HashSet<Fingerprint>)> {
+    let passphrase = passphrase.to_string();
+    let msgs = async_std::task::spawn_blocking(move || {
+        let cursor = Cursor::new(ctext);
+        let (msg, _) = Message::from_armor_single(cursor)?;
+
+        let decryptor = msg.decrypt_with_password(|| passphrase)?;
+        decryptor.collect::<pgp::errors::Result<Vec<_>>>()
+    })
+    .await?;
+
+    get_content_and_signatures(msgs, public_keys_for_validation)
+}
+
+/// Returns the content of the first decrypted message
+/// and the fingerprints of the keys from `public_keys_for_validation`
+/// that have valid signatures on it.
+fn get_content_and_signatures(
+    msgs: Vec<Message>,
+    public_keys_for_validation: &Keyring<SignedPublicKey>,
+) -> Result<(Vec<u8>, HashSet<Fingerprint>)> {
+    let mut ret_signature_fingerprints: HashSet<Fingerprint> = Default::default();
+
     if let Some(msg) = msgs.into_iter().next() {
         // get_content() will decompress the message if needed,
         // but this avoids decompressing it again to check signatures
@@ -566,4 +603,23 @@ async fn test_decrypt_unsigned() {
         assert_eq!(plain, CLEARTEXT);
         assert_eq!(valid_signatures.len(), 0);
     }
+
+    #[async_std::test]
+    async fn test_decrypt_with_passphrase() {
+        let ctext = symm_encrypt("1234-5678", CLEARTEXT).await.unwrap();
+        let mut sig_check_keyring = Keyring::new();
+        sig_check_keyring.add(KEYS.alice_public.clone());
+        let (plain, valid_signatures) =
+            decrypt_with_passphrase(ctext.as_bytes().to_vec(), "1234-5678", &sig_check_keyring)
+                .await
+                .unwrap();
+        assert_eq!(plain, CLEARTEXT);
+        assert_eq!(valid_signatures.len(), 0);
+
+        assert!(
+            decrypt_with_passphrase(ctext.as_bytes().to_vec(), "wrong", &sig_check_keyring)
+                .await
+                .is_err()
+        );
+    }
 }
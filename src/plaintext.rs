@@ -3,6 +3,10 @@
 use crate::simplify::split_lines;
 use once_cell::sync::Lazy;
 
+/// Matches links in plain text.
+pub(crate) static LINKIFY_URL_RE: Lazy<regex::Regex> =
+    Lazy::new(|| regex::Regex::new(r#"\b((http|https|ftp|ftps):[\w.,:;$/@!?&%\-~=#+]+)"#).unwrap());
+
 #[derive(Debug)]
 pub struct PlainText {
     pub text: String,
@@ -25,10 +29,6 @@ pub async fn to_html(&self) -> String {
         static LINKIFY_MAIL_RE: Lazy<regex::Regex> =
             Lazy::new(|| regex::Regex::new(r#"\b([\w.\-+]+@[\w.\-]+)\b"#).unwrap());
 
-        static LINKIFY_URL_RE: Lazy<regex::Regex> = Lazy::new(|| {
-            regex::Regex::new(r#"\b((http|https|ftp|ftps):[\w.,:;$/@!?&%\-~=#+]+)"#).unwrap()
-        });
-
         let lines = split_lines(&self.text);
 
         let mut ret =
@@ -320,10 +320,17 @@ fn decode_account(qr: &str) -> Result<Qr> {
 }
 
 /// scheme: `DCWEBRTC:https://meet.jit.si/$ROOM`
+///
+/// The instance must contain the `$ROOM` placeholder.
 fn decode_webrtc_instance(_context: &Context, qr: &str) -> Result<Qr> {
     let payload = qr
         .get(DCWEBRTC_SCHEME.len()..)
         .context("invalid DCWEBRTC payload")?;
+    ensure!(
+        payload.contains("$ROOM"),
+        "WebRTC instance without $ROOM placeholder: {:?}",
+        payload
+    );
 
     let (_type, url) = Message::parse_webrtc_instance(payload);
     let url =
@@ -999,15 +1006,27 @@ async fn test_decode_webrtc_instance() -> Result<()> {
         );
 
         // Test it again with mixcased "dcWebRTC:" uri scheme
-        let qr = check_qr(&ctx.ctx, "dcWebRTC:https://example.org/").await?;
+        let qr = check_qr(&ctx.ctx, "dcWebRTC:https://example.org/$ROOM").await?;
         assert_eq!(
             qr,
             Qr::WebrtcInstance {
                 domain: "example.org".to_string(),
-                instance_pattern: "https://example.org/".to_string()
+                instance_pattern: "https://example.org/$ROOM".to_string()
             }
         );
 
+        // Invalid URLs, non-HTTP schemes and instances without `$ROOM` are rejected.
+        assert!(check_qr(&ctx.ctx, "DCWEBRTC:").await.is_err());
+        assert!(check_qr(&ctx.ctx, "DCWEBRTC:jitsi:not a url $ROOM")
+            .await
+            .is_err());
+        assert!(check_qr(&ctx.ctx, "DCWEBRTC:ftp://example.org/$ROOM")
+            .await
+            .is_err());
+        assert!(check_qr(&ctx.ctx, "DCWEBRTC:https://example.org/")
+            .await
+            .is_err());
+
         Ok(())
     }
 
@@ -1045,10 +1064,14 @@ async fn test_set_config_from_qr() -> Result<()> {
         assert!(ctx.ctx.get_config(Config::WebrtcInstance).await?.is_none());
 
         let res = set_config_from_qr(&ctx.ctx, "dcwebrtc:https://example.org/").await;
+        assert!(res.is_err());
+        assert!(ctx.ctx.get_config(Config::WebrtcInstance).await?.is_none());
+
+        let res = set_config_from_qr(&ctx.ctx, "dcwebrtc:https://example.org/$ROOM").await;
         assert!(res.is_ok());
         assert_eq!(
             ctx.ctx.get_config(Config::WebrtcInstance).await?.unwrap(),
-            "https://example.org/"
+            "https://example.org/$ROOM"
         );
 
         let res =
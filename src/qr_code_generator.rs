@@ -11,6 +11,10 @@
     securejoin, stock_str,
 };
 
+/// Renders the Secure-Join QR code returned by [`securejoin::dc_get_securejoin_qr`] as SVG image.
+///
+/// The image contains the avatar of the group or of the user
+/// and the translated description of the QR code.
 pub async fn get_securejoin_qr_svg(context: &Context, chat_id: Option<ChatId>) -> Result<String> {
     if let Some(chat_id) = chat_id {
         generate_join_group_qr_code(context, chat_id).await
@@ -265,6 +269,9 @@ fn inner_generate_secure_join_qr_code(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::chat::{create_group_chat, ProtectionStatus};
+    use crate::stock_str::StockMessage;
+    use crate::test_utils::TestContext;
 
     #[async_std::test]
     async fn test_svg_escaping() {
@@ -278,4 +285,23 @@ async fn test_svg_escaping() {
         .unwrap();
         assert!(svg.contains("descr123 &quot; &lt; &gt; &amp;"))
     }
+
+    #[async_std::test]
+    async fn test_securejoin_qr_svg_description() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        t.set_stock_translation(
+            StockMessage::SecureJoinGroupQRDescription,
+            "Gruppe %1$s beitreten".to_string(),
+        )
+        .await?;
+        let chat_id = create_group_chat(&t, ProtectionStatus::Unprotected, "Foo").await?;
+
+        let svg = get_securejoin_qr_svg(&t, Some(chat_id)).await?;
+        assert!(svg.contains("<svg"));
+        assert!(svg.contains("Gruppe Foo beitreten"));
+
+        let svg = get_securejoin_qr_svg(&t, None).await?;
+        assert!(svg.contains("alice@example.org"));
+        Ok(())
+    }
 }
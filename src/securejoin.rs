@@ -1,14 +1,15 @@
 //! Verified contact protocol implementation as [specified by countermitm project](https://countermitm.readthedocs.io/en/stable/new.html#setup-contact-protocol).
 
 use std::convert::TryFrom;
+use std::time::Duration;
 
-use anyhow::{bail, Context as _, Error, Result};
+use anyhow::{bail, ensure, Context as _, Error, Result};
 use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
 
 use crate::aheader::EncryptPreference;
 use crate::chat::{self, Chat, ChatId, ChatIdBlocked};
 use crate::config::Config;
-use crate::constants::Blocked;
+use crate::constants::{Blocked, Chattype};
 use crate::contact::{Contact, ContactId, Origin, VerifiedStatus};
 use crate::context::Context;
 use crate::dc_tools::time;
@@ -16,6 +17,7 @@
 use crate::events::EventType;
 use crate::headerdef::HeaderDef;
 use crate::key::{DcKey, Fingerprint, SignedPublicKey};
+use crate::log::LogExt;
 use crate::message::{Message, Viewtype};
 use crate::mimeparser::{MimeMessage, SystemMessage};
 use crate::param::Param;
@@ -40,6 +42,9 @@ macro_rules! inviter_progress {
             $progress >= 0 && $progress <= 1000,
             "value in range 0..1000 expected with: 0=error, 1..999=progress, 1000=success"
         );
+        set_securejoin_progress($context, $contact_id, $progress)
+            .await
+            .ok_or_log($context);
         $context.emit_event($crate::events::EventType::SecurejoinInviterProgress {
             contact_id: $contact_id,
             progress: $progress,
@@ -47,11 +52,84 @@ macro_rules! inviter_progress {
     };
 }
 
+/// Remembers the latest progress of a handshake with `contact_id`
+/// so that it can be queried by [`Context::get_securejoin_progress`].
+async fn set_securejoin_progress(
+    context: &Context,
+    contact_id: ContactId,
+    progress: usize,
+) -> Result<()> {
+    let mut contact = Contact::load_from_db(context, contact_id).await?;
+    contact
+        .param
+        .set_int(Param::SecurejoinProgress, progress as i32);
+    contact.update_param(context).await
+}
+
+impl Context {
+    /// Returns the latest progress of a secure-join handshake with the contact.
+    ///
+    /// The values are the same as in [`EventType::SecurejoinInviterProgress`]
+    /// and [`EventType::SecurejoinJoinerProgress`], so UIs can restore the progress
+    /// after missing events, e.g. because of a restart.
+    /// Returns `None` if there was no handshake with the contact.
+    pub async fn get_securejoin_progress(&self, contact_id: ContactId) -> Result<Option<u16>> {
+        let contact = Contact::load_from_db(self, contact_id).await?;
+        Ok(contact
+            .param
+            .get_int(Param::SecurejoinProgress)
+            .map(|progress| progress as u16))
+    }
+
+    /// Returns the fingerprints of the last failed verification of the contact
+    /// as a pair of the expected fingerprint, eg. from a scanned QR code,
+    /// and the actual fingerprint of the key used by the contact.
+    ///
+    /// UIs can show both fingerprints for manual comparison.
+    /// Returns `None` if no verification of the contact failed because of a fingerprint mismatch.
+    pub async fn get_fingerprint_mismatch(
+        &self,
+        contact_id: ContactId,
+    ) -> Result<Option<(Fingerprint, Fingerprint)>> {
+        let contact = Contact::load_from_db(self, contact_id).await?;
+        let (expected, actual) = match contact
+            .param
+            .get(Param::FingerprintMismatch)
+            .and_then(|value| value.split_once(' '))
+        {
+            Some(pair) => pair,
+            None => return Ok(None),
+        };
+        Ok(Some((expected.parse()?, actual.parse()?)))
+    }
+}
+
 /// Generates a Secure Join QR code.
 ///
 /// With `group` set to `None` this generates a setup-contact QR code, with `group` set to a
 /// [`ChatId`] generates a join-group QR code for the given chat.
 pub async fn dc_get_securejoin_qr(context: &Context, group: Option<ChatId>) -> Result<String> {
+    get_securejoin_qr(context, group, None).await
+}
+
+/// Like [`dc_get_securejoin_qr`], but the QR code expires at the unix timestamp `expires`.
+///
+/// The QR code uses its own tokens, so other QR codes are not affected by the expiry.
+/// Handshakes started with the QR code after the expiry are denied.
+pub async fn dc_get_securejoin_qr_expiring(
+    context: &Context,
+    group: Option<ChatId>,
+    expires: i64,
+) -> Result<String> {
+    ensure!(expires > 0, "Invalid expiry {}", expires);
+    get_securejoin_qr(context, group, Some(expires)).await
+}
+
+async fn get_securejoin_qr(
+    context: &Context,
+    group: Option<ChatId>,
+    expires: Option<i64>,
+) -> Result<String> {
     /*=======================================================
     ====             Alice - the inviter side            ====
     ====   Step 1 in "Setup verified contact" protocol   ====
@@ -61,11 +139,30 @@ pub async fn dc_get_securejoin_qr(context: &Context, group: Option<ChatId>) -> R
 
     // invitenumber will be used to allow starting the handshake,
     // auth will be used to verify the fingerprint
-    let sync_token = token::lookup(context, Namespace::InviteNumber, group)
-        .await?
-        .is_none();
-    let invitenumber = token::lookup_or_new(context, Namespace::InviteNumber, group).await;
-    let auth = token::lookup_or_new(context, Namespace::Auth, group).await;
+    let (invitenumber, auth, sync_token) = if let Some(expires) = expires {
+        // expiring tokens are not synced as other devices do not know about the expiry
+        (
+            token::new_expiring(context, Namespace::InviteNumber, group, expires).await?,
+            token::new_expiring(context, Namespace::Auth, group, expires).await?,
+            false,
+        )
+    } else {
+        let old_invitenumber = token::lookup(context, Namespace::InviteNumber, group).await?;
+        let ttl = token::qr_token_ttl(context).await?;
+        let (invitenumber, auth) = if ttl.as_secs() > 0 {
+            (
+                token::lookup_or_new_with_ttl(context, Namespace::InviteNumber, group, ttl).await,
+                token::lookup_or_new_with_ttl(context, Namespace::Auth, group, ttl).await,
+            )
+        } else {
+            (
+                token::lookup_or_new(context, Namespace::InviteNumber, group).await,
+                token::lookup_or_new(context, Namespace::Auth, group).await,
+            )
+        };
+        let sync_token = old_invitenumber.as_ref() != Some(&invitenumber);
+        (invitenumber, auth, sync_token)
+    };
     let self_addr = context.get_primary_self_addr().await?;
     let self_name = context
         .get_config(Config::Displayname)
@@ -121,12 +218,42 @@ pub async fn dc_get_securejoin_qr(context: &Context, group: Option<ChatId>) -> R
             &auth,
         )
     };
+    let qr = match expires {
+        Some(expires) => format!("{}&e={}", qr, expires),
+        None => qr,
+    };
 
     info!(context, "Generated QR code: {}", qr);
 
     Ok(qr)
 }
 
+/// Generates an onboarding QR code for the given welcome group.
+///
+/// Scanning the QR code sets up a verified contact with the inviter
+/// and, once the contact is verified, adds the joiner to `welcome_group`.
+/// Both is done by the join-group handshake, so this is the join-group QR code
+/// as returned by [`dc_get_securejoin_qr`] after checking that `welcome_group` is suitable.
+pub async fn dc_get_onboarding_qr(context: &Context, welcome_group: ChatId) -> Result<String> {
+    ensure!(
+        !welcome_group.is_special(),
+        "Invalid welcome group {}",
+        welcome_group
+    );
+    let chat = Chat::load_from_db(context, welcome_group).await?;
+    ensure!(
+        chat.typ == Chattype::Group,
+        "Welcome group {} is not a group",
+        welcome_group
+    );
+    ensure!(
+        chat::is_contact_in_chat(context, welcome_group, ContactId::SELF).await?,
+        "Not a member of welcome group {}",
+        welcome_group
+    );
+    dc_get_securejoin_qr(context, Some(welcome_group)).await
+}
+
 async fn get_self_fingerprint(context: &Context) -> Option<Fingerprint> {
     match SignedPublicKey::load_self(context).await {
         Ok(key) => Some(key.fingerprint()),
@@ -152,6 +279,81 @@ pub async fn dc_join_securejoin(context: &Context, qr: &str) -> Result<ChatId> {
     })
 }
 
+/// Error returned by [`dc_join_securejoin_blocking`] if the handshake did not finish.
+#[derive(Debug, thiserror::Error)]
+pub enum JoinError {
+    /// The inviter did not finish the handshake in time.
+    #[error("Secure-Join timed out")]
+    Timeout,
+    /// The handshake was aborted, eg. because the inviter rejected the request
+    /// or a new QR code was scanned.
+    #[error("Secure-Join was aborted")]
+    Aborted,
+}
+
+/// Reason passed with [`EventType::SecurejoinFailed`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum SecurejoinFailure {
+    /// The `Secure-Join-Fingerprint` header is missing.
+    FingerprintNotProvided = 1,
+    /// The handshake message is not encrypted and signed correctly.
+    NotEncrypted = 2,
+    /// The fingerprint does not match the key of the sender.
+    FingerprintMismatch = 3,
+    /// The `Secure-Join-Auth` header is missing.
+    AuthNotProvided = 4,
+    /// The `Secure-Join-Auth` token is unknown, eg. because the QR code was withdrawn.
+    AuthInvalid = 5,
+    /// The tokens of the QR code are expired, see [`Config::QrTokenTtl`].
+    TokenExpired = 6,
+}
+
+/// Like [`dc_join_securejoin`], but waits until the handshake is finished.
+///
+/// Returns the [`ChatId`] of the joined chat once `vc-contact-confirm` or `vg-member-added`
+/// is received. If the handshake is aborted or does not finish within `timeout`,
+/// the returned error contains a [`JoinError`]; on timeout, the handshake is aborted.
+///
+/// The handshake messages are still received by the IO scheduler,
+/// so IO must be running for the handshake to finish.
+pub async fn dc_join_securejoin_blocking(
+    context: &Context,
+    qr: &str,
+    timeout: Duration,
+) -> Result<ChatId> {
+    let invite = QrInvite::try_from(check_qr(context, qr).await?)?;
+    let contact_id = invite.contact_id();
+
+    let (sender, receiver) = async_std::channel::unbounded();
+    context.joiner_progress_senders.lock().await.push(sender);
+    let chat_id = dc_join_securejoin(context, qr).await?;
+    // Progress of handshakes aborted by starting this one is not of interest.
+    while receiver.try_recv().is_ok() {}
+
+    let wait_finished = async {
+        while let Ok((progress_contact_id, progress)) = receiver.recv().await {
+            if progress_contact_id != contact_id {
+                continue;
+            }
+            match progress {
+                0 => return Err(JoinError::Aborted),
+                1000 => return Ok(()),
+                _ => {}
+            }
+        }
+        Err(JoinError::Aborted)
+    };
+    match async_std::future::timeout(timeout, wait_finished).await {
+        Ok(res) => res?,
+        Err(_) => {
+            bob::abort_protocol(context, chat_id).await?;
+            return Err(Error::new(JoinError::Timeout));
+        }
+    }
+    Ok(chat_id)
+}
+
 async fn securejoin(context: &Context, qr: &str) -> Result<ChatId> {
     /*========================================================
     ====             Bob - the joiner's side             =====
@@ -166,6 +368,19 @@ async fn securejoin(context: &Context, qr: &str) -> Result<ChatId> {
     bob::start_protocol(context, invite).await
 }
 
+/// Aborts a secure-join started by [`dc_join_securejoin`].
+///
+/// `chat_id` is the [`ChatId`] returned by [`dc_join_securejoin`].
+/// Later handshake messages from the inviter are ignored,
+/// a [`EventType::SecurejoinJoinerProgress`] event with progress 0 is emitted.
+/// No info message is added to the chat.
+///
+/// It is safe to call this while another QR code is scanned,
+/// only the handshake joining `chat_id` is aborted.
+pub async fn dc_abort_securejoin(context: &Context, chat_id: ChatId) -> Result<()> {
+    bob::abort_protocol(context, chat_id).await
+}
+
 /// Send handshake message from Alice's device;
 /// Bob's handshake messages are sent in `BobState::send_handshake_message()`.
 async fn send_alice_handshake_msg(
@@ -236,6 +451,27 @@ async fn fingerprint_equals_sender(
     Ok(false)
 }
 
+/// Remembers that the key of `contact_id` does not match the `expected` fingerprint,
+/// so that it can be queried by [`Context::get_fingerprint_mismatch`].
+async fn set_fingerprint_mismatch(
+    context: &Context,
+    contact_id: ContactId,
+    expected: &Fingerprint,
+) -> Result<()> {
+    let mut contact = Contact::load_from_db(context, contact_id).await?;
+    let actual = Peerstate::from_addr(context, contact.get_addr())
+        .await?
+        .and_then(|peerstate| peerstate.public_key_fingerprint);
+    if let Some(actual) = actual {
+        contact.param.set(
+            Param::FingerprintMismatch,
+            format!("{} {}", expected.hex(), actual.hex()),
+        );
+        contact.update_param(context).await?;
+    }
+    Ok(())
+}
+
 /// What to do with a Secure-Join handshake message after it was handled.
 ///
 /// This status is returned to [`dc_receive_imf`] which will use it to decide what to do
@@ -314,6 +550,14 @@ pub(crate) async fn handle_securejoin_handshake(
                 warn!(context, "Secure-join denied (bad invitenumber).");
                 return Ok(HandshakeMessage::Ignore);
             }
+            if token::is_expired(context, token::Namespace::InviteNumber, invitenumber).await? {
+                warn!(context, "Secure-join denied (QR code expired).");
+                context.emit_event(EventType::SecurejoinFailed {
+                    contact_id,
+                    reason: SecurejoinFailure::TokenExpired,
+                });
+                return Ok(HandshakeMessage::Ignore);
+            }
             info!(context, "Secure-join requested.",);
 
             inviter_progress!(context, contact_id, 300);
@@ -359,6 +603,7 @@ pub(crate) async fn handle_securejoin_handshake(
                             contact_id,
                             info_chat_id(context, contact_id).await?,
                             "Fingerprint not provided.",
+                            SecurejoinFailure::FingerprintNotProvided,
                         )
                         .await?;
                         return Ok(HandshakeMessage::Ignore);
@@ -370,16 +615,19 @@ pub(crate) async fn handle_securejoin_handshake(
                     contact_id,
                     info_chat_id(context, contact_id).await?,
                     "Auth not encrypted.",
+                    SecurejoinFailure::NotEncrypted,
                 )
                 .await?;
                 return Ok(HandshakeMessage::Ignore);
             }
             if !fingerprint_equals_sender(context, &fingerprint, contact_id).await? {
+                set_fingerprint_mismatch(context, contact_id, &fingerprint).await?;
                 could_not_establish_secure_connection(
                     context,
                     contact_id,
                     info_chat_id(context, contact_id).await?,
                     "Fingerprint mismatch on inviter-side.",
+                    SecurejoinFailure::FingerprintMismatch,
                 )
                 .await?;
                 return Ok(HandshakeMessage::Ignore);
@@ -394,6 +642,7 @@ pub(crate) async fn handle_securejoin_handshake(
                         contact_id,
                         info_chat_id(context, contact_id).await?,
                         "Auth not provided.",
+                        SecurejoinFailure::AuthNotProvided,
                     )
                     .await?;
                     return Ok(HandshakeMessage::Ignore);
@@ -405,6 +654,18 @@ pub(crate) async fn handle_securejoin_handshake(
                     contact_id,
                     info_chat_id(context, contact_id).await?,
                     "Auth invalid.",
+                    SecurejoinFailure::AuthInvalid,
+                )
+                .await?;
+                return Ok(HandshakeMessage::Ignore);
+            }
+            if token::is_expired(context, token::Namespace::Auth, auth_0).await? {
+                could_not_establish_secure_connection(
+                    context,
+                    contact_id,
+                    info_chat_id(context, contact_id).await?,
+                    "Auth expired.",
+                    SecurejoinFailure::TokenExpired,
                 )
                 .await?;
                 return Ok(HandshakeMessage::Ignore);
@@ -415,6 +676,7 @@ pub(crate) async fn handle_securejoin_handshake(
                     contact_id,
                     info_chat_id(context, contact_id).await?,
                     "Fingerprint mismatch on inviter-side.",
+                    SecurejoinFailure::FingerprintMismatch,
                 )
                 .await?;
                 return Ok(HandshakeMessage::Ignore);
@@ -566,6 +828,7 @@ pub(crate) async fn observe_securejoin_on_other_device(
                     contact_id,
                     info_chat_id(context, contact_id).await?,
                     "Message not encrypted correctly.",
+                    SecurejoinFailure::NotEncrypted,
                 )
                 .await?;
                 return Ok(HandshakeMessage::Ignore);
@@ -579,6 +842,7 @@ pub(crate) async fn observe_securejoin_on_other_device(
                         contact_id,
                         info_chat_id(context, contact_id).await?,
                         "Fingerprint not provided, please update Delta Chat on all your devices.",
+                        SecurejoinFailure::FingerprintNotProvided,
                     )
                     .await?;
                         return Ok(HandshakeMessage::Ignore);
@@ -590,6 +854,7 @@ pub(crate) async fn observe_securejoin_on_other_device(
                     contact_id,
                     info_chat_id(context, contact_id).await?,
                     format!("Fingerprint mismatch on observing {}.", step).as_ref(),
+                    SecurejoinFailure::FingerprintMismatch,
                 )
                 .await?;
                 return Ok(HandshakeMessage::Ignore);
@@ -621,6 +886,7 @@ async fn could_not_establish_secure_connection(
     contact_id: ContactId,
     chat_id: ChatId,
     details: &str,
+    reason: SecurejoinFailure,
 ) -> Result<(), Error> {
     let contact = Contact::get_by_id(context, contact_id).await?;
     let msg = stock_str::contact_not_verified(context, &contact).await;
@@ -629,6 +895,7 @@ async fn could_not_establish_secure_connection(
         context,
         "StockMessage::ContactNotVerified posted to 1:1 chat ({})", details
     );
+    context.emit_event(EventType::SecurejoinFailed { contact_id, reason });
     Ok(())
 }
 
@@ -691,7 +958,7 @@ mod tests {
     use crate::chat;
     use crate::chat::ProtectionStatus;
     use crate::chatlist::Chatlist;
-    use crate::constants::{Chattype, DC_GCM_ADDDAYMARKER};
+    use crate::constants::DC_GCM_ADDDAYMARKER;
     use crate::dc_receive_imf::dc_receive_imf;
     use crate::peerstate::Peerstate;
     use crate::test_utils::{TestContext, TestContextManager};
@@ -903,6 +1170,323 @@ async fn test_setup_contact() {
         );
     }
 
+    #[async_std::test]
+    async fn test_get_securejoin_progress() -> Result<()> {
+        let mut tcm = TestContextManager::new().await;
+        let alice = tcm.alice().await;
+        let bob = tcm.bob().await;
+        let alice_bob_id = Contact::create(&alice, "", "bob@example.net").await?;
+        assert_eq!(alice.get_securejoin_progress(alice_bob_id).await?, None);
+
+        let qr = dc_get_securejoin_qr(&alice, None).await?;
+        dc_join_securejoin(&bob, &qr).await?;
+        let bob_alice_id = Contact::lookup_id_by_addr(&bob, "alice@example.org", Origin::Unknown)
+            .await?
+            .unwrap();
+        assert_eq!(bob.get_securejoin_progress(bob_alice_id).await?, None);
+
+        // vc-request
+        alice.recv_msg(&bob.pop_sent_msg().await).await;
+        assert_eq!(
+            alice.get_securejoin_progress(alice_bob_id).await?,
+            Some(300)
+        );
+
+        // vc-auth-required
+        bob.recv_msg(&alice.pop_sent_msg().await).await;
+        assert_eq!(bob.get_securejoin_progress(bob_alice_id).await?, Some(400));
+
+        // vc-request-with-auth
+        alice.recv_msg(&bob.pop_sent_msg().await).await;
+        assert_eq!(
+            alice.get_securejoin_progress(alice_bob_id).await?,
+            Some(1000)
+        );
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn test_get_fingerprint_mismatch() -> Result<()> {
+        let mut tcm = TestContextManager::new().await;
+        let alice = tcm.alice().await;
+        let bob = tcm.bob().await;
+
+        // Bob scans a QR code containing a fingerprint that is not Alice's.
+        let alice_fp = SignedPublicKey::load_self(&alice).await?.fingerprint();
+        let wrong_fp = crate::test_utils::fiona_keypair().public.fingerprint();
+        let qr = dc_get_securejoin_qr(&alice, None)
+            .await?
+            .replace(&alice_fp.hex(), &wrong_fp.hex());
+        dc_join_securejoin(&bob, &qr).await?;
+        let bob_alice_id = Contact::lookup_id_by_addr(&bob, "alice@example.org", Origin::Unknown)
+            .await?
+            .unwrap();
+        assert_eq!(bob.get_fingerprint_mismatch(bob_alice_id).await?, None);
+
+        // vc-request
+        alice.recv_msg(&bob.pop_sent_msg().await).await;
+
+        // vc-auth-required, signed by Alice's actual key
+        bob.recv_msg(&alice.pop_sent_msg().await).await;
+        assert_eq!(
+            bob.get_fingerprint_mismatch(bob_alice_id).await?,
+            Some((wrong_fp, alice_fp))
+        );
+        assert_eq!(
+            Contact::get_by_id(&bob, bob_alice_id)
+                .await?
+                .is_verified(&bob)
+                .await?,
+            VerifiedStatus::Unverified
+        );
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn test_join_securejoin_blocking() -> Result<()> {
+        let mut tcm = TestContextManager::new().await;
+        let alice = tcm.alice().await;
+        let bob = tcm.bob().await;
+
+        let qr = dc_get_securejoin_qr(&alice, None).await?;
+        let join = dc_join_securejoin_blocking(&bob, &qr, Duration::from_secs(10));
+        let handshake = async {
+            // vc-request
+            alice.recv_msg(&bob.pop_sent_msg().await).await;
+            // vc-auth-required
+            bob.recv_msg(&alice.pop_sent_msg().await).await;
+            // vc-request-with-auth
+            alice.recv_msg(&bob.pop_sent_msg().await).await;
+            // vc-contact-confirm
+            bob.recv_msg(&alice.pop_sent_msg().await).await;
+        };
+        let (chat_id, ()) = futures::join!(join, handshake);
+
+        let bob_alice_id = Contact::lookup_id_by_addr(&bob, "alice@example.org", Origin::Unknown)
+            .await?
+            .unwrap();
+        assert_eq!(
+            chat_id?,
+            ChatId::lookup_by_contact(&bob, bob_alice_id)
+                .await?
+                .unwrap()
+        );
+        assert_eq!(bob.get_securejoin_progress(bob_alice_id).await?, Some(1000));
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn test_join_securejoin_blocking_timeout() -> Result<()> {
+        let mut tcm = TestContextManager::new().await;
+        let alice = tcm.alice().await;
+        let bob = tcm.bob().await;
+
+        // Alice never answers.
+        let qr = dc_get_securejoin_qr(&alice, None).await?;
+        let err = dc_join_securejoin_blocking(&bob, &qr, Duration::from_millis(300))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<JoinError>(),
+            Some(JoinError::Timeout)
+        ));
+
+        // The handshake is aborted, a late answer of Alice is ignored.
+        let bob_alice_id = bob.add_or_lookup_contact(&alice).await.id;
+        assert_eq!(bob.get_securejoin_progress(bob_alice_id).await?, Some(0));
+        assert!(BobState::from_db(&bob.sql).await?.is_none());
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn test_join_securejoin_blocking_aborted() -> Result<()> {
+        let mut tcm = TestContextManager::new().await;
+        let alice = tcm.alice().await;
+        let bob = tcm.bob().await;
+
+        // The QR code contains a fingerprint that is not Alice's,
+        // so Bob aborts the handshake when receiving vc-auth-required.
+        let alice_fp = SignedPublicKey::load_self(&alice).await?.fingerprint();
+        let wrong_fp = crate::test_utils::fiona_keypair().public.fingerprint();
+        let qr = dc_get_securejoin_qr(&alice, None)
+            .await?
+            .replace(&alice_fp.hex(), &wrong_fp.hex());
+        let join = dc_join_securejoin_blocking(&bob, &qr, Duration::from_secs(10));
+        let handshake = async {
+            alice.recv_msg(&bob.pop_sent_msg().await).await;
+            bob.recv_msg(&alice.pop_sent_msg().await).await;
+        };
+        let (res, ()) = futures::join!(join, handshake);
+        assert!(matches!(
+            res.unwrap_err().downcast_ref::<JoinError>(),
+            Some(JoinError::Aborted)
+        ));
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn test_setup_contact_auth_invalid() -> Result<()> {
+        let mut tcm = TestContextManager::new().await;
+        let alice = tcm.alice().await;
+        let bob = tcm.bob().await;
+
+        let qr = dc_get_securejoin_qr(&alice, None).await?;
+        dc_join_securejoin(&bob, &qr).await?;
+        alice.recv_msg(&bob.pop_sent_msg().await).await;
+        bob.recv_msg(&alice.pop_sent_msg().await).await;
+
+        // Alice withdraws the QR code before receiving vc-request-with-auth.
+        let sent = bob.pop_sent_msg().await;
+        let msg = alice.parse_msg(&sent).await;
+        let auth = msg.get_header(HeaderDef::SecureJoinAuth).unwrap();
+        token::delete(&alice, Namespace::Auth, auth).await?;
+        alice.recv_msg(&sent).await;
+
+        let contact_bob_id = Contact::lookup_id_by_addr(&alice, "bob@example.net", Origin::Unknown)
+            .await?
+            .expect("Contact not found");
+        let event = alice
+            .evtracker
+            .get_matching(|evt| matches!(evt, EventType::SecurejoinFailed { .. }))
+            .await;
+        assert_eq!(
+            event,
+            EventType::SecurejoinFailed {
+                contact_id: contact_bob_id,
+                reason: SecurejoinFailure::AuthInvalid
+            }
+        );
+        assert_eq!(
+            Contact::get_by_id(&alice, contact_bob_id)
+                .await?
+                .is_verified(&alice)
+                .await?,
+            VerifiedStatus::Unverified
+        );
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn test_setup_contact_qr_expired() -> Result<()> {
+        let mut tcm = TestContextManager::new().await;
+        let alice = tcm.alice().await;
+        let bob = tcm.bob().await;
+
+        let expires = time() - 10;
+        let qr = dc_get_securejoin_qr_expiring(&alice, None, expires).await?;
+        assert!(qr.ends_with(&format!("&e={}", expires)));
+        dc_join_securejoin(&bob, &qr).await?;
+        let sent = bob.pop_sent_msg().await;
+        assert_eq!(
+            alice
+                .parse_msg(&sent)
+                .await
+                .get_header(HeaderDef::SecureJoin)
+                .unwrap(),
+            "vc-request"
+        );
+        alice.recv_msg(&sent).await;
+
+        // Alice denies the request and does not send vc-auth-required.
+        assert_eq!(
+            alice
+                .sql
+                .count("SELECT COUNT(*) FROM smtp", paramsv![])
+                .await?,
+            0
+        );
+        let contact_bob_id = Contact::lookup_id_by_addr(&alice, "bob@example.net", Origin::Unknown)
+            .await?
+            .expect("Contact not found");
+        assert_eq!(alice.get_securejoin_progress(contact_bob_id).await?, None);
+
+        // QR codes without expiry are not affected.
+        let qr = dc_get_securejoin_qr(&alice, None).await?;
+        assert!(!qr.contains("&e="));
+        dc_join_securejoin(&bob, &qr).await?;
+        alice.recv_msg(&bob.pop_sent_msg().await).await;
+        assert_eq!(
+            alice.get_securejoin_progress(contact_bob_id).await?,
+            Some(300)
+        );
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn test_expired_tokens_deleted_in_housekeeping() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+        let count_tokens = || async {
+            alice
+                .sql
+                .count("SELECT COUNT(*) FROM tokens", paramsv![])
+                .await
+                .unwrap()
+        };
+
+        dc_get_securejoin_qr(&alice, None).await?;
+        dc_get_securejoin_qr_expiring(&alice, None, time() - 10).await?;
+        dc_get_securejoin_qr_expiring(&alice, None, time() + 3600).await?;
+        assert_eq!(count_tokens().await, 6);
+
+        crate::sql::housekeeping(&alice).await?;
+        assert_eq!(count_tokens().await, 4);
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn test_qr_token_ttl() -> Result<()> {
+        let mut tcm = TestContextManager::new().await;
+        let alice = tcm.alice().await;
+        let bob = tcm.bob().await;
+        alice.set_config(Config::QrTokenTtl, Some("3600")).await?;
+
+        // Tokens are reused while they are not expired.
+        let qr = dc_get_securejoin_qr(&alice, None).await?;
+        assert_eq!(dc_get_securejoin_qr(&alice, None).await?, qr);
+
+        alice
+            .sql
+            .execute("UPDATE tokens SET timestamp=timestamp-7200", paramsv![])
+            .await?;
+        assert_ne!(dc_get_securejoin_qr(&alice, None).await?, qr);
+
+        // The expired QR code is denied with a dedicated reason.
+        dc_join_securejoin(&bob, &qr).await?;
+        alice.recv_msg(&bob.pop_sent_msg().await).await;
+        let contact_bob_id = Contact::lookup_id_by_addr(&alice, "bob@example.net", Origin::Unknown)
+            .await?
+            .expect("Contact not found");
+        let event = alice
+            .evtracker
+            .get_matching(|evt| matches!(evt, EventType::SecurejoinFailed { .. }))
+            .await;
+        assert_eq!(
+            event,
+            EventType::SecurejoinFailed {
+                contact_id: contact_bob_id,
+                reason: SecurejoinFailure::TokenExpired
+            }
+        );
+        assert_eq!(alice.get_securejoin_progress(contact_bob_id).await?, None);
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn test_qr_token_ttl_large() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+        alice
+            .set_config(Config::QrTokenTtl, Some(&u64::MAX.to_string()))
+            .await?;
+
+        let qr = dc_get_securejoin_qr(&alice, None).await?;
+        assert_eq!(dc_get_securejoin_qr(&alice, None).await?, qr);
+        let auth = token::lookup(&alice, Namespace::Auth, None)
+            .await?
+            .expect("no auth token");
+        assert!(!token::is_expired(&alice, Namespace::Auth, &auth).await?);
+        Ok(())
+    }
+
     #[async_std::test]
     async fn test_setup_contact_bad_qr() {
         let bob = TestContext::new_bob().await;
@@ -1066,6 +1650,68 @@ async fn test_setup_contact_concurrent_calls() -> Result<()> {
         Ok(())
     }
 
+    #[async_std::test]
+    async fn test_abort_securejoin() -> Result<()> {
+        let mut tcm = TestContextManager::new().await;
+        let alice = tcm.alice().await;
+        let bob = tcm.bob().await;
+
+        let qr = dc_get_securejoin_qr(&alice, None).await?;
+        let chat_id = dc_join_securejoin(&bob, &qr).await?;
+        let sent = bob.pop_sent_msg().await;
+        assert!(BobState::from_db(&bob.sql).await?.is_some());
+
+        let msg_cnt = chat_id.get_msg_cnt(&bob).await?;
+        dc_abort_securejoin(&bob, chat_id).await?;
+        assert!(BobState::from_db(&bob.sql).await?.is_none());
+        assert_eq!(chat_id.get_msg_cnt(&bob).await?, msg_cnt);
+        let bob_alice_id = Contact::lookup_id_by_addr(&bob, "alice@example.org", Origin::Unknown)
+            .await?
+            .unwrap();
+        bob.evtracker
+            .get_matching(|evt| {
+                matches!(evt, EventType::SecurejoinJoinerProgress { progress: 0, .. })
+            })
+            .await;
+        assert_eq!(bob.get_securejoin_progress(bob_alice_id).await?, Some(0));
+
+        // Alice's answer is ignored, Bob does not continue the handshake.
+        alice.recv_msg(&sent).await;
+        let sent_cnt = bob
+            .sql
+            .count("SELECT COUNT(*) FROM smtp", paramsv![])
+            .await?;
+        bob.recv_msg_opt(&alice.pop_sent_msg().await).await;
+        assert_eq!(
+            bob.sql
+                .count("SELECT COUNT(*) FROM smtp", paramsv![])
+                .await?,
+            sent_cnt
+        );
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn test_abort_securejoin_concurrent_scan() -> Result<()> {
+        let mut tcm = TestContextManager::new().await;
+        let alice = tcm.alice().await;
+        let bob = tcm.bob().await;
+
+        let qr_stale = "OPENPGP4FPR:1234567890123456789012345678901234567890#a=claire%40foo.de&n=&i=12345678901&s=23456789012";
+        let claire_chat_id = dc_join_securejoin(&bob, qr_stale).await?;
+        let qr = dc_get_securejoin_qr(&alice, None).await?;
+        let alice_chat_id = dc_join_securejoin(&bob, &qr).await?;
+
+        // Aborting the stale scan keeps the running one.
+        dc_abort_securejoin(&bob, claire_chat_id).await?;
+        let bobstate = BobState::from_db(&bob.sql).await?.unwrap();
+        assert_eq!(bobstate.alice_chat(), alice_chat_id);
+
+        dc_abort_securejoin(&bob, alice_chat_id).await?;
+        assert!(BobState::from_db(&bob.sql).await?.is_none());
+        Ok(())
+    }
+
     #[async_std::test]
     async fn test_secure_join() -> Result<()> {
         let mut tcm = TestContextManager::new().await;
@@ -1290,6 +1936,57 @@ async fn test_secure_join() -> Result<()> {
         Ok(())
     }
 
+    #[async_std::test]
+    async fn test_onboarding_qr() -> Result<()> {
+        let mut tcm = TestContextManager::new().await;
+        let alice = tcm.alice().await;
+        let bob = tcm.bob().await;
+
+        let welcome_group =
+            chat::create_group_chat(&alice, ProtectionStatus::Unprotected, "Welcome").await?;
+        let qr = dc_get_onboarding_qr(&alice, welcome_group).await?;
+        assert_eq!(qr, dc_get_securejoin_qr(&alice, Some(welcome_group)).await?);
+
+        // Bob scans the QR code and the handshake is done.
+        let bob_chat_id = dc_join_securejoin(&bob, &qr).await?;
+        alice.recv_msg(&bob.pop_sent_msg().await).await;
+        bob.recv_msg(&alice.pop_sent_msg().await).await;
+        alice.recv_msg(&bob.pop_sent_msg().await).await;
+
+        // Alice verified Bob and added him to the welcome group.
+        let contact_bob_id = Contact::lookup_id_by_addr(&alice, "bob@example.net", Origin::Unknown)
+            .await?
+            .expect("Contact not found");
+        let contact_bob = Contact::load_from_db(&alice, contact_bob_id).await?;
+        assert_eq!(
+            contact_bob.is_verified(&alice).await?,
+            VerifiedStatus::BidirectVerified
+        );
+        assert!(chat::is_contact_in_chat(&alice, welcome_group, contact_bob_id).await?);
+
+        // Bob verified Alice and is in the welcome group.
+        bob.recv_msg(&alice.pop_sent_msg().await).await;
+        let contact_alice_id =
+            Contact::lookup_id_by_addr(&bob, "alice@example.org", Origin::Unknown)
+                .await?
+                .expect("Contact not found");
+        let contact_alice = Contact::load_from_db(&bob, contact_alice_id).await?;
+        assert_eq!(
+            contact_alice.is_verified(&bob).await?,
+            VerifiedStatus::BidirectVerified
+        );
+        let bob_chat = Chat::load_from_db(&bob, bob_chat_id).await?;
+        assert_eq!(bob_chat.typ, Chattype::Group);
+        assert_eq!(bob_chat.get_name(), "Welcome");
+        assert!(chat::is_contact_in_chat(&bob, bob_chat_id, ContactId::SELF).await?);
+
+        // 1:1 chats cannot be used as welcome group.
+        let single_chat = alice.create_chat(&bob).await;
+        assert!(dc_get_onboarding_qr(&alice, single_chat.id).await.is_err());
+
+        Ok(())
+    }
+
     #[async_std::test]
     async fn test_adhoc_group_no_qr() -> Result<()> {
         let alice = TestContext::new_alice().await;
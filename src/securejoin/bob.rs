@@ -11,12 +11,13 @@
 use crate::context::Context;
 use crate::dc_tools::time;
 use crate::events::EventType;
+use crate::log::LogExt;
 use crate::mimeparser::MimeMessage;
 use crate::{chat, stock_str};
 
 use super::bobstate::{BobHandshakeStage, BobState};
 use super::qrinvite::QrInvite;
-use super::HandshakeMessage;
+use super::{set_securejoin_progress, HandshakeMessage};
 
 /// Starts the securejoin protocol with the QR `invite`.
 ///
@@ -48,10 +49,12 @@ pub(super) async fn start_protocol(context: &Context, invite: QrInvite) -> Resul
     for state in aborted_states {
         error!(context, "Aborting previously unfinished QR Join process.");
         state.notify_aborted(context, "new QR scanned").await?;
-        state.emit_progress(context, JoinerProgress::Error);
+        state.emit_progress(context, JoinerProgress::Error).await;
     }
     if matches!(stage, BobHandshakeStage::RequestWithAuthSent) {
-        state.emit_progress(context, JoinerProgress::RequestWithAuthSent);
+        state
+            .emit_progress(context, JoinerProgress::RequestWithAuthSent)
+            .await;
     }
     match invite {
         QrInvite::Group { .. } => {
@@ -75,6 +78,36 @@ pub(super) async fn start_protocol(context: &Context, invite: QrInvite) -> Resul
     }
 }
 
+/// Aborts the running securejoin protocol joining `chat_id`.
+///
+/// `chat_id` is the [`ChatId`] returned when starting the protocol.
+/// Nothing is done if no protocol joining this chat is running.
+pub(super) async fn abort_protocol(context: &Context, chat_id: ChatId) -> Result<()> {
+    let mut bobstate = match BobState::from_db(&context.sql).await? {
+        Some(bobstate) => bobstate,
+        None => {
+            info!(context, "No secure-join to abort");
+            return Ok(());
+        }
+    };
+    let joining_chat_id = match bobstate.invite() {
+        QrInvite::Contact { .. } => Some(bobstate.alice_chat()),
+        QrInvite::Group { ref grpid, .. } => chat::get_chat_id_by_grpid(context, grpid)
+            .await?
+            .map(|(chat_id, _protected, _blocked)| chat_id),
+    };
+    if joining_chat_id != Some(chat_id) {
+        info!(context, "No secure-join for chat {} to abort", chat_id);
+        return Ok(());
+    }
+
+    bobstate.abort(&context.sql).await?;
+    // The user knows about the abort, the contact is not reported as unverified.
+    info!(context, "Secure-join for chat {} aborted by user", chat_id);
+    bobstate.emit_progress(context, JoinerProgress::Error).await;
+    Ok(())
+}
+
 /// Handles `vc-auth-required` and `vg-auth-required` handshake messages.
 ///
 /// # Bob - the joiner's side
@@ -87,6 +120,7 @@ pub(super) async fn handle_auth_required(
         Some(mut bobstate) => match bobstate.handle_message(context, message).await? {
             Some(BobHandshakeStage::Terminated(why)) => {
                 bobstate.notify_aborted(context, why).await?;
+                bobstate.emit_progress(context, JoinerProgress::Error).await;
                 Ok(HandshakeMessage::Done)
             }
             Some(_stage) => {
@@ -98,7 +132,9 @@ pub(super) async fn handle_auth_required(
                     let chat_id = bobstate.joining_chat_id(context).await?;
                     chat::add_info_msg(context, chat_id, &msg, time()).await?;
                 }
-                bobstate.emit_progress(context, JoinerProgress::RequestWithAuthSent);
+                bobstate
+                    .emit_progress(context, JoinerProgress::RequestWithAuthSent)
+                    .await;
                 Ok(HandshakeMessage::Done)
             }
             None => Ok(HandshakeMessage::Ignore),
@@ -124,6 +160,7 @@ pub(super) async fn handle_contact_confirm(
     match bobstate.handle_message(context, message).await? {
         Some(BobHandshakeStage::Terminated(why)) => {
             bobstate.notify_aborted(context, why).await?;
+            bobstate.emit_progress(context, JoinerProgress::Error).await;
             Ok(HandshakeMessage::Done)
         }
         Some(BobHandshakeStage::Completed) => {
@@ -131,6 +168,9 @@ pub(super) async fn handle_contact_confirm(
             // verify both contacts (this could be a bug/security issue, see
             // e.g. https://github.com/deltachat/deltachat-core-rust/issues/1177).
             bobstate.notify_peer_verified(context).await?;
+            bobstate
+                .emit_progress(context, JoinerProgress::Succeeded)
+                .await;
             Ok(retval)
         }
         Some(_) => {
@@ -153,12 +193,21 @@ fn is_join_group(&self) -> bool {
         }
     }
 
-    fn emit_progress(&self, context: &Context, progress: JoinerProgress) {
+    async fn emit_progress(&self, context: &Context, progress: JoinerProgress) {
         let contact_id = self.invite().contact_id();
+        let progress = progress.into();
+        set_securejoin_progress(context, contact_id, progress)
+            .await
+            .ok_or_log(context);
         context.emit_event(EventType::SecurejoinJoinerProgress {
             contact_id,
-            progress: progress.into(),
+            progress,
         });
+        context
+            .joiner_progress_senders
+            .lock()
+            .await
+            .retain(|sender| sender.try_send((contact_id, progress)).is_ok());
     }
 
     /// Returns the [`ChatId`] of the chat being joined.
@@ -239,8 +288,8 @@ enum JoinerProgress {
     ///
     /// Typically shows as "alice@addr verified, introducing myself."
     RequestWithAuthSent,
-    // /// Completed securejoin.
-    // Succeeded,
+    /// Completed securejoin.
+    Succeeded,
 }
 
 impl From<JoinerProgress> for usize {
@@ -248,7 +297,7 @@ fn from(progress: JoinerProgress) -> Self {
         match progress {
             JoinerProgress::Error => 0,
             JoinerProgress::RequestWithAuthSent => 400,
-            // JoinerProgress::Succeeded => 1000,
+            JoinerProgress::Succeeded => 1000,
         }
     }
 }
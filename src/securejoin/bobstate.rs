@@ -22,7 +22,10 @@
 use crate::sql::Sql;
 
 use super::qrinvite::QrInvite;
-use super::{encrypted_and_signed, fingerprint_equals_sender, mark_peer_as_verified};
+use super::{
+    encrypted_and_signed, fingerprint_equals_sender, mark_peer_as_verified,
+    set_fingerprint_mismatch,
+};
 
 /// The stage of the [`BobState`] securejoin handshake protocol state machine.
 ///
@@ -204,6 +207,13 @@ pub fn alice_chat(&self) -> ChatId {
         self.chat_id
     }
 
+    /// Aborts the handshake and removes this [`BobState`] from the database.
+    ///
+    /// If a new QR code was scanned meanwhile, the [`BobState`] of the new scan is kept.
+    pub async fn abort(&mut self, sql: &Sql) -> Result<()> {
+        self.update_next(sql, SecureJoinStep::Terminated).await
+    }
+
     /// Updates the [`BobState::next`] field in memory and the database.
     ///
     /// If the next state is a terminal state it will remove this [`BobState`] from the
@@ -307,6 +317,8 @@ async fn step_auth_required(
         if !fingerprint_equals_sender(context, self.invite.fingerprint(), self.invite.contact_id())
             .await?
         {
+            set_fingerprint_mismatch(context, self.invite.contact_id(), self.invite.fingerprint())
+                .await?;
             self.update_next(&context.sql, SecureJoinStep::Terminated)
                 .await?;
             return Ok(Some(BobHandshakeStage::Terminated("Fingerprint mismatch")));
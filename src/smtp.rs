@@ -10,7 +10,6 @@
 use async_smtp::{smtp, EmailAddress, ServerAddress};
 use async_std::task;
 
-use crate::config::Config;
 use crate::constants::DC_LP_AUTH_OAUTH2;
 use crate::contact::{Contact, ContactId};
 use crate::events::EventType;
@@ -611,7 +610,7 @@ async fn send_mdn_msg_id(
 
 /// Tries to send a single MDN. Returns false if there are no MDNs to send.
 async fn send_mdn(context: &Context, smtp: &mut Smtp) -> Result<bool> {
-    let mdns_enabled = context.get_config_bool(Config::MdnsEnabled).await?;
+    let mdns_enabled = context.should_send_mdns().await?;
     if !mdns_enabled {
         // User has disabled MDNs.
         context.sql.execute("DELETE FROM smtp_mdns", []).await?;
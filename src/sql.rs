@@ -19,10 +19,13 @@
 use crate::context::Context;
 use crate::dc_tools::{dc_delete_file, time};
 use crate::ephemeral::start_ephemeral_timers;
+use crate::key;
+use crate::location;
 use crate::message::{Message, Viewtype};
 use crate::param::{Param, Params};
 use crate::peerstate::{deduplicate_peerstates, Peerstate};
 use crate::stock_str;
+use crate::token;
 
 #[macro_export]
 macro_rules! paramsv {
@@ -383,6 +386,28 @@ pub async fn get_conn(
         Ok(conn)
     }
 
+    /// Releases memory held by the idle connections,
+    /// i.e. their page caches and cached prepared statements.
+    ///
+    /// Connections that are currently in use are skipped.
+    /// Does nothing if the database is not open.
+    pub(crate) async fn shrink_memory(&self) -> Result<()> {
+        let lock = self.pool.read().await;
+        let pool = match lock.as_ref() {
+            Some(pool) => pool,
+            None => return Ok(()),
+        };
+
+        // Keep the connections until all are processed, so each one is returned only once.
+        let mut conns = Vec::new();
+        while let Some(conn) = pool.try_get() {
+            conn.flush_prepared_statement_cache();
+            conn.execute_batch("PRAGMA shrink_memory;")?;
+            conns.push(conn);
+        }
+        Ok(())
+    }
+
     /// Used for executing `SELECT COUNT` statements only. Returns the resulting count.
     pub async fn count(&self, query: &str, params: impl rusqlite::Params) -> anyhow::Result<usize> {
         let count: isize = self.query_row(query, params, |row| row.get(0)).await?;
@@ -540,6 +565,31 @@ pub async fn set_raw_config(&self, key: impl AsRef<str>, value: Option<&str>) ->
         Ok(())
     }
 
+    /// Sets several configuration values in a single transaction.
+    ///
+    /// Either all values are stored or, on error, none of them.
+    pub async fn set_raw_configs(&self, changes: Vec<(String, Option<String>)>) -> Result<()> {
+        let mut lock = self.config_cache.write().await;
+        let rows = changes.clone();
+        self.transaction(move |transaction| {
+            for (key, value) in rows {
+                transaction.execute("DELETE FROM config WHERE keyname=?;", paramsv![key])?;
+                if let Some(value) = value {
+                    transaction.execute(
+                        "INSERT INTO config (keyname, value) VALUES (?, ?);",
+                        paramsv![key, value],
+                    )?;
+                }
+            }
+            Ok(())
+        })
+        .await?;
+        for (key, value) in changes {
+            lock.insert(key, value);
+        }
+        Ok(())
+    }
+
     /// Get configuration options from the database.
     pub async fn get_raw_config(&self, key: impl AsRef<str>) -> Result<Option<String>> {
         let lock = self.config_cache.read().await;
@@ -631,6 +681,24 @@ pub async fn housekeeping(context: &Context) -> Result<()> {
         warn!(context, "Failed to deduplicate peerstates: {}", err)
     }
 
+    if let Err(err) = location::repair_orphans(context).await {
+        warn!(
+            context,
+            "Housekeeping: cannot delete orphaned locations: {}", err
+        );
+    }
+
+    if let Err(err) = token::delete_expired(context).await {
+        warn!(
+            context,
+            "Housekeeping: cannot delete expired tokens: {}", err
+        );
+    }
+
+    if let Err(err) = key::rotate_keypair_if_needed(context).await {
+        warn!(context, "Housekeeping: cannot rotate keypair: {}", err);
+    }
+
     context.schedule_quota_update().await?;
 
     // Try to clear the freelist to free some space on the disk. This
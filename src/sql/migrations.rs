@@ -637,6 +637,39 @@ pub async fn run(context: &Context, sql: &Sql) -> Result<(bool, bool, bool, bool
         )
         .await?;
     }
+    if dbversion < 91 {
+        info!(context, "[migration] v91");
+        sql.execute_migration(
+            r#"CREATE TABLE mentions (
+              msg_id INTEGER NOT NULL, -- id of the message in msgs table containing the mention
+              contact_id INTEGER NOT NULL, -- id of the mentioned contact
+              PRIMARY KEY(msg_id, contact_id),
+              FOREIGN KEY(msg_id) REFERENCES msgs(id) ON DELETE CASCADE
+            );
+            CREATE INDEX mentions_index1 ON mentions (contact_id);"#,
+            91,
+        )
+        .await?;
+    }
+    if dbversion < 92 {
+        info!(context, "[migration] v92");
+        sql.execute_migration(
+            r#"CREATE TABLE blocked_fingerprints (
+              fingerprint TEXT PRIMARY KEY, -- hex fingerprint of the blocked key
+              timestamp INTEGER NOT NULL DEFAULT 0 -- time the key was blocked
+            );"#,
+            92,
+        )
+        .await?;
+    }
+    if dbversion < 93 {
+        info!(context, "[migration] v93");
+        sql.execute_migration(
+            "ALTER TABLE tokens ADD COLUMN expires INTEGER NOT NULL DEFAULT 0;",
+            93,
+        )
+        .await?;
+    }
 
     Ok((
         recalc_fingerprints,
@@ -1,9 +1,11 @@
 //! Module to work with translatable stock strings.
 
+use std::collections::HashMap;
 use std::future::Future;
 use std::pin::Pin;
 
 use anyhow::{bail, Error};
+use num_traits::FromPrimitive;
 use strum::EnumProperty;
 use strum_macros::EnumProperty;
 
@@ -332,6 +334,24 @@ pub enum StockMessage {
 
     #[strum(props(fallback = "Not connected"))]
     NotConnected = 121,
+
+    #[strum(props(fallback = "New message"))]
+    NewMessage = 122,
+
+    #[strum(props(fallback = "Message from %1$s"))]
+    MessageFrom = 123,
+
+    #[strum(props(fallback = "Location streaming extended until %1$s."))]
+    MsgLocationExtended = 124,
+
+    #[strum(props(fallback = "Members %1$s added."))]
+    MsgAddMembers = 125,
+
+    #[strum(props(fallback = "%1$s and %2$s"))]
+    ListAnd = 126,
+
+    #[strum(props(fallback = "%1$s others"))]
+    ListOthers = 127,
 }
 
 impl StockMessage {
@@ -344,24 +364,61 @@ fn fallback(self) -> &'static str {
 }
 
 async fn translated(context: &Context, id: StockMessage) -> String {
-    context
+    if let Some(stockstring) = context
         .translated_stockstrings
         .read()
         .await
         .get(&(id as usize))
+    {
+        return stockstring.to_string();
+    }
+    context
+        .fallback_stockstrings
+        .read()
+        .await
+        .get(&(id as usize))
         .map(AsRef::as_ref)
         .unwrap_or_else(|| id.fallback())
         .to_string()
 }
 
+/// Checks that a translation uses only the placeholders of the default string.
+fn check_placeholders(id: StockMessage, stockstring: &str) -> Result<(), Error> {
+    if stockstring.contains("%1") && !id.fallback().contains("%1") {
+        bail!(
+            "translation {} contains invalid %1 placeholder, default is {}",
+            stockstring,
+            id.fallback()
+        );
+    }
+    if stockstring.contains("%2") && !id.fallback().contains("%2") {
+        bail!(
+            "translation {} contains invalid %2 placeholder, default is {}",
+            stockstring,
+            id.fallback()
+        );
+    }
+    Ok(())
+}
+
 /// Helper trait only meant to be implemented for [`String`].
 trait StockStringMods: AsRef<str> + Sized {
+    /// Substitutes all occurrences of the placeholder for the given `position`,
+    /// eg. `%1$s`, `%1$d` and `%1$@` for position 1.
+    ///
+    /// Translations may use a placeholder several times or in a different order
+    /// than the default string, so every occurrence is replaced.
+    fn replace_all_positional(&self, position: usize, replacement: impl AsRef<str>) -> String {
+        let replacement = replacement.as_ref();
+        self.as_ref()
+            .replace(&format!("%{}$s", position), replacement)
+            .replace(&format!("%{}$d", position), replacement)
+            .replace(&format!("%{}$@", position), replacement)
+    }
+
     /// Substitutes the first replacement value if one is present.
     fn replace1(&self, replacement: impl AsRef<str>) -> String {
-        self.as_ref()
-            .replacen("%1$s", replacement.as_ref(), 1)
-            .replacen("%1$d", replacement.as_ref(), 1)
-            .replacen("%1$@", replacement.as_ref(), 1)
+        self.replace_all_positional(1, replacement)
     }
 
     /// Substitutes the second replacement value if one is present.
@@ -369,10 +426,7 @@ fn replace1(&self, replacement: impl AsRef<str>) -> String {
     /// Be aware you probably should have also called [`StockStringMods::replace1`] if
     /// you are calling this.
     fn replace2(&self, replacement: impl AsRef<str>) -> String {
-        self.as_ref()
-            .replacen("%2$s", replacement.as_ref(), 1)
-            .replacen("%2$d", replacement.as_ref(), 1)
-            .replacen("%2$@", replacement.as_ref(), 1)
+        self.replace_all_positional(2, replacement)
     }
 
     /// Augments the message by saying it was performed by a user.
@@ -498,6 +552,60 @@ pub(crate) async fn msg_add_member(
         .await
 }
 
+/// Stock string: `Members %1$s added.`.
+///
+/// The `added_member_addrs` are looked up in the contacts and listed by their display names
+/// as `Alice, Bob and Carol` or, for more than three members, as `Alice, Bob and 3 others`.
+/// For a single member, this is the same as [`msg_add_member`].
+pub(crate) async fn msg_add_members(
+    context: &Context,
+    added_member_addrs: &[String],
+    by_contact: ContactId,
+) -> String {
+    if let [addr] = added_member_addrs {
+        return msg_add_member(context, addr, by_contact).await;
+    }
+
+    const MAX_LISTED: usize = 3;
+    let listed = if added_member_addrs.len() > MAX_LISTED {
+        MAX_LISTED - 1
+    } else {
+        added_member_addrs.len()
+    };
+    let mut names = Vec::new();
+    for addr in added_member_addrs.iter().take(listed) {
+        let name = match Contact::lookup_id_by_addr(context, addr, Origin::Unknown).await {
+            Ok(Some(contact_id)) => Contact::get_by_id(context, contact_id)
+                .await
+                .map(|contact| contact.get_display_name().to_string())
+                .unwrap_or_else(|_| addr.to_string()),
+            _ => addr.to_string(),
+        };
+        names.push(name);
+    }
+    if added_member_addrs.len() > listed {
+        names.push(
+            translated(context, StockMessage::ListOthers)
+                .await
+                .replace1((added_member_addrs.len() - listed).to_string()),
+        );
+    }
+
+    let who = match names.pop() {
+        Some(last) if !names.is_empty() => translated(context, StockMessage::ListAnd)
+            .await
+            .replace1(names.join(", "))
+            .replace2(last),
+        Some(last) => last,
+        None => String::new(),
+    };
+    translated(context, StockMessage::MsgAddMembers)
+        .await
+        .replace1(who)
+        .action_by_contact(context, by_contact)
+        .await
+}
+
 /// Stock string: `Member %1$s removed.`.
 ///
 /// The `removed_member_addr` parameter should be an email address and is looked up in
@@ -732,6 +840,13 @@ pub(crate) async fn msg_location_disabled(context: &Context) -> String {
     translated(context, StockMessage::MsgLocationDisabled).await
 }
 
+/// Stock string: `Location streaming extended until %1$s.`.
+pub(crate) async fn msg_location_extended(context: &Context, timestamp: i64) -> String {
+    translated(context, StockMessage::MsgLocationExtended)
+        .await
+        .replace1(dc_timestamp_to_str(timestamp))
+}
+
 /// Stock string: `Location`.
 pub(crate) async fn location(context: &Context) -> String {
     translated(context, StockMessage::Location).await
@@ -1076,6 +1191,20 @@ pub(crate) async fn broadcast_list(context: &Context) -> String {
     translated(context, StockMessage::BroadcastList).await
 }
 
+/// Stock string: `New message`.
+/// Used as notification text if the notification preview is disabled.
+pub(crate) async fn new_message(context: &Context) -> String {
+    translated(context, StockMessage::NewMessage).await
+}
+
+/// Stock string: `Message from %1$s`.
+/// Used as notification text if only the sender is shown in notifications.
+pub(crate) async fn message_from(context: &Context, sender: impl AsRef<str>) -> String {
+    translated(context, StockMessage::MessageFrom)
+        .await
+        .replace1(sender)
+}
+
 impl Context {
     /// Set the stock string for the [StockMessage].
     ///
@@ -1084,20 +1213,7 @@ pub async fn set_stock_translation(
         id: StockMessage,
         stockstring: String,
     ) -> Result<(), Error> {
-        if stockstring.contains("%1") && !id.fallback().contains("%1") {
-            bail!(
-                "translation {} contains invalid %1 placeholder, default is {}",
-                stockstring,
-                id.fallback()
-            );
-        }
-        if stockstring.contains("%2") && !id.fallback().contains("%2") {
-            bail!(
-                "translation {} contains invalid %2 placeholder, default is {}",
-                stockstring,
-                id.fallback()
-            );
-        }
+        check_placeholders(id, &stockstring)?;
         self.translated_stockstrings
             .write()
             .await
@@ -1105,6 +1221,58 @@ pub async fn set_stock_translation(
         Ok(())
     }
 
+    /// Sets several stock strings at once, see [`Context::set_stock_translation`].
+    ///
+    /// All entries are checked before any of them is set,
+    /// so if one entry is invalid, no stock string is changed.
+    pub async fn set_stock_translations(
+        &self,
+        entries: Vec<(StockMessage, String)>,
+    ) -> Result<(), Error> {
+        for (id, stockstring) in entries.iter() {
+            check_placeholders(*id, stockstring)?;
+        }
+        let mut translated_stockstrings = self.translated_stockstrings.write().await;
+        for (id, stockstring) in entries {
+            translated_stockstrings.insert(id as usize, stockstring);
+        }
+        Ok(())
+    }
+
+    /// Sets the translations used for stock strings
+    /// that are not set by [`Context::set_stock_translation`],
+    /// eg. `pt` translations for a `pt-BR` account.
+    ///
+    /// The map is keyed by [`StockMessage`] ids and replaces a previously set fallback map.
+    /// Strings missing in both maps use the built-in English default.
+    pub async fn set_stock_translation_fallback_map(
+        &self,
+        map: HashMap<usize, String>,
+    ) -> Result<(), Error> {
+        for (id, stockstring) in map.iter() {
+            let id = match StockMessage::from_usize(*id) {
+                Some(id) => id,
+                None => bail!("invalid stock string id {}", id),
+            };
+            check_placeholders(id, stockstring)?;
+        }
+        *self.fallback_stockstrings.write().await = map;
+        Ok(())
+    }
+
+    /// Returns a snapshot of the translations set by [`Context::set_stock_translation`],
+    /// keyed by [`StockMessage`] ids.
+    ///
+    /// Stock strings not contained in the map use the fallback translation or the built-in default.
+    pub async fn get_stock_translations(&self) -> HashMap<u32, String> {
+        self.translated_stockstrings
+            .read()
+            .await
+            .iter()
+            .map(|(id, stockstring)| (*id as u32, stockstring.clone()))
+            .collect()
+    }
+
     /// Returns a stock message saying that protection status has changed.
     pub(crate) async fn stock_protection_msg(
         &self,
@@ -1176,6 +1344,98 @@ async fn test_set_stock_translation() {
         assert_eq!(no_messages(&t).await, "xyz")
     }
 
+    #[async_std::test]
+    async fn test_set_stock_translations() -> anyhow::Result<()> {
+        let t = TestContext::new().await;
+        t.set_stock_translations(vec![
+            (StockMessage::NoMessages, "xyz".to_string()),
+            (StockMessage::SelfMsg, "me".to_string()),
+        ])
+        .await?;
+        assert_eq!(no_messages(&t).await, "xyz");
+        assert_eq!(self_msg(&t).await, "me");
+
+        // A single invalid entry rejects the whole batch.
+        assert!(t
+            .set_stock_translations(vec![
+                (StockMessage::NoMessages, "abc".to_string()),
+                (StockMessage::Draft, "draft %1$s".to_string()),
+            ])
+            .await
+            .is_err());
+        assert_eq!(no_messages(&t).await, "xyz");
+        assert_eq!(draft(&t).await, "Draft");
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn test_get_stock_translations() -> anyhow::Result<()> {
+        let t = TestContext::new().await;
+        assert!(t.get_stock_translations().await.is_empty());
+
+        t.set_stock_translation(StockMessage::NoMessages, "xyz".to_string())
+            .await?;
+        t.set_stock_translation(StockMessage::SelfMsg, "me".to_string())
+            .await?;
+        t.set_stock_translation(StockMessage::NoMessages, "abc".to_string())
+            .await?;
+        let translations = t.get_stock_translations().await;
+        assert_eq!(translations.len(), 2);
+        assert_eq!(
+            translations.get(&(StockMessage::NoMessages as u32)),
+            Some(&"abc".to_string())
+        );
+        assert_eq!(
+            translations.get(&(StockMessage::SelfMsg as u32)),
+            Some(&"me".to_string())
+        );
+
+        // Fallback translations are not included.
+        t.set_stock_translation_fallback_map(
+            [(StockMessage::Draft as usize, "fallback".to_string())]
+                .into_iter()
+                .collect(),
+        )
+        .await?;
+        assert_eq!(t.get_stock_translations().await.len(), 2);
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn test_set_stock_translation_fallback_map() -> anyhow::Result<()> {
+        let t = TestContext::new().await;
+        t.set_stock_translation(StockMessage::NoMessages, "primary".to_string())
+            .await?;
+        t.set_stock_translation_fallback_map(
+            [
+                (StockMessage::NoMessages as usize, "secondary".to_string()),
+                (StockMessage::SelfMsg as usize, "secondary me".to_string()),
+            ]
+            .into_iter()
+            .collect(),
+        )
+        .await?;
+        assert_eq!(no_messages(&t).await, "primary");
+        assert_eq!(self_msg(&t).await, "secondary me");
+        assert_eq!(draft(&t).await, "Draft");
+
+        assert!(t
+            .set_stock_translation_fallback_map(
+                [(StockMessage::NoMessages as usize, "xyz %1$s".to_string())]
+                    .into_iter()
+                    .collect()
+            )
+            .await
+            .is_err());
+        assert!(t
+            .set_stock_translation_fallback_map(
+                [(usize::MAX, "xyz".to_string())].into_iter().collect()
+            )
+            .await
+            .is_err());
+        Ok(())
+    }
+
     #[async_std::test]
     async fn test_set_stock_translation_wrong_replacements() {
         let t = TestContext::new().await;
@@ -1265,6 +1525,70 @@ async fn test_stock_system_msg_add_member_by_other_with_displayname() {
         );
     }
 
+    #[test]
+    fn test_replace_all_positional() {
+        assert_eq!(
+            "%1$s added %1$s".to_string().replace1("Alice"),
+            "Alice added Alice"
+        );
+        assert_eq!(
+            "%2$s, then %1$s"
+                .to_string()
+                .replace1("one")
+                .replace2("two"),
+            "two, then one"
+        );
+        assert_eq!(
+            "%1$d%% of %2$@, %1$d%%"
+                .to_string()
+                .replace_all_positional(1, "80")
+                .replace_all_positional(2, "1 GB"),
+            "80%% of 1 GB, 80%%"
+        );
+        assert_eq!(
+            "%1$s and %2$s".to_string().replace_all_positional(3, "x"),
+            "%1$s and %2$s"
+        );
+    }
+
+    #[async_std::test]
+    async fn test_stock_system_msg_add_members() -> anyhow::Result<()> {
+        let t = TestContext::new().await;
+        Contact::create(&t, "Alice", "alice@example.org").await?;
+        Contact::create(&t, "Bob", "bob@example.net").await?;
+        let addrs = |n| -> Vec<String> {
+            [
+                "alice@example.org",
+                "bob@example.net",
+                "claire@example.org",
+                "dom@example.org",
+                "elena@example.org",
+            ]
+            .iter()
+            .take(n)
+            .map(|addr| addr.to_string())
+            .collect()
+        };
+
+        assert_eq!(
+            msg_add_members(&t, &addrs(1), ContactId::SELF).await,
+            "Member Alice (alice@example.org) added by me."
+        );
+        assert_eq!(
+            msg_add_members(&t, &addrs(2), ContactId::SELF).await,
+            "Members Alice and Bob added by me."
+        );
+        assert_eq!(
+            msg_add_members(&t, &addrs(3), ContactId::SELF).await,
+            "Members Alice, Bob and claire@example.org added by me."
+        );
+        assert_eq!(
+            msg_add_members(&t, &addrs(5), ContactId::SELF).await,
+            "Members Alice, Bob and 3 others added by me."
+        );
+        Ok(())
+    }
+
     #[async_std::test]
     async fn test_quota_exceeding_stock_str() -> anyhow::Result<()> {
         let t = TestContext::new().await;
@@ -102,15 +102,41 @@ pub fn truncated_text(&self, approx_chars: usize) -> Cow<str> {
 }
 
 impl Message {
-    /// Returns a summary text.
-    async fn get_summary_text(&self, context: &Context) -> String {
-        let mut append_text = true;
-        let prefix = match self.viewtype {
+    /// Returns the translated noun describing the message's viewtype,
+    /// such as "Image" or "Voice message".
+    ///
+    /// Location-only messages are labeled as "Location".
+    /// For text messages and other viewtypes without a noun an empty string is returned.
+    pub async fn viewtype_label(&self, context: &Context) -> String {
+        match self.viewtype {
             Viewtype::Image => stock_str::image(context).await,
             Viewtype::Gif => stock_str::gif(context).await,
             Viewtype::Sticker => stock_str::sticker(context).await,
             Viewtype::Video => stock_str::video(context).await,
             Viewtype::Voice => stock_str::voice_message(context).await,
+            Viewtype::Audio => stock_str::audio(context).await,
+            Viewtype::File => stock_str::file(context).await,
+            Viewtype::VideochatInvitation => stock_str::videochat_invitation(context).await,
+            Viewtype::Text | Viewtype::Unknown => {
+                if self.param.get_cmd() == SystemMessage::LocationOnly {
+                    stock_str::location(context).await
+                } else {
+                    "".to_string()
+                }
+            }
+            Viewtype::Webxdc => "".to_string(),
+        }
+    }
+
+    /// Returns a summary text.
+    async fn get_summary_text(&self, context: &Context) -> String {
+        let mut append_text = true;
+        let prefix = match self.viewtype {
+            Viewtype::Image
+            | Viewtype::Gif
+            | Viewtype::Sticker
+            | Viewtype::Video
+            | Viewtype::Voice => self.viewtype_label(context).await,
             Viewtype::Audio | Viewtype::File => {
                 if self.param.get_cmd() == SystemMessage::AutocryptSetupMessage {
                     append_text = false;
@@ -125,17 +151,12 @@ async fn get_summary_text(&self, context: &Context) -> String {
                                 .map(|fname| fname.to_string_lossy().into_owned())
                         })
                         .unwrap_or_else(|| String::from("ErrFileName"));
-                    let label = if self.viewtype == Viewtype::Audio {
-                        stock_str::audio(context).await
-                    } else {
-                        stock_str::file(context).await
-                    };
-                    format!("{} – {}", label, file_name)
+                    format!("{} – {}", self.viewtype_label(context).await, file_name)
                 }
             }
             Viewtype::VideochatInvitation => {
                 append_text = false;
-                stock_str::videochat_invitation(context).await
+                self.viewtype_label(context).await
             }
             Viewtype::Webxdc => {
                 append_text = true;
@@ -145,12 +166,10 @@ async fn get_summary_text(&self, context: &Context) -> String {
                     .unwrap_or_else(|_| "ErrWebxdcName".to_string())
             }
             Viewtype::Text | Viewtype::Unknown => {
-                if self.param.get_cmd() != SystemMessage::LocationOnly {
-                    "".to_string()
-                } else {
+                if self.param.get_cmd() == SystemMessage::LocationOnly {
                     append_text = false;
-                    stock_str::location(context).await
                 }
+                self.viewtype_label(context).await
             }
         };
 
@@ -189,6 +208,30 @@ mod tests {
     use super::*;
     use crate::test_utils as test;
 
+    #[async_std::test]
+    async fn test_viewtype_label() {
+        let t = test::TestContext::new().await;
+        for (viewtype, label) in [
+            (Viewtype::Text, ""),
+            (Viewtype::Image, "Image"),
+            (Viewtype::Gif, "GIF"),
+            (Viewtype::Sticker, "Sticker"),
+            (Viewtype::Video, "Video"),
+            (Viewtype::Voice, "Voice message"),
+            (Viewtype::Audio, "Audio"),
+            (Viewtype::File, "File"),
+            (Viewtype::VideochatInvitation, "Video chat invitation"),
+            (Viewtype::Webxdc, ""),
+        ] {
+            let msg = Message::new(viewtype);
+            assert_eq!(msg.viewtype_label(&t).await, label, "{:?}", viewtype);
+        }
+
+        let mut msg = Message::new(Viewtype::Text);
+        msg.param.set_cmd(SystemMessage::LocationOnly);
+        assert_eq!(msg.viewtype_label(&t).await, "Location");
+    }
+
     #[async_std::test]
     async fn test_get_summary_text() {
         let d = test::TestContext::new().await;
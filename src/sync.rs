@@ -9,13 +9,14 @@
 use crate::message::{Message, MsgId, Viewtype};
 use crate::mimeparser::SystemMessage;
 use crate::param::Param;
-use crate::sync::SyncData::{AddQrToken, DeleteQrToken};
+use crate::sync::SyncData::{AddQrToken, AlterConfig, DeleteQrToken};
 use crate::token::Namespace;
 use crate::{chat, stock_str, token};
 use anyhow::Result;
 use lettre_email::mime::{self};
 use lettre_email::PartBuilder;
 use serde::{Deserialize, Serialize};
+use std::str::FromStr;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub(crate) struct QrTokenData {
@@ -28,6 +29,7 @@ pub(crate) struct QrTokenData {
 pub(crate) enum SyncData {
     AddQrToken(QrTokenData),
     DeleteQrToken(QrTokenData),
+    AlterConfig { key: String, val: Option<String> },
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -41,6 +43,20 @@ pub(crate) struct SyncItems {
     items: Vec<SyncItem>,
 }
 
+/// Status of the synchronization with other devices, see [`Context::get_sync_status`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyncStatus {
+    /// Timestamp of the last sync message sent, 0 if none was sent yet.
+    pub last_sent: i64,
+
+    /// Timestamp of the last sync message received from another device,
+    /// 0 if none was received yet.
+    pub last_received: i64,
+
+    /// Number of items waiting to be sent with the next sync message.
+    pub pending_items: usize,
+}
+
 impl Context {
     /// Checks if sync messages shall be sent.
     /// Receiving sync messages is currently always enabled;
@@ -123,8 +139,22 @@ pub(crate) async fn sync_qr_code_token_deletion(
         .await
     }
 
-    /// Sends out a self-sent message with items to be synchronized, if any.
+    /// Sends out self-sent messages with items to be synchronized, if any.
+    /// Returns the ID of the last message sent.
     pub async fn send_sync_msg(&self) -> Result<Option<MsgId>> {
+        // Config items and other items are sent in separate messages,
+        // so there are at most two messages, see `build_sync_json()`.
+        let mut last_msg_id = None;
+        for _ in 0..2 {
+            match self.send_sync_msg_once().await? {
+                Some(msg_id) => last_msg_id = Some(msg_id),
+                None => break,
+            }
+        }
+        Ok(last_msg_id)
+    }
+
+    async fn send_sync_msg_once(&self) -> Result<Option<MsgId>> {
         if let Some((json, ids)) = self.build_sync_json().await? {
             let chat_id =
                 ChatId::create_for_contact_with_blocked(self, ContactId::SELF, Blocked::Yes)
@@ -141,14 +171,24 @@ pub async fn send_sync_msg(&self) -> Result<Option<MsgId>> {
             msg.param.set(Param::Arg, json);
             msg.param.set(Param::Arg2, ids);
             msg.param.set_int(Param::GuaranteeE2ee, 1);
-            Ok(Some(chat::send_msg(self, chat_id, &mut msg).await?))
+            let msg_id = chat::send_msg(self, chat_id, &mut msg).await?;
+            self.sql
+                .set_raw_config_int64("last_sync_msg_sent", time())
+                .await?;
+            Ok(Some(msg_id))
         } else {
             Ok(None)
         }
     }
 
-    /// Copies all sync items to a JSON string and clears the sync-table.
+    /// Copies sync items to a JSON string.
     /// Returns the JSON string and a comma-separated string of the IDs used.
+    ///
+    /// Items altering the config are never mixed with other items:
+    /// older versions fail to parse a message containing an unknown item type
+    /// and would drop the QR-code tokens sent along.
+    /// The kind of the oldest item decides which items are returned,
+    /// the remaining ones are returned once the returned IDs are deleted.
     pub(crate) async fn build_sync_json(&self) -> Result<Option<(String, String)>> {
         let (ids, serialized) = self
             .sql
@@ -159,8 +199,19 @@ pub(crate) async fn build_sync_json(&self) -> Result<Option<(String, String)>> {
                 |rows| {
                     let mut ids = vec![];
                     let mut serialized = String::default();
+                    let mut config_items = None;
                     for row in rows {
                         let (id, item) = row?;
+                        let is_config = matches!(
+                            serde_json::from_str::<SyncItem>(&item),
+                            Ok(SyncItem {
+                                data: AlterConfig { .. },
+                                ..
+                            })
+                        );
+                        if *config_items.get_or_insert(is_config) != is_config {
+                            continue;
+                        }
                         ids.push(id);
                         if !serialized.is_empty() {
                             serialized.push_str(",\n");
@@ -252,10 +303,71 @@ pub(crate) async fn execute_sync_items(&self, items: &SyncItems) -> Result<()> {
                     token::delete(self, Namespace::InviteNumber, &token.invitenumber).await?;
                     token::delete(self, Namespace::Auth, &token.auth).await?;
                 }
+                AlterConfig { key, val } => match Config::from_str(key) {
+                    Ok(key)
+                        if key.is_synced()
+                            && !self.get_sync_excluded_keys().await?.contains(&key) =>
+                    {
+                        // Do not sync the change back, see CAVE above.
+                        if let Err(err) = self.set_config_ex(key, val.as_deref(), false).await {
+                            warn!(self, "Cannot apply synced config {}: {}", key, err);
+                        }
+                    }
+                    _ => warn!(self, "Ignoring sync item for config key {:?}.", key),
+                },
             }
         }
+        self.sql
+            .set_raw_config_int64("last_sync_msg_received", time())
+            .await?;
         Ok(())
     }
+
+    /// Returns the status of the synchronization with other devices,
+    /// eg. to show the time of the last synchronization.
+    pub async fn get_sync_status(&self) -> Result<SyncStatus> {
+        Ok(SyncStatus {
+            last_sent: self
+                .sql
+                .get_raw_config_int64("last_sync_msg_sent")
+                .await?
+                .unwrap_or_default(),
+            last_received: self
+                .sql
+                .get_raw_config_int64("last_sync_msg_received")
+                .await?
+                .unwrap_or_default(),
+            pending_items: self
+                .sql
+                .count("SELECT COUNT(*) FROM multi_device_sync;", paramsv![])
+                .await?,
+        })
+    }
+
+    /// Returns true if there are changes that did not yet reach the other devices
+    /// via the self-chat, ie. sync items or self-chat messages that are not yet sent.
+    ///
+    /// UIs may use this to ask the user to open Delta Chat on the other devices.
+    pub async fn self_chat_pending_sync(&self) -> Result<bool> {
+        if self
+            .sql
+            .exists("SELECT COUNT(*) FROM multi_device_sync;", paramsv![])
+            .await?
+        {
+            return Ok(true);
+        }
+        match ChatId::lookup_by_contact(self, ContactId::SELF).await? {
+            Some(chat_id) => {
+                self.sql
+                    .exists(
+                        "SELECT COUNT(*) FROM smtp s INNER JOIN msgs m ON s.msg_id=m.id WHERE m.chat_id=?;",
+                        paramsv![chat_id],
+                    )
+                    .await
+            }
+            None => Ok(false),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -323,6 +435,44 @@ async fn test_build_sync_json() -> Result<()> {
         Ok(())
     }
 
+    #[async_std::test]
+    async fn test_build_sync_json_config_separately() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        t.set_config_bool(Config::SendSyncMsgs, true).await?;
+
+        t.add_sync_item(SyncData::AddQrToken(QrTokenData {
+            invitenumber: "testinvite".to_string(),
+            auth: "testauth".to_string(),
+            grpid: None,
+        }))
+        .await?;
+        t.add_sync_item(SyncData::AlterConfig {
+            key: "selfstatus".to_string(),
+            val: Some("busy".to_string()),
+        })
+        .await?;
+        t.add_sync_item(SyncData::DeleteQrToken(QrTokenData {
+            invitenumber: "in".to_string(),
+            auth: "auth".to_string(),
+            grpid: None,
+        }))
+        .await?;
+
+        // Older versions must be able to parse the message with the tokens.
+        let (serialized, ids) = t.build_sync_json().await?.unwrap();
+        assert!(!serialized.contains("AlterConfig"));
+        assert_eq!(t.parse_sync_items(serialized)?.items.len(), 2);
+        t.delete_sync_ids(ids).await?;
+
+        let (serialized, ids) = t.build_sync_json().await?.unwrap();
+        assert!(serialized.contains("AlterConfig"));
+        assert_eq!(t.parse_sync_items(serialized)?.items.len(), 1);
+        t.delete_sync_ids(ids).await?;
+        assert!(t.build_sync_json().await?.is_none());
+
+        Ok(())
+    }
+
     #[async_std::test]
     async fn test_build_sync_json_sync_msgs_off() -> Result<()> {
         let t = TestContext::new_alice().await;
@@ -492,4 +642,128 @@ async fn test_send_sync_msg() -> Result<()> {
 
         Ok(())
     }
+
+    #[async_std::test]
+    async fn test_send_sync_msg_config_separately() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+        alice.set_config_bool(Config::SendSyncMsgs, true).await?;
+        alice
+            .add_sync_item(SyncData::AddQrToken(QrTokenData {
+                invitenumber: "in".to_string(),
+                auth: "testtoken".to_string(),
+                grpid: None,
+            }))
+            .await?;
+        alice.set_config(Config::Selfstatus, Some("busy")).await?;
+        alice.send_sync_msg().await?.unwrap();
+        assert!(alice.build_sync_json().await?.is_none());
+
+        let alice2 = TestContext::new_alice().await;
+        alice2.recv_msg(&alice.pop_sent_msg().await).await;
+        alice2.recv_msg(&alice.pop_sent_msg().await).await;
+        assert!(token::exists(&alice2, token::Namespace::Auth, "testtoken").await);
+        assert_eq!(
+            alice2.get_config(Config::Selfstatus).await?,
+            Some("busy".to_string())
+        );
+
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn test_get_sync_status() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+        alice.set_config_bool(Config::SendSyncMsgs, true).await?;
+        assert_eq!(
+            alice.get_sync_status().await?,
+            SyncStatus {
+                last_sent: 0,
+                last_received: 0,
+                pending_items: 0
+            }
+        );
+
+        alice
+            .add_sync_item(SyncData::AddQrToken(QrTokenData {
+                invitenumber: "in".to_string(),
+                auth: "testtoken".to_string(),
+                grpid: None,
+            }))
+            .await?;
+        assert_eq!(alice.get_sync_status().await?.pending_items, 1);
+
+        let before = time();
+        alice.send_sync_msg().await?.unwrap();
+        let status = alice.get_sync_status().await?;
+        assert!(status.last_sent >= before);
+        assert_eq!(status.last_received, 0);
+        assert_eq!(status.pending_items, 0);
+
+        let alice2 = TestContext::new_alice().await;
+        assert_eq!(alice2.get_sync_status().await?.last_received, 0);
+        alice2.recv_msg(&alice.pop_sent_msg().await).await;
+        let status = alice2.get_sync_status().await?;
+        assert!(status.last_received >= before);
+        assert_eq!(status.last_sent, 0);
+
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn test_self_chat_pending_sync() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+        alice.set_config_bool(Config::SendSyncMsgs, true).await?;
+        assert!(!alice.self_chat_pending_sync().await?);
+
+        alice
+            .add_sync_item(SyncData::AddQrToken(QrTokenData {
+                invitenumber: "in".to_string(),
+                auth: "testtoken".to_string(),
+                grpid: None,
+            }))
+            .await?;
+        assert!(alice.self_chat_pending_sync().await?);
+
+        // The sync message is pending until it is sent.
+        alice.send_sync_msg().await?.unwrap();
+        assert!(alice.self_chat_pending_sync().await?);
+        alice.sql.execute("DELETE FROM smtp;", paramsv![]).await?;
+        assert!(!alice.self_chat_pending_sync().await?);
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn test_sync_excluded_keys() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+        alice.set_config_bool(Config::SendSyncMsgs, true).await?;
+        assert!(alice
+            .set_config(Config::SyncExcludedKeys, Some("displayname,foobar"))
+            .await
+            .is_err());
+        alice
+            .set_config(Config::SyncExcludedKeys, Some("displayname, media_quality"))
+            .await?;
+
+        alice
+            .set_config(Config::Displayname, Some("Alice Excluded"))
+            .await?;
+        alice.set_config(Config::Selfstatus, Some("busy")).await?;
+        let (json, _) = alice.build_sync_json().await?.unwrap();
+        assert!(!json.contains("displayname"));
+        assert!(json.contains(r#""AlterConfig":{"key":"selfstatus","val":"busy"}"#));
+
+        let alice2 = TestContext::new_alice().await;
+        alice2.set_config_bool(Config::SendSyncMsgs, true).await?;
+        alice.send_sync_msg().await?.unwrap();
+        alice2.recv_msg(&alice.pop_sent_msg().await).await;
+        assert_eq!(
+            alice2.get_config(Config::Selfstatus).await?,
+            Some("busy".to_string())
+        );
+        assert_eq!(alice2.get_config(Config::Displayname).await?, None);
+
+        // Applying a synced change must not queue it again.
+        assert!(alice2.build_sync_json().await?.is_none());
+        Ok(())
+    }
 }
@@ -4,10 +4,14 @@
 //!
 //! Tokens are used in countermitm verification protocols.
 
+use std::convert::TryFrom;
+use std::time::Duration;
+
 use anyhow::Result;
 use deltachat_derive::{FromSql, ToSql};
 
 use crate::chat::ChatId;
+use crate::config::Config;
 use crate::context::Context;
 use crate::dc_tools::{dc_create_id, time};
 
@@ -57,6 +61,46 @@ pub async fn save(
     Ok(())
 }
 
+/// Creates and saves a new token that expires at the unix timestamp `expires`.
+///
+/// Expiring tokens are never returned by lookup(),
+/// so they are not reused for other QR codes.
+pub async fn new_expiring(
+    context: &Context,
+    namespace: Namespace,
+    foreign_id: Option<ChatId>,
+    expires: i64,
+) -> Result<String> {
+    let token = dc_create_id();
+    context
+        .sql
+        .execute(
+            "INSERT INTO tokens (namespc, foreign_id, token, timestamp, expires) VALUES (?, ?, ?, ?, ?);",
+            paramsv![
+                namespace,
+                foreign_id.unwrap_or_default(),
+                token,
+                time(),
+                expires
+            ],
+        )
+        .await?;
+    Ok(token)
+}
+
+/// Deletes tokens created by new_expiring() that are expired.
+///
+/// Returns the number of deleted tokens.
+pub(crate) async fn delete_expired(context: &Context) -> Result<usize> {
+    context
+        .sql
+        .execute(
+            "DELETE FROM tokens WHERE expires!=0 AND expires<?;",
+            paramsv![time()],
+        )
+        .await
+}
+
 /// Lookup most recently created token for a namespace/chat combination.
 ///
 /// As there may be more than one valid token for a chat-id,
@@ -69,14 +113,24 @@ pub async fn lookup(
     context: &Context,
     namespace: Namespace,
     chat: Option<ChatId>,
+) -> Result<Option<String>> {
+    lookup_since(context, namespace, chat, 0).await
+}
+
+/// Like lookup(), but ignores tokens created before `min_timestamp`.
+async fn lookup_since(
+    context: &Context,
+    namespace: Namespace,
+    chat: Option<ChatId>,
+    min_timestamp: i64,
 ) -> Result<Option<String>> {
     let token = match chat {
         Some(chat_id) => {
             context
                 .sql
                 .query_get_value(
-                    "SELECT token FROM tokens WHERE namespc=? AND foreign_id=? ORDER BY timestamp DESC LIMIT 1;",
-                    paramsv![namespace, chat_id],
+                    "SELECT token FROM tokens WHERE namespc=? AND foreign_id=? AND expires=0 AND timestamp>=? ORDER BY timestamp DESC LIMIT 1;",
+                    paramsv![namespace, chat_id, min_timestamp],
                 )
                 .await?
         }
@@ -85,8 +139,8 @@ pub async fn lookup(
             context
                 .sql
                 .query_get_value(
-                    "SELECT token FROM tokens WHERE namespc=? AND foreign_id=0 ORDER BY timestamp DESC LIMIT 1;",
-                    paramsv![namespace],
+                    "SELECT token FROM tokens WHERE namespc=? AND foreign_id=0 AND expires=0 AND timestamp>=? ORDER BY timestamp DESC LIMIT 1;",
+                    paramsv![namespace, min_timestamp],
                 )
                 .await?
         }
@@ -108,6 +162,35 @@ pub async fn lookup_or_new(
     token
 }
 
+/// Returns [`Config::QrTokenTtl`], zero if tokens never expire.
+pub(crate) async fn qr_token_ttl(context: &Context) -> Result<Duration> {
+    Ok(Duration::from_secs(
+        context.get_config_u64(Config::QrTokenTtl).await?,
+    ))
+}
+
+/// Returns the timestamp tokens created before are older than `ttl`.
+fn ttl_min_timestamp(now: i64, ttl: Duration) -> i64 {
+    now.saturating_sub(i64::try_from(ttl.as_secs()).unwrap_or(i64::MAX))
+}
+
+/// Like lookup_or_new(), but creates a new token if the most recent one is older than `ttl`.
+pub async fn lookup_or_new_with_ttl(
+    context: &Context,
+    namespace: Namespace,
+    foreign_id: Option<ChatId>,
+    ttl: Duration,
+) -> String {
+    let min_timestamp = ttl_min_timestamp(time(), ttl);
+    if let Ok(Some(token)) = lookup_since(context, namespace, foreign_id, min_timestamp).await {
+        return token;
+    }
+
+    let token = dc_create_id();
+    save(context, namespace, foreign_id, &token).await.ok();
+    token
+}
+
 pub async fn exists(context: &Context, namespace: Namespace, token: &str) -> bool {
     context
         .sql
@@ -119,6 +202,26 @@ pub async fn exists(context: &Context, namespace: Namespace, token: &str) -> boo
         .unwrap_or_default()
 }
 
+/// Returns true if the token has an expiry and the expiry is reached
+/// or if the token is older than [`Config::QrTokenTtl`].
+pub async fn is_expired(context: &Context, namespace: Namespace, token: &str) -> Result<bool> {
+    let now = time();
+    let ttl = qr_token_ttl(context).await?;
+    let min_timestamp = if ttl.as_secs() > 0 {
+        ttl_min_timestamp(now, ttl)
+    } else {
+        0
+    };
+    context
+        .sql
+        .exists(
+            "SELECT COUNT(*) FROM tokens WHERE namespc=? AND token=?
+             AND ((expires!=0 AND expires<=?) OR (expires=0 AND timestamp<?));",
+            paramsv![namespace, token, now, min_timestamp],
+        )
+        .await
+}
+
 pub async fn delete(context: &Context, namespace: Namespace, token: &str) -> Result<()> {
     context
         .sql